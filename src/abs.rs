@@ -1,5 +1,6 @@
 /// make all names in a module absolute
 use super::ast;
+use super::lint;
 use super::loader;
 use super::makro;
 use super::name::Name;
@@ -45,6 +46,7 @@ struct InScope {
     loc: ast::Location,
     is_module: bool,
     subtypes: bool,
+    used: std::cell::Cell<bool>,
 }
 
 #[derive(Default)]
@@ -68,6 +70,7 @@ impl Scope {
     fn get(&self, n: &str) -> Option<&InScope> {
         for scope in self.v.iter().rev() {
             if let Some(v) = scope.get(n) {
+                v.used.set(true);
                 return Some(v);
             }
         }
@@ -85,13 +88,14 @@ impl Scope {
         if let Some(previous) = self.cur().get(&local) {
             if !is_module || !previous.is_module || fqn != previous.name {
                 emit_error(
+                    "E0213",
                     format!("conflicting local name '{}'", local),
                     &[
                         (loc.clone(), "declared here"),
                         (previous.loc.clone(), "also declared here"),
                     ],
                 );
-                std::process::exit(9);
+                super::exit(9);
             }
         }
         trace!("  insert {} := {}", local, fqn);
@@ -102,6 +106,7 @@ impl Scope {
                 loc: loc.clone(),
                 is_module,
                 subtypes,
+                used: std::cell::Cell::new(false),
             },
         );
     }
@@ -244,6 +249,10 @@ impl Scope {
             "static"| "len"| "nullterm"| "typeof"| "tailof" => {
                 return;
             }
+            // unless C's are imported from math.h
+            "isnan" | "isinf" if self.get(&name.0[0]).is_none() => {
+                return;
+            }
             _ => (),
         };
 
@@ -255,6 +264,7 @@ impl Scope {
                 if inbody {
                     if name.len() > 1 {
                         emit_error(
+                            "E0202",
                             format!("possibly undefined name '{}'", lhs),
                             &[(
                                 t.loc.clone(),
@@ -265,6 +275,7 @@ impl Scope {
                     }
                 } else {
                     emit_error(
+                        "E0201",
                         format!("undefined name '{}'", lhs),
                         &[(t.loc.clone(), "used in this scope")],
                     );
@@ -274,6 +285,7 @@ impl Scope {
             Some(v) => {
                 if rhs.len() != 0 && !v.subtypes {
                     emit_error(
+                        "E0204",
                         format!("resolving '{}' as member is not possible", name),
                         &[(t.loc.clone(), format!("'{}' is not a module", lhs))],
                     );
@@ -282,7 +294,7 @@ impl Scope {
 
                 /*
                 if rhs.len() != 0 && v.name.0[1] == "ext" {
-                    emit_error("'{}' cannot be used as qualified name\n{}\n{}",
+                    emit_error("E0205", "'{}' cannot be used as qualified name\n{}\n{}",
                            v.name,
                            (t.loc, format!("'{}' is a c header", lhs)),
                            (v.loc, format!("suggestion: add '{}' to this import", rhs.join("::")))
@@ -293,6 +305,7 @@ impl Scope {
 
                 if rhs.len() == 0 && v.is_module {
                     emit_error(
+                        "E0206",
                         format!("cannot use module '{}' as a type", v.name),
                         &[
                             (
@@ -399,10 +412,11 @@ fn abs_import(
     }
 
     emit_error(
+        "E0207",
         format!("cannot find module '{}'", import.name),
         &[(import.loc.clone(), "imported here")],
     );
-    std::process::exit(9);
+    super::exit(9);
 }
 
 fn check_abs_available(
@@ -414,6 +428,7 @@ fn check_abs_available(
 ) {
     if !fqn.is_absolute() && fqn.len() > 1 {
         emit_warn(
+            "W0104",
             format!(
                 "relative name {} not resolved. likely due to previous error",
                 fqn
@@ -459,13 +474,14 @@ fn check_abs_available(
     let module = match all_modules.get(&module_name) {
         None => {
             emit_error(
+                "E0208",
                 format!(
                     "cannot find module '{}' during abs of module '{}'",
                     module_name, selfname
                 ),
                 &[(loc.clone(), "expected to be in scope here")],
             );
-            std::process::exit(9);
+            super::exit(9);
         }
         Some(loader::Module::C(_)) => return,
         Some(loader::Module::ZZ(v)) => v,
@@ -475,6 +491,7 @@ fn check_abs_available(
         if local2.name == local_name {
             if local2.vis == ast::Visibility::Object {
                 emit_error(
+                    "E0209",
                     format!("the type '{}' in '{}' is private", local_name, module_name),
                     &[
                         (loc.clone(), "cannot use private type"),
@@ -485,6 +502,7 @@ fn check_abs_available(
             }
             if this_vis == &ast::Visibility::Export && local2.vis != ast::Visibility::Export {
                 emit_error(
+                    "E0210",
                     format!(
                         "the type '{}' in '{}' is not exported",
                         local_name, module_name
@@ -521,6 +539,7 @@ fn check_abs_available(
     }
 
     emit_error(
+        "E0211",
         format!("module '{}' does not contain '{}'", module_name, local_name),
         &[(loc.clone(), "imported here")],
     );
@@ -620,8 +639,8 @@ fn abs_expr(
                 }
                 Err(e) => {
                     if scope.macros_available {
-                        emit_error(e.message, &e.details);
-                        std::process::exit(9);
+                        emit_error(e.code, e.message, &e.details);
+                        super::exit(9);
                     } else {
                         log::debug!("abs incomplete because of macro {} {}", name, loc);
                         scope.complete.replace(false);
@@ -660,8 +679,11 @@ fn abs_statement(
         ast::Statement::Unsafe(b2) => {
             abs_block(b2, &scope, all_modules, self_md_name);
         }
-        ast::Statement::For { e1, e2, e3, body, ..} => {
+        ast::Statement::For { e1, e2, e3, invariants, body, ..} => {
             abs_block(body, &scope, all_modules, self_md_name);
+            for s in invariants {
+                abs_expr(s, scope, inbody, all_modules, self_md_name);
+            }
             for s in e1 {
                 abs_statement(s, scope, inbody, all_modules, self_md_name);
             }
@@ -672,8 +694,11 @@ fn abs_statement(
                 abs_statement(s, scope, inbody, all_modules, self_md_name);
             }
         }
-        ast::Statement::While { expr, body } => {
+        ast::Statement::While { expr, invariants, body } => {
             abs_expr(expr, &scope, inbody, all_modules, self_md_name);
+            for s in invariants {
+                abs_expr(s, &scope, inbody, all_modules, self_md_name);
+            }
             abs_block(body, &scope, all_modules, self_md_name);
         }
         ast::Statement::If { branches } => {
@@ -697,10 +722,8 @@ fn abs_statement(
             if let Some(assign) = assign {
                 abs_expr(assign, &scope, inbody, all_modules, self_md_name);
             }
-            if let Some(array) = array {
-                if let Some(array) = array {
-                    abs_expr(array, &scope, inbody, all_modules, self_md_name);
-                }
+            if let ast::Array::Sized(array) = array {
+                abs_expr(array, scope, inbody, all_modules, self_md_name);
             }
             scope.abs(all_modules, self_md_name,typed, false);
             if let ast::Type::Other(ref mut name) = &mut typed.t {
@@ -746,6 +769,7 @@ fn abs_statement(
         } => {
             if *scope.complete.borrow() {
                 emit_error(
+                    "E0704",
                     format!("macro cannot expand to statements here"),
                     &[(
                         loc.clone(),
@@ -820,8 +844,8 @@ fn expand_makro_statements(
                     }
                     Err(e) => {
                         if scope.macros_available {
-                            emit_error(e.message, &e.details);
-                            std::process::exit(9);
+                            emit_error(e.code, e.message, &e.details);
+                            super::exit(9);
                         } else {
                             scope.complete.replace(false);
                             i += 1;
@@ -853,6 +877,7 @@ pub fn abs(
     all_modules: &HashMap<Name, loader::Module>,
     ext: Ext,
     macros_available: bool,
+    lint_unused: bool,
 ) -> bool {
     debug!("abs {}", md.name);
 
@@ -861,6 +886,9 @@ pub fn abs(
     scope.complete.replace(true);
     scope.push();
 
+    // names brought in by private imports, for the unused_import lint
+    let mut imported = Vec::new();
+
     let newimports = Vec::new();
     for import in &mut md.imports {
         let mut fqn = abs_import(&md.name, &import, all_modules);
@@ -878,10 +906,11 @@ pub fn abs(
                     Ok(v) => v,
                     Err(e) => {
                         emit_error(
+                            "E0217",
                             format!("path resolve error"),
                             &[(import.loc.clone(), format!("{} : {:?}", e, path))],
                         );
-                        std::process::exit(9);
+                        super::exit(9);
                     }
                 };
                 expr = path.to_string_lossy().into();
@@ -895,6 +924,10 @@ pub fn abs(
             .unwrap_or(import.name.0.last().unwrap().clone());
 
         if import.local.len() == 0 {
+            // a c header without names may only be there for its side effects
+            if fqn.0[1] != "ext" && import.vis == ast::Visibility::Object {
+                imported.push((local_module_name.clone(), import.loc.clone()));
+            }
             scope.insert(local_module_name, fqn.clone(), &import.loc, true, true);
         } else {
             let mut new_import_local = Vec::new();
@@ -917,6 +950,9 @@ pub fn abs(
                 // if not self
                 if md.name.len() > nn.len() || md.name.0[..] != nn.0[..md.name.len()] {
                     // add to scope
+                    if import.vis == ast::Visibility::Object {
+                        imported.push((localname.clone(), import.loc.clone()));
+                    }
                     scope.insert(localname, nn, &import.loc, false, false);
                 }
             }
@@ -1102,6 +1138,7 @@ pub fn abs(
                             ast::Tail::Bind(_, _) | ast::Tail::Dynamic(_) => {
                                 if i != fieldslen - 1 {
                                     emit_error(
+                                        "E0615",
                                         format!("nested tail must be last field"),
                                         &[(
                                             field.loc.clone(),
@@ -1111,7 +1148,7 @@ pub fn abs(
                                             ),
                                         )],
                                     );
-                                    std::process::exit(9);
+                                    super::exit(9);
                                 }
                             }
                         }
@@ -1150,6 +1187,18 @@ pub fn abs(
         for ast in &mut working_on_locals {
             let mut ns = md.name.clone();
             ns.0.push(ast.name.clone());
+            match &mut ast.def {
+                ast::Def::Function { ref mut derives, .. }
+                | ast::Def::Enum { ref mut derives, .. }
+                | ast::Def::Struct { ref mut derives, .. }
+                | ast::Def::Type { ref mut derives, .. } => {
+                    if let Err(e) = lint::declare(derives, &ast.loc) {
+                        emit_error(e.code, e.message, &e.details);
+                        super::exit(9);
+                    }
+                }
+                _ => {}
+            }
             let ast_ = ast.clone();
             match &mut ast.def {
                 ast::Def::Enum { ref mut derives, .. } | ast::Def::Struct { ref mut derives, .. } | ast::Def::Type { ref mut derives, .. } => {
@@ -1174,8 +1223,8 @@ pub fn abs(
                             }
                             Err(e) => {
                                 if scope.macros_available {
-                                    emit_error(e.message, &e.details);
-                                    std::process::exit(9);
+                                    emit_error(e.code, e.message, &e.details);
+                                    super::exit(9);
                                 } else {
                                     derives.push(derive);
                                     scope.complete.replace(false);
@@ -1211,6 +1260,7 @@ pub fn abs(
                 if let ast::Def::Include { inline, .. } = previous.def {
                     if inline != import.inline {
                         emit_error(
+                            "E0214",
                             format!("conflicting import modes"),
                             &[
                                 (
@@ -1226,7 +1276,7 @@ pub fn abs(
                                 ),
                             ],
                         );
-                        std::process::exit(9);
+                        super::exit(9);
                     }
                 }
             }
@@ -1235,13 +1285,14 @@ pub fn abs(
             if import.inline {
                 if !expr.starts_with("\"") || !expr.ends_with("\"") || expr.len() < 3 {
                     emit_error(
+                        "E0215",
                         "cannot inline non-relative include",
                         &[(
                             import.loc.clone(),
                             format!("'{}' is not a relative include", expr),
                         )],
                     );
-                    std::process::exit(9);
+                    super::exit(9);
                 }
                 expr = (&expr[1..expr.len() - 1]).to_string();
             }
@@ -1265,9 +1316,37 @@ pub fn abs(
         }
     }
 
-    if ABORT.load(Ordering::Relaxed) {
+    if ABORT.swap(false, Ordering::Relaxed) {
         warn!("exit abs due to previous errors");
-        std::process::exit(9);
+        super::exit(9);
+    }
+
+    // only once every macro is expanded, or uses in the expansion are missing
+    if lint_unused && *scope.complete.borrow() {
+        for (local, loc) in imported {
+            if !scope.v[0][&local].used.get() {
+                lint::emit(
+                    "unused_import",
+                    "W0105",
+                    &loc,
+                    format!("unused import '{}'", local),
+                    &[(loc.clone(), "imported here")],
+                );
+            }
+        }
+        for local in &md.locals {
+            if let ast::Def::Theory { .. } = local.def {
+                if local.vis == ast::Visibility::Object && !scope.v[0][&local.name].used.get() {
+                    lint::emit(
+                        "unused_theory",
+                        "W0106",
+                        &local.loc,
+                        format!("unused theory '{}'", local.name),
+                        &[(local.loc.clone(), "declared here")],
+                    );
+                }
+            }
+        }
     }
 
     return scope.complete.into_inner();
@@ -1297,23 +1376,25 @@ fn abs_args(
             ast::Tail::None => {}
             ast::Tail::Dynamic(_) => {
                 emit_error(
+                    "E0616",
                     format!("missing tail binding "),
                     &[(
                         arg.loc.clone(),
                         "+ without a name makes no sense in this context",
                     )],
                 );
-                std::process::exit(9);
+                super::exit(9);
             }
             ast::Tail::Static(_, _) => {
                 emit_error(
+                    "E0616",
                     format!("missing tail binding "),
                     &[(
                         arg.loc.clone(),
                         "+ with static size makes no sense in this context",
                     )],
                 );
-                std::process::exit(9);
+                super::exit(9);
             }
             ast::Tail::Bind(s, loc) => {
                 let mut tags = ast::Tags::new();
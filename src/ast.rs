@@ -509,7 +509,7 @@ pub enum AssignOperator {
 pub enum EmitBehaviour {
     Default,
     Skip,
-    Error { loc: Location, message: String },
+    Error { loc: Location, code: String, message: String },
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -660,11 +660,12 @@ pub enum Statement {
         typed: Typed,
         tags: Tags,
         name: String,
-        array: Option<Option<Expression>>,
+        array: Array,
         assign: Option<Expression>,
     },
     While {
         expr: Expression,
+        invariants: Vec<Expression>,
         body: Block,
     },
     For {
@@ -672,6 +673,7 @@ pub enum Statement {
         e1:     Vec<Box<Statement>>,
         e2:     Option<Expression>,
         e3:     Vec<Box<Statement>>,
+        invariants: Vec<Expression>,
         body:   Block,
     },
     If {
@@ -0,0 +1,178 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+
+// what every output in target/ was last built from, as a hash of the contents of its inputs,
+// the flags and the compiler. mtimes don't survive git checkouts, ci caches or nfs clocks.
+
+#[derive(Default, Serialize, Deserialize)]
+struct Entry {
+    key: String,
+    // what the c compiler said it included last time
+    deps: Vec<PathBuf>,
+}
+
+lazy_static::lazy_static! {
+    static ref DB: Mutex<Option<Db>> = Mutex::new(None);
+    static ref COMPILERS: Mutex<HashMap<String, String>> = Mutex::new(HashMap::new());
+}
+
+fn path() -> PathBuf {
+    super::project::target_dir().join("builddb")
+}
+
+#[derive(Default)]
+struct Db {
+    entries: HashMap<String, Entry>,
+    // recorded since the last flush
+    dirty: bool,
+}
+
+fn with<R>(f: impl FnOnce(&mut Db) -> R) -> R {
+    let mut db = DB.lock().unwrap();
+    let db = db.get_or_insert_with(|| Db {
+        entries: std::fs::read(path())
+            .ok()
+            .and_then(|v| rmp_serde::from_read_ref(&v).ok())
+            .unwrap_or_default(),
+        dirty: false,
+    });
+    f(db)
+}
+
+pub struct Hasher(Sha256);
+
+impl Default for Hasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hasher {
+    pub fn new() -> Self {
+        let mut hasher = Sha256::new();
+        hasher.input(env!("CARGO_PKG_VERSION").as_bytes());
+        Hasher(hasher)
+    }
+
+    pub fn str(&mut self, v: &str) -> &mut Self {
+        self.0.input(v.len().to_le_bytes());
+        self.0.input(v.as_bytes());
+        self
+    }
+
+    // a missing file hashes differently from an empty one
+    pub fn file(&mut self, path: &Path) -> &mut Self {
+        self.str(&path.to_string_lossy());
+        match std::fs::read(path) {
+            Ok(v) => {
+                self.0.input(v.len().to_le_bytes());
+                self.0.input(&v);
+            }
+            Err(_) => {
+                self.0.input(b"missing");
+            }
+        }
+        self
+    }
+
+    // in path order, so the order of a hashset doesn't matter
+    pub fn files<'a>(&mut self, paths: impl IntoIterator<Item = &'a PathBuf>) -> &mut Self {
+        let mut paths: Vec<&PathBuf> = paths.into_iter().collect();
+        paths.sort();
+        paths.dedup();
+        for path in paths {
+            self.file(path);
+        }
+        self
+    }
+
+    // a different compiler or version behind the same name builds something else
+    pub fn compiler(&mut self, cmd: &str) -> &mut Self {
+        let id = COMPILERS
+            .lock()
+            .unwrap()
+            .entry(cmd.to_string())
+            .or_insert_with(|| match Command::new(cmd).arg("--version").output() {
+                Ok(v) => String::from_utf8_lossy(&v.stdout).to_string(),
+                Err(_) => String::new(),
+            })
+            .clone();
+        self.str(cmd);
+        self.str(&id)
+    }
+
+    pub fn finish(&self) -> String {
+        format!("{:x}", self.0.clone().result())
+    }
+}
+
+// the same file may be reached as target/test/zz/../../c/a.h
+fn id(output: &Path) -> String {
+    output
+        .canonicalize()
+        .unwrap_or_else(|_| output.to_path_buf())
+        .to_string_lossy()
+        .to_string()
+}
+
+// the output exists and was built from exactly this
+pub fn fresh(output: &Path, key: &str) -> bool {
+    if !output.exists() {
+        return false;
+    }
+    let id = id(output);
+    with(|db| db.entries.get(&id).map(|v| v.key == key).unwrap_or(false))
+}
+
+pub fn deps(output: &Path) -> Vec<PathBuf> {
+    let id = id(output);
+    with(|db| db.entries.get(&id).map(|v| v.deps.clone()).unwrap_or_default())
+}
+
+pub fn record(output: &Path, key: String) {
+    record_with_deps(output, key, Vec::new())
+}
+
+pub fn record_with_deps(output: &Path, key: String, deps: Vec<PathBuf>) {
+    let id = id(output);
+    with(|db| {
+        db.entries.insert(id, Entry { key, deps });
+        db.dirty = true;
+    });
+}
+
+// writes what was recorded since the last flush. called once a build is done,
+// and before zz exits from wherever it fails so the steps that did build are kept
+pub fn flush() {
+    with(|db| {
+        if !db.dirty {
+            return;
+        }
+        let path = path();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).ok();
+        }
+        let tmp = path.with_extension(format!("{}.tmp", std::process::id()));
+        let encoded = rmp_serde::to_vec(&db.entries).expect("cannot encode builddb");
+        if std::fs::write(&tmp, encoded).is_ok() {
+            std::fs::rename(&tmp, &path).ok();
+        }
+        db.dirty = false;
+    });
+}
+
+// generated files keep their mtime when nothing changed, so anything outside of zz
+// that looks at them, like an exported makefile, doesn't rebuild either
+pub fn write_if_changed(path: &Path, content: &[u8]) -> bool {
+    if let Ok(v) = std::fs::read(path) {
+        if v == content {
+            return false;
+        }
+    }
+    std::fs::write(path, content).unwrap_or_else(|e| panic!("cannot write {:?}: {}", path, e));
+    true
+}
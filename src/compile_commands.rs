@@ -0,0 +1,74 @@
+use super::builddb;
+use super::make::Make;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+// target/<stage>/compile_commands.json, for clangd, clang-tidy and other tools that need to
+// know how each c file is compiled. every artifact of a stage keeps the steps it was last built with
+// in target/<stage>/compile_commands/, and the file is merged from those
+pub fn write(make: &Make) {
+    let dir = super::project::target_dir().join(make.stage.to_string());
+    let fragments = dir.join("compile_commands");
+    std::fs::create_dir_all(&fragments).expect("create target dir");
+
+    let cwd = std::env::current_dir().expect("current dir");
+    let cwd = cwd.to_string_lossy().to_string();
+
+    let mut steps: Vec<Value> = Vec::new();
+    for step in &make.steps {
+        let cmd = if step.cxx { &make.cxx } else { &make.cc };
+        let mut command = vec![quote(cmd)];
+        command.extend(step.args.iter().map(|arg| quote(arg)));
+
+        steps.push(json!({
+            "directory": cwd,
+            "command": command.join(" "),
+            "file": step.source.to_string_lossy(),
+            "output": step.outp,
+        }));
+    }
+    let fragment = fragments.join(format!("{:?}_{}.json", make.artifact.typ, make.artifact.name));
+    builddb::write_if_changed(&fragment, serde_json::to_string(&steps).unwrap().as_bytes());
+
+    // one entry per file, this artifact's wins.
+    // an artifact that is gone from zz.toml leaves its fragment, but not the files it deleted
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(&fragments)
+        .expect("read compile_commands dir")
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path != &fragment)
+        .collect();
+    paths.sort();
+    paths.push(fragment);
+
+    let mut entries: BTreeMap<String, Value> = BTreeMap::new();
+    for path in paths {
+        let steps: Vec<Value> = match std::fs::read(&path).map(|v| serde_json::from_slice(&v)) {
+            Ok(Ok(v)) => v,
+            _ => continue,
+        };
+        for entry in steps {
+            if let Some(file) = entry["file"].as_str() {
+                let directory = Path::new(entry["directory"].as_str().unwrap_or(""));
+                if !directory.join(file).exists() {
+                    continue;
+                }
+                entries.insert(file.to_string(), entry.clone());
+            }
+        }
+    }
+
+    let entries: Vec<Value> = entries.into_values().collect();
+    let mut content = serde_json::to_string_pretty(&entries).unwrap();
+    content.push('\n');
+    builddb::write_if_changed(&dir.join("compile_commands.json"), content.as_bytes());
+}
+
+// tools split the command like a shell would
+pub fn quote(arg: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "-_=+/.,:@%".contains(c);
+    if !arg.is_empty() && arg.chars().all(safe) {
+        return arg.to_string();
+    }
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
@@ -0,0 +1,92 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// the width of int, size_t and pointers is up to the C compiler.
+// proofs over them only hold for the data model they were made with
+static DATA_MODEL: AtomicUsize = AtomicUsize::new(UNSET);
+const UNSET: usize = usize::MAX;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DataModel {
+    // 64bit unix and most hosted targets
+    LP64,
+    // 32bit cpus, like arm cortex-m, esp32 or wasm32
+    ILP32,
+    // 16bit mcus with a 16bit int and size_t
+    LP32,
+}
+
+impl DataModel {
+    pub fn current() -> DataModel {
+        match DATA_MODEL.load(Ordering::Relaxed) {
+            1 => DataModel::ILP32,
+            2 => DataModel::LP32,
+            _ => DataModel::LP64,
+        }
+    }
+
+    pub fn set(self) {
+        DATA_MODEL.store(self.index(), Ordering::Relaxed);
+    }
+
+    // zz.toml only applies if --target wasn't given on the command line
+    pub fn set_default(self) {
+        let _ = DATA_MODEL.compare_exchange(UNSET, self.index(), Ordering::Relaxed, Ordering::Relaxed);
+    }
+
+    fn index(&self) -> usize {
+        match self {
+            DataModel::LP64 => 0,
+            DataModel::ILP32 => 1,
+            DataModel::LP32 => 2,
+        }
+    }
+
+    pub fn int_width(&self) -> u32 {
+        match self {
+            DataModel::LP64 => 32,
+            DataModel::ILP32 => 32,
+            DataModel::LP32 => 16,
+        }
+    }
+
+    pub fn size_width(&self) -> u32 {
+        match self {
+            DataModel::LP64 => 64,
+            DataModel::ILP32 => 32,
+            DataModel::LP32 => 16,
+        }
+    }
+
+    pub fn pointer_width(&self) -> u32 {
+        match self {
+            DataModel::LP64 => 64,
+            DataModel::ILP32 => 32,
+            DataModel::LP32 => 32,
+        }
+    }
+}
+
+impl std::str::FromStr for DataModel {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "lp64" => Ok(DataModel::LP64),
+            "ilp32" => Ok(DataModel::ILP32),
+            "lp32" => Ok(DataModel::LP32),
+            _ => Err(format!(
+                "unknown target data model \"{}\". expected one of lp64, ilp32, lp32",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for DataModel {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DataModel::LP64 => write!(f, "lp64"),
+            DataModel::ILP32 => write!(f, "ilp32"),
+            DataModel::LP32 => write!(f, "lp32"),
+        }
+    }
+}
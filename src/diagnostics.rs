@@ -0,0 +1,286 @@
+// every diagnostic has a stable code, given where it is emitted. the message next to it
+// here is what it says, with {} for whatever is formatted into it.
+// codes are never reused. a message that goes away moves to RETIRED and keeps its code reserved.
+
+//  E01xx   syntax
+//  E02xx   names and modules
+//  E03xx   types and declarations
+//  E04xx   memory safety
+//  E05xx   proofs and contracts
+//  E06xx   tails
+//  E07xx   macros, testcases and the emitter
+//  E08xx   solver and tooling
+//  E09xx   internal compiler errors
+//  W01xx   warnings
+
+pub static CODES: &[(&str, &str)] = &[
+    // syntax
+    ("E0101", "syntax error"),
+    ("E0102", "else without if"),
+    ("E0103", "multiple default cases"),
+    ("E0104", "vararg not possible here"),
+    ("E0105", "assign arguments can not be followed by non assign argument"),
+    ("E0106", "hex value too big for char"),
+    ("E0107", "unsupported escape character"),
+    ("E0108", "enums must be positive integer literals"),
+    ("E0109", "self arg must be first"),
+    ("E0110", "invalid flags statement"),
+    // names and modules
+    ("E0201", "undefined name '{}'"),
+    ("E0202", "possibly undefined name '{}'"),
+    ("E0203", "undefined symbol '{}'"),
+    ("E0204", "resolving '{}' as member is not possible"),
+    ("E0205", "'{}' cannot be used as qualified name\n{}\n{}"),
+    ("E0206", "cannot use module '{}' as a type"),
+    ("E0207", "cannot find module '{}'"),
+    ("E0208", "cannot find module '{}' during abs of module '{}'"),
+    ("E0209", "the type '{}' in '{}' is private"),
+    ("E0210", "the type '{}' in '{}' is not exported"),
+    ("E0211", "module '{}' does not contain '{}'"),
+    ("E0212", "module {} does not contain {}"),
+    ("E0213", "conflicting local name '{}'"),
+    ("E0214", "conflicting import modes"),
+    ("E0215", "cannot inline non-relative include"),
+    ("E0217", "path resolve error"),
+    ("E0218", "undefined type '{}' during flatten of '{}'"),
+    ("E0219", "redeclaration of local name '{}'"),
+    ("E0220", "redeclaration of builtin theory '{}'"),
+    // types and declarations
+    ("E0301", "expected boolean, got {}"),
+    ("E0302", "expected integer, got {}"),
+    ("E0304", "incompatible types {} and {}"),
+    ("E0305", "incompatible function pointers"),
+    ("E0306", "literal does not fit into {}"),
+    ("E0307", "type cannot be elided"),
+    ("E0308", "invalid types for integer operator"),
+    ("E0309", "invalid types for boolean operator"),
+    ("E0310", "unprovable types for expression"),
+    ("E0311", "assignment of incompatible types"),
+    ("E0312", "assignment of incompatible pointer depth"),
+    ("E0313", "assignment of incompatible types (2)"),
+    ("E0314", "assigning arrays of different len"),
+    ("E0315", "assign arithmetic is not yet implemented"),
+    ("E0316", "call argument count mismatch"),
+    ("E0317", "call argument type mismatch"),
+    ("E0318", "cannot initialize non-struct with a struct literal"),
+    ("E0319", "empty literal array not possible"),
+    ("E0320", "array size must be static"),
+    ("E0321", "{} has no member {}"),
+    ("E0322", "{} of type {} does not a have a field named {}"),
+    ("E0323", "closure does not a have a field named {}"),
+    ("E0324", "{} is not accessible as struct. it is {}"),
+    ("E0325", "{} is not accessible as struct. it is typeof({})"),
+    ("E0326", "recursive type {} will never complete"),
+    ("E0327", "new stack initialization cannot be array"),
+    ("E0328", "new stack initialization requires function call to constructor"),
+    ("E0329", "invalid use of new tag"),
+    ("E0330", "new must be first or last argument"),
+    ("E0331", "only one argument can be new"),
+    ("E0332", "theory '{}' is not a real world object"),
+    ("E0333", "taking the value of a theory is not a thing"),
+    ("E0334", "theory needs a return value"),
+    ("E0335", "incompatible arguments to theory {}"),
+    ("E0336", "array access with signed index is not well defined"),
+    ("E0337", "array access with something not a usize"),
+    ("E0338", "void is not a value: '{}'"),
+    ("E0339", "typeof cannot be used here as it has no actual rvalue"),
+    // memory safety
+    ("E0401", "deref of unsafe pointer"),
+    ("E0402", "deref of {} is not possible"),
+    ("E0403", "possible out of bounds array access"),
+    ("E0404", "possible out of bounds pointer arithmetic"),
+    ("E0405", "unprovable pointer arithmetic"),
+    ("E0406", "cannot prove memory access due to unexpected type"),
+    ("E0407", "{} is not safe to access. it is an untracked C type {}"),
+    ("E0408", "unsafe read access to uninitialized local '{}'"),
+    ("E0409", "call expression on {} not safe"),
+    ("E0410", "call expression on {} is unprovable"),
+    ("E0411", "shift right of signed value is unprovable"),
+    ("E0412", "float to integer cast may overflow"),
+    // proofs and contracts
+    ("E0501", "unproven callsite assert for {}"),
+    ("E0502", "unproven model"),
+    ("E0503", "unproven loop invariant"),
+    ("E0504", "loop invariant breaks ssa"),
+    ("E0505", "loop never terminates"),
+    ("E0506", "condition breaks ssa"),
+    ("E0508", "callsite assert broke ssa solution"),
+    ("E0509", "callsite effect would break SSA"),
+    ("E0510", "callsite assign broke ssa"),
+    ("E0511", "attachment leads to conflicting constraints"),
+    ("E0512", "function is unprovable"),
+    ("E0513", "theory is unproven"),
+    ("E0514", "theory is unprovable"),
+    ("E0515", "static is not solveable"),
+    ("E0516", "static is unconstrained"),
+    ("E0517", "static default value is not solveable"),
+    ("E0518", "static default value is unconstrained"),
+    ("E0519", "proof timed out"),
+    ("E0520", "invalid callsite_source {}"),
+    ("E0521", "aborted due to previous smt errors"),
+    ("E0522", "assertion of theory {} outside static()"),
+    // tails
+    ("E0601", "tail size of {} bound to wrong pointer"),
+    ("E0602", "tail size of {} bound to wrong value"),
+    ("E0603", "tail size of {} not bound"),
+    ("E0604", "tail size must be known for stack variables"),
+    ("E0605", "tail value on non struct {}"),
+    ("E0606", "tail value on non struct {:?} ({})"),
+    ("E0607", "tail tag without previous arg"),
+    ("E0608", "tail binding on struct with no members"),
+    ("E0609", "tail passed as non pointer"),
+    ("E0610", "undeclared nested tail"),
+    ("E0611", "unavailable type used as tail"),
+    ("E0612", "flat type used as tail"),
+    ("E0613", "struct declared as having a tail, but has no tail"),
+    ("E0614", "tail sized binding on struct declaration is invalid"),
+    ("E0615", "nested tail must be last field"),
+    ("E0616", "missing tail binding "),
+    ("E0617", "tail field has no be the last field in a struct"),
+    // macros, testcases and the emitter
+    ("E0701", "macro {} is unavailable"),
+    ("E0702", "macro {} is unavailable (exe {:?})"),
+    ("E0703", "syntax error in proc macro return: {}"),
+    ("E0704", "macro cannot expand to statements here"),
+    ("E0705", "macro expansion requires @ident syntax"),
+    ("E0706", "testcase field must be literal string or byte array"),
+    ("E0707", "testcase field must be literal string or byte array, not"),
+    ("E0708", "expression not usable (yet?) in cpp context"),
+    ("E0709", "invalid c preprocessor directive in local expression location"),
+    ("E0710", "cannot inline {:?}"),
+    ("E0711", "no field available"),
+    ("E0712", "unknown lint '{}'"),
+    ("E0713", "@{} expects lint names"),
+    ("E0714", "lint '{}' can only be set for the whole project"),
+    // solver and tooling
+    ("E0801", "@smt_timeout macro expects one integer argument in milliseconds"),
+    ("E0802", "@solver macro expects one string literal argument"),
+    ("E0803", "no smt solver installed. zz needs one of {} in PATH"),
+    ("E0804", "unknown solver \"{}\". expected one of {}"),
+    ("E0805", "solver {} is not installed. none of {} found in PATH"),
+    ("E0806", "nothing to explain"),
+    ("E0807", "variant \"{}\" is not declared in zz.toml"),
+    ("E0808", "smt solver failed: {}"),
+    // internal compiler errors
+    ("E0901", "internal compiler error"),
+    ("E0902", "ICE: untyped literal ended up in emitter"),
+    ("E0903", "ICE: unsupported attr"),
+    ("E0904", "ICE: unexpected rhs {:?}"),
+    ("E0905", "ICE: unexpected operator {}"),
+    ("E0906", "ICE: unexpected operator"),
+    ("E0907", "ICE: tail binding not emitted as usize"),
+    ("E0908", "ICE in {}: redeclation of local name '{}' should have failed in expand"),
+    ("E0909", "ICE ext module {} unavable or somehow we're missing local {}"),
+    ("E0910", "ice: unknown module {}"),
+    ("E0911", "ICE: untyped ended up in emitter"),
+    ("E0912", "ICE: incomplete macro expansion ended up in emitter"),
+    // warnings
+    ("W0101", "floating point arithmetic is not proven"),
+    ("W0102", "ICE: reuse of dead symbol '{}'"),
+    ("W0103", "unnecessary branch condition"),
+    ("W0104", "relative name {} not resolved. likely due to previous error"),
+    ("W0105", "unused import '{}'"),
+    ("W0106", "unused theory '{}'"),
+    ("W0107", "err '{}' may be returned without being checked"),
+];
+
+// codes that are no longer emitted, and what they were
+pub static RETIRED: &[(&str, &str)] = &[
+    ("E0216", "relative name {} not resolved. likely due to previous error. now W0104"),
+    ("E0303", "expected integer , got {}. now E0302"),
+    ("E0507", "unnecessary branch condition. now W0103"),
+];
+
+// long explanations for zz explain-error, with a broken and a fixed example
+pub static EXPLANATIONS: &[(&str, &str)] = &[
+    ("E0101", include_str!("errors/E0101.md")),
+    ("E0201", include_str!("errors/E0201.md")),
+    ("E0202", include_str!("errors/E0202.md")),
+    ("E0203", include_str!("errors/E0203.md")),
+    ("E0213", include_str!("errors/E0213.md")),
+    ("E0219", include_str!("errors/E0219.md")),
+    ("E0220", include_str!("errors/E0220.md")),
+    ("E0301", include_str!("errors/E0301.md")),
+    ("E0304", include_str!("errors/E0304.md")),
+    ("E0306", include_str!("errors/E0306.md")),
+    ("E0307", include_str!("errors/E0307.md")),
+    ("E0316", include_str!("errors/E0316.md")),
+    ("E0320", include_str!("errors/E0320.md")),
+    ("E0332", include_str!("errors/E0332.md")),
+    ("E0336", include_str!("errors/E0336.md")),
+    ("E0337", include_str!("errors/E0337.md")),
+    ("E0401", include_str!("errors/E0401.md")),
+    ("E0403", include_str!("errors/E0403.md")),
+    ("E0404", include_str!("errors/E0404.md")),
+    ("E0501", include_str!("errors/E0501.md")),
+    ("E0502", include_str!("errors/E0502.md")),
+    ("E0503", include_str!("errors/E0503.md")),
+    ("E0512", include_str!("errors/E0512.md")),
+    ("E0513", include_str!("errors/E0513.md")),
+    ("E0519", include_str!("errors/E0519.md")),
+    ("E0521", include_str!("errors/E0521.md")),
+    ("E0615", include_str!("errors/E0615.md")),
+    ("E0712", include_str!("errors/E0712.md")),
+    ("E0803", include_str!("errors/E0803.md")),
+    ("W0101", include_str!("errors/W0101.md")),
+    ("W0103", include_str!("errors/W0103.md")),
+    ("W0105", include_str!("errors/W0105.md")),
+    ("W0106", include_str!("errors/W0106.md")),
+    ("W0107", include_str!("errors/W0107.md")),
+];
+
+pub fn template(code: &str) -> Option<&'static str> {
+    CODES.iter().find(|(c, _)| *c == code).map(|(_, template)| *template)
+}
+
+pub fn explanation(code: &str) -> Option<&'static str> {
+    EXPLANATIONS.iter().find(|(c, _)| *c == code).map(|(_, text)| *text)
+}
+
+pub fn retired(code: &str) -> Option<&'static str> {
+    RETIRED.iter().find(|(c, _)| *c == code).map(|(_, was)| *was)
+}
+
+pub fn print_codes() {
+    for (code, template) in CODES {
+        let more = if explanation(code).is_some() { "*" } else { " " };
+        println!("{}{} {}", code, more, template.lines().next().unwrap_or(""));
+    }
+    for (code, was) in RETIRED {
+        println!("{}  retired: {}", code, was);
+    }
+    println!("\ncodes marked with * have a longer explanation with `zz explain-error <code>`");
+}
+
+pub fn print_explanation(code: &str) -> bool {
+    let code = code.to_uppercase();
+    if let Some(was) = retired(&code) {
+        println!("{} is no longer emitted. it was: {}", code, was);
+        return true;
+    }
+    let template = match template(&code) {
+        Some(v) => v,
+        None => return false,
+    };
+    println!("{}: {}\n", code, template);
+    match explanation(&code) {
+        Some(text) => print!("{}", text),
+        None => println!("there is no longer explanation for this error yet."),
+    }
+    true
+}
+
+// what a detail of a diagnostic is, for tools that want to show them differently
+pub fn note_kind(message: &str) -> (&'static str, Option<bool>) {
+    if message == "last callsite" {
+        ("callsite", None)
+    } else if message == "reached because this branch condition was true" {
+        ("branch", Some(true))
+    } else if message == "reached because this branch condition was false" {
+        ("branch", Some(false))
+    } else if message.starts_with("for ") && message.contains(" = ") {
+        ("value", None)
+    } else {
+        ("note", None)
+    }
+}
@@ -1,16 +1,18 @@
 #![allow(unused)]
 
 use super::ast;
+use super::builddb;
 use super::flatten;
 use super::make;
 use super::name::Name;
 use super::parser::{self, emit_error};
 use super::project::Project;
+use super::proofcache;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::fs;
 use std::io::{Read, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use super::project;
 use super::mergecc;
 
@@ -24,12 +26,17 @@ pub struct CFile {
 
     pub cflags:     Vec<String>,
     pub lflags:     Vec<String>,
+
+    // from proving the module, shown again when it comes from the buildcache
+    #[serde(default)]
+    pub warnings:   Vec<proofcache::Warning>,
 }
 
 pub struct Emitter {
     cxx: bool,
     p: String,
-    f: fs::File,
+    // written out when done, and only if it changed
+    f: Vec<u8>,
     module: flatten::Module,
     cincludes: Vec<String>,
     header: bool,
@@ -80,7 +87,7 @@ impl Emitter {
         header: bool,
     ) -> Self {
         let (cxx, p) = outname(project, &stage, &module.name, header);
-        let mut f = fs::File::create(&p).expect(&format!("cannot create {}", p));
+        let mut f = Vec::new();
 
         let casedir = project::target_dir()
             .join(stage.to_string())
@@ -165,13 +172,14 @@ impl Emitter {
             }
             ast::Type::ILiteral | ast::Type::ULiteral | ast::Type::Elided | ast::Type::New | ast::Type::Typeid => {
                 parser::emit_error(
+                    "E0902",
                     "ICE: untyped literal ended up in emitter",
                     &[(
                         name.loc.clone(),
                         format!("this should have been resolved earlier"),
                     )],
                 );
-                std::process::exit(9);
+                super::exit(9);
             }
         }
     }
@@ -230,6 +238,8 @@ impl Emitter {
 
                     self.emit_struct_def(&d, None);
                     if let Some(vs) = module.typevariants.get(&Name::from(&d.name)) {
+                        let mut vs: Vec<_> = vs.iter().collect();
+                        vs.sort_by_key(|(v, _)| **v);
                         for (v, loc) in vs {
                             write!(self.f, "#endif\n#ifndef ZZ_FORWARD_{tn}_{v}\n#define ZZ_FORWARD_{tn}_{v}\n",
                                    tn = self.to_local_name_mangle(&Name::from(&d.name)),
@@ -315,6 +325,8 @@ impl Emitter {
                     self.emit_struct(&d, isimpl, None);
 
                     if let Some(vs) = module.typevariants.get(&Name::from(&d.name)) {
+                        let mut vs: Vec<_> = vs.iter().collect();
+                        vs.sort_by_key(|(v, _)| **v);
                         for (v, tvloc) in vs {
                             let mut d = d.clone();
                             d.name = format!("{}_{}", d.name, v);
@@ -361,6 +373,8 @@ impl Emitter {
             }
         }
 
+        builddb::write_if_changed(Path::new(&self.p), &self.f);
+
         CFile {
             name: module.name,
             filepath: self.p,
@@ -369,6 +383,7 @@ impl Emitter {
             symbols: self.symbols,
             cflags: Vec::new(),
             lflags: Vec::new(),
+            warnings: Vec::new(),
         }
     }
 
@@ -416,10 +431,11 @@ impl Emitter {
             let mut f = match fs::File::open(&fi) {
                 Err(e) => {
                     parser::emit_error(
+                        "E0710",
                         format!("cannot inline {:?}", expr),
                         &[(loc.clone(), format!("{}", e))],
                     );
-                    std::process::exit(9);
+                    super::exit(9);
                 }
                 Ok(f) => f,
             };
@@ -460,8 +476,8 @@ impl Emitter {
                 &path,
                 );
 
-            let thispath = std::fs::canonicalize(&self.p).expect("PWD broke somehow");
-            let thisdir  = thispath.parent().expect("PWD broke somehow");
+            let thisdir = Path::new(&self.p).parent().expect("PWD broke somehow");
+            let thisdir = std::fs::canonicalize(thisdir).expect("PWD broke somehow");
             let path = pathdiff::diff_paths(
                 std::path::Path::new(&fi),
                 std::path::Path::new(&thisdir),
@@ -656,14 +672,16 @@ impl Emitter {
                                 }
                                 _ => {
                                     parser::emit_error(
+                                            "E0706",
                                             "testcase field must be literal string or byte array",
                                             &[(loc.clone(), format!("this expression cannot be emitted as testcase file"))]
                                             );
-                                    std::process::exit(9);
+                                    super::exit(9);
                                 }
                             },
                             _ => {
                                 parser::emit_error(
+                                    "E0707",
                                     "testcase field must be literal string or byte array, not",
                                     &[(
                                         field.loc().clone(),
@@ -672,7 +690,7 @@ impl Emitter {
                                         ),
                                     )],
                                 );
-                                std::process::exit(9);
+                                super::exit(9);
                             }
                         }
                     }
@@ -682,13 +700,14 @@ impl Emitter {
                 }
                 _ => {
                     parser::emit_error(
+                        "E0706",
                         "testcase field must be literal string or byte array",
                         &[(
                             expr.loc().clone(),
                             format!("this expression cannot be emitted as testcase file"),
                         )],
                     );
-                    std::process::exit(9);
+                    super::exit(9);
                 }
             }
         }
@@ -781,13 +800,14 @@ impl Emitter {
                 ast::Array::Unsized => {
                     if i != (fields.len() - 1) {
                         parser::emit_error(
+                            "E0617",
                             "tail field has no be the last field in a struct",
                             &[(
                                 field.loc.clone(),
                                 format!("tail field would displace next field"),
                             )],
                         );
-                        std::process::exit(9);
+                        super::exit(9);
                     }
                     if let Some((tt, _)) = &tail_variant {
                         emitted_exact_tail = true;
@@ -820,13 +840,14 @@ impl Emitter {
                     }
                     o => {
                         parser::emit_error(
+                            "E0711",
                             "no field available",
                             &[
                                 (ast.loc.clone(), format!("tail field is {:?}", o)),
                                 (loc.clone(), format!("when expanding type here")),
                             ],
                         );
-                        std::process::exit(9);
+                        super::exit(9);
                     }
                 }
             }
@@ -925,10 +946,11 @@ impl Emitter {
             match attr.as_str() {
                 o => {
                     parser::emit_error(
+                        "E0903",
                         "ICE: unsupported attr",
                         &[(loc.clone(), format!("'{}' is not a valid c attribute", o))],
                     );
-                    std::process::exit(9);
+                    super::exit(9);
                 }
             }
         }
@@ -1017,10 +1039,11 @@ impl Emitter {
                 }
                 o => {
                     parser::emit_error(
+                        "E0903",
                         "ICE: unsupported attr",
                         &[(loc.clone(), format!("'{}' is not a valid c attribute", o))],
                     );
-                    std::process::exit(9);
+                    super::exit(9);
                 }
             }
         }
@@ -1126,10 +1149,11 @@ impl Emitter {
                 }
                 o => {
                     parser::emit_error(
+                        "E0903",
                         "ICE: unsupported attr",
                         &[(loc.clone(), format!("'{}' is not a valid c attribute", o))],
                     );
-                    std::process::exit(9);
+                    super::exit(9);
                 }
             }
         }
@@ -1221,9 +1245,9 @@ impl Emitter {
                             if let ast::Type::Other(name) = &name.t {
                                 if name.to_string() == "linker" {
                                     if args.len() != 1 {
-                                        emit_error(format!("invalid flags statement"),
+                                        emit_error("E0110", format!("invalid flags statement"),
                                             &[(loc.clone(), "link flag tage a single string arg")]);
-                                        std::process::exit(9);
+                                        super::exit(9);
                                     }
                                     if let ast::Expression::LiteralString{v,..} = args[0].as_ref() {
                                         write!(self.f, "#pragma comment(linker, \"").unwrap();
@@ -1231,17 +1255,17 @@ impl Emitter {
                                         write!(self.f, "\")\n").unwrap();
                                         continue;
                                     } else {
-                                        emit_error(format!("invalid flags statement"),
+                                        emit_error("E0110", format!("invalid flags statement"),
                                         &[(expr.loc().clone(), "literal string required")]);
                                     }
                                 }
-                                emit_error(format!("invalid flags statement"),
+                                emit_error("E0110", format!("invalid flags statement"),
                                     &[(expr.loc().clone(), format!("unknown flag {}", name))]);
                             }
                         }
                     }
-                    emit_error(format!("invalid flags statement"), &[(loc.clone(), "this expression does not add a flag")]);
-                    std::process::exit(9);
+                    emit_error("E0110", format!("invalid flags statement"), &[(loc.clone(), "this expression does not add a flag")]);
+                    super::exit(9);
                 }
             }
         }
@@ -1285,7 +1309,7 @@ impl Emitter {
                 self.emit_zblock(b2, true, None);
                 false
             }
-            ast::Statement::For {label, e1, e2, e3, body } => {
+            ast::Statement::For {label, e1, e2, e3, body, .. } => {
                 write!(self.f, "  for (").unwrap();
                 let mut first = true;
                 for expr in e1 {
@@ -1326,7 +1350,7 @@ impl Emitter {
                 self.emit_zblock(body, true, label.clone());
                 false
             }
-            ast::Statement::While { expr, body } => {
+            ast::Statement::While { expr, body, .. } => {
                 write!(self.f, "while (").unwrap();
                 self.emit_expr(expr);
                 write!(self.f, ")").unwrap();
@@ -1394,12 +1418,17 @@ impl Emitter {
                 }
 
                 write!(self.f, " {} ", name).unwrap();
-                if let Some(array) = &array {
-                    write!(self.f, " [ ").unwrap();
-                    if let Some(array) = &array {
-                        self.emit_expr(array);
+                match &array {
+                    ast::Array::Sized(expr) => {
+                        write!(self.f, " [ ").unwrap();
+                        self.emit_expr(expr);
+                        write!(self.f, " ] ").unwrap();
+                    }
+                    ast::Array::Unsized => {
+                        write!(self.f, " [ ").unwrap();
+                        write!(self.f, " ] ").unwrap();
                     }
-                    write!(self.f, " ] ").unwrap();
+                    ast::Array::None => {}
                 }
 
                 self.emit_loc(&loc);
@@ -1511,8 +1540,8 @@ impl Emitter {
                 self.emit_expr(expr);
             }
             _ =>  {
-                emit_error(format!("expression not usable (yet?) in cpp context"), &[(v.loc().clone(), "here")]);
-                std::process::exit(9);
+                emit_error("E0708", format!("expression not usable (yet?) in cpp context"), &[(v.loc().clone(), "here")]);
+                super::exit(9);
             }
         }
     }
@@ -1523,8 +1552,8 @@ impl Emitter {
                 self.emit_expr(expr);
             }
             ast::Expression::MacroCall { loc, .. } => {
-                emit_error(format!("internal compiler error"), &[(loc.clone(), "ICE: macro not available yet")]);
-                std::process::exit(9);
+                emit_error("E0901", format!("internal compiler error"), &[(loc.clone(), "ICE: macro not available yet")]);
+                super::exit(9);
             }
             ast::Expression::ArrayInit { fields, loc } => {
                 self.emit_loc(&loc);
@@ -1615,8 +1644,9 @@ impl Emitter {
                 match parser::parse_int(&v) {
                     // Output as decimal if an integer to avoid warnings for binary literals
                     // (binary literals are a GNU extension)
+                    // a negative number in hex would be unsigned in C, which is wrong if it becomes a float
                     Some(parser::Integer::Signed(int)) => {
-                        write!(self.f, "    {:#x}", int).unwrap();
+                        write!(self.f, "    {}", int).unwrap();
                     }
                     Some(parser::Integer::Unsigned(int)) => {
                         write!(self.f, "    {:#x}", int).unwrap();
@@ -1640,9 +1670,9 @@ impl Emitter {
                     ast::EmitBehaviour::Skip => {
                         return;
                     }
-                    ast::EmitBehaviour::Error { loc, message } => {
-                        emit_error(format!("{}", message), &[(loc.clone(), "here")]);
-                        std::process::exit(9);
+                    ast::EmitBehaviour::Error { loc, code, message } => {
+                        emit_error(code, format!("{}", message), &[(loc.clone(), "here")]);
+                        super::exit(9);
                     }
                 };
 
@@ -1711,13 +1741,14 @@ impl Emitter {
             }
             ast::Expression::Cpp {loc, ..} => {
                 parser::emit_error(
+                    "E0709",
                     "invalid c preprocessor directive in local expression location".to_string(),
                     &[(
                         loc.clone(),
                         format!("c preprocessor expression not possible in this location"),
                     )],
                 );
-                std::process::exit(9);
+                super::exit(9);
             }
         }
     }
@@ -1757,31 +1788,6 @@ impl Emitter {
     }
 }
 
-impl CFile {
-    pub fn is_newer_than(&self, target: &str) -> bool {
-        let itarget = match std::fs::metadata(&target) {
-            Ok(v) => v,
-            Err(_) => return true,
-        };
-        let itarget = itarget
-            .modified()
-            .expect(&format!("cannot stat {}", target));
-
-        for source in &self.sources {
-            let isource = std::fs::metadata(source).expect(&format!("cannot stat {:?}", source));
-
-            let isource = isource
-                .modified()
-                .expect(&format!("cannot stat {:?}", source));
-
-            if isource > itarget {
-                return true;
-            }
-        }
-        return false;
-    }
-}
-
 pub fn builtin(
     project: &Project,
     stage: &make::Stage,
@@ -1792,7 +1798,11 @@ pub fn builtin(
         "target/gen/zz_builtins_{}_{}_{:?}.c",
         project.name, artifact.name, artifact.typ
     );
-    let mut f = fs::File::create(&p).expect(&format!("cannot create {}", p));
+    let mut f = Vec::new();
+
+    // in a stable order, so the file doesn't change when the symbols didn't
+    let mut symbols: Vec<Name> = symbols.into_iter().collect();
+    symbols.sort();
 
     write!(
         f,
@@ -1830,6 +1840,7 @@ pub fn builtin(
     write!(f, "}};\n").unwrap();
 
     write!(f, "#endif\n").unwrap();
+    builddb::write_if_changed(Path::new(&p), &f);
 
     CFile {
         name: Name::from("__zz_builtins"),
@@ -1839,5 +1850,6 @@ pub fn builtin(
         symbols: HashSet::new(),
         cflags: Vec::new(),
         lflags: Vec::new(),
+        warnings: Vec::new(),
     }
 }
@@ -274,13 +274,14 @@ impl Emitter {
             }
             ast::Type::ILiteral | ast::Type::ULiteral | ast::Type::Elided | ast::Type::New | ast::Type::Typeid => {
                 parser::emit_error(
+                    "E0902",
                     "ICE: untyped literal ended up in emitter",
                     &[(
                         name.loc.clone(),
                         format!("this should have been resolved earlier"),
                     )],
                 );
-                std::process::exit(9);
+                super::exit(9);
             }
         }
     }
@@ -355,13 +356,14 @@ impl Emitter {
             }
             ast::Type::ILiteral | ast::Type::ULiteral | ast::Type::Elided | ast::Type::New | ast::Type::Typeid => {
                 parser::emit_error(
+                    "E0902",
                     "ICE: untyped literal ended up in emitter",
                     &[(
                         typed.loc.clone(),
                         format!("this should have been resolved earlier"),
                     )],
                 );
-                std::process::exit(9);
+                super::exit(9);
             }
             ast::Type::Other(ref n) => {
                 //TODO
@@ -479,13 +481,14 @@ impl Emitter {
             }
             ast::Type::ILiteral | ast::Type::ULiteral | ast::Type::Elided | ast::Type::New | ast::Type::Typeid => {
                 parser::emit_error(
+                    "E0902",
                     "ICE: untyped literal ended up in emitter",
                     &[(
                         typed.loc.clone(),
                         format!("this should have been resolved earlier"),
                     )],
                 );
-                std::process::exit(9);
+                super::exit(9);
             }
         }
     }
@@ -186,13 +186,14 @@ impl Emitter {
             }
             ast::Type::ILiteral | ast::Type::ULiteral | ast::Type::Elided | ast::Type::New | ast::Type::Typeid => {
                 parser::emit_error(
+                    "E0902",
                     "ICE: untyped literal ended up in emitter",
                     &[(
                         name.loc.clone(),
                         format!("this should have been resolved earlier"),
                     )],
                 );
-                std::process::exit(9);
+                super::exit(9);
             }
         }
     }
@@ -904,13 +905,14 @@ PyTypeObject py_Type_{ln}  = {{
                     | ast::Type::Typeid
                     | ast::Type::New => {
                         parser::emit_error(
+                            "E0902",
                             "ICE: untyped literal ended up in emitter",
                             &[(
                                 arg.loc.clone(),
                                 format!("this should have been resolved earlier"),
                             )],
                         );
-                        std::process::exit(9);
+                        super::exit(9);
                     }
                 }
             } else {
@@ -1147,13 +1149,14 @@ static PyObject* py_{}(PyObject *pyself, PyObject *args) {{
                     | ast::Type::Typeid
                     | ast::Type::New => {
                         parser::emit_error(
+                            "E0902",
                             "ICE: untyped literal ended up in emitter",
                             &[(
                                 arg.loc.clone(),
                                 format!("this should have been resolved earlier"),
                             )],
                         );
-                        std::process::exit(9);
+                        super::exit(9);
                     }
                 }
             } else {
@@ -1302,13 +1305,14 @@ static PyObject* py_{}(PyObject *pyself, PyObject *args) {{
                 }
                 ast::Type::ILiteral | ast::Type::ULiteral | ast::Type::Elided | ast::Type::New | ast::Type::Typeid => {
                     parser::emit_error(
+                        "E0902",
                         "ICE: untyped literal ended up in emitter",
                         &[(
                             arg.typed.loc.clone(),
                             format!("this should have been resolved earlier"),
                         )],
                     );
-                    std::process::exit(9);
+                    super::exit(9);
                 }
             },
         }
@@ -1348,13 +1352,14 @@ static PyObject* py_{}(PyObject *pyself, PyObject *args) {{
             }
             ast::Type::ILiteral | ast::Type::ULiteral | ast::Type::Elided | ast::Type::New | ast::Type::Typeid => {
                 parser::emit_error(
+                    "E0902",
                     "ICE: untyped literal ended up in emitter",
                     &[(
                         name.loc.clone(),
                         format!("this should have been resolved earlier"),
                     )],
                 );
-                std::process::exit(9);
+                super::exit(9);
             }
         }
     }
@@ -176,13 +176,14 @@ impl Emitter {
                 */
             ast::Type::ILiteral | ast::Type::ULiteral | ast::Type::Elided | ast::Type::New | ast::Type::Typeid=> {
                 parser::emit_error(
+                    "E0911",
                     "ICE: untyped ended up in emitter",
                     &[(
                         name.loc.clone(),
                         format!("this should have been resolved earlier"),
                     )],
                 );
-                std::process::exit(9);
+                super::exit(9);
             }
         })
     }
@@ -623,13 +624,14 @@ impl {name} {{
                 ast::Array::Unsized => {
                     if i != (fields.len() - 1) {
                         parser::emit_error(
+                            "E0617",
                             "tail field has no be the last field in a struct",
                             &[(
                                 field.loc.clone(),
                                 format!("tail field would displace next field"),
                             )],
                         );
-                        std::process::exit(9);
+                        super::exit(9);
                     }
                     if let Some(tt) = tail_variant {
                         write!(self.f, "    pub {} : [", field.name).unwrap();
@@ -773,13 +775,14 @@ impl {name} {{
             }
             ast::Expression::MacroCall { loc, .. } => {
                 parser::emit_error(
+                    "E0912",
                     "ICE: incomplete macro expansion ended up in emitter",
                     &[(
                         loc.clone(),
                         format!("this should have been resolved earlier"),
                     )],
                 );
-                std::process::exit(9);
+                super::exit(9);
             }
             ast::Expression::ArrayInit { .. } => {}
             ast::Expression::StructInit { .. } => {}
@@ -857,9 +860,9 @@ impl {name} {{
                     ast::EmitBehaviour::Skip => {
                         return;
                     }
-                    ast::EmitBehaviour::Error { loc, message } => {
-                        emit_error(format!("{}", message), &[(loc.clone(), "here")]);
-                        std::process::exit(9);
+                    ast::EmitBehaviour::Error { loc, code, message } => {
+                        emit_error(code, format!("{}", message), &[(loc.clone(), "here")]);
+                        super::exit(9);
                     }
                 };
 
@@ -926,13 +929,14 @@ impl {name} {{
             }
             ast::Expression::Cpp{loc, ..} => {
                 parser::emit_error(
+                    "E0709",
                     "invalid c preprocessor directive in local expression location".to_string(),
                     &[(
                         loc.clone(),
                         format!("c preprocessor expression not possible in this location"),
                     )],
                 );
-                std::process::exit(9);
+                super::exit(9);
             }
         }
     }
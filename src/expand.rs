@@ -1,4 +1,5 @@
 use super::flatten;
+use super::lint;
 use super::Error;
 use crate::ast;
 use crate::name::Name;
@@ -61,13 +62,25 @@ impl Stack {
         match format!("{}", name).as_str() {
             "len" | "theory" | "safe" | "nullterm" => {
                 if self.stack.len() > 1 {
-                    return Err(Error::new(
-                        format!("redeclaration of builtin theory '{}'", name),
-                        vec![(
-                            loc.clone(),
-                            "this declaration would shadow a builtin".to_string(),
-                        )],
-                    ));
+                    let message = format!("redeclaration of builtin theory '{}'", name);
+                    // an error rather than a lint emitted as error, so expansion stops here
+                    if lint::level("shadowed_builtin", &loc) == lint::Level::Deny {
+                        return Err(Error::new(
+                            "E0220",
+                            message,
+                            vec![(
+                                loc.clone(),
+                                "this declaration would shadow a builtin [shadowed_builtin]".to_string(),
+                            )],
+                        ));
+                    }
+                    lint::emit(
+                        "shadowed_builtin",
+                        "E0220",
+                        &loc,
+                        message,
+                        &[(loc.clone(), "this declaration would shadow a builtin")],
+                    );
                 }
             }
             _ => {}
@@ -78,6 +91,7 @@ impl Stack {
                 && complete == &flatten::TypeComplete::Complete
             {
                 return Err(Error::new(
+                    "E0219",
                     format!("redeclaration of local name '{}'", name),
                     vec![
                         (
@@ -116,6 +130,7 @@ impl Stack {
                     if let ast::Tail::Dynamic(_) = tail {
                     } else {
                         return Err(Error::new(
+                            "E0610",
                             format!("undeclared nested tail"),
                             vec![(
                                 field.loc.clone(),
@@ -132,6 +147,7 @@ impl Stack {
                             }
                             other => {
                                 return Err(Error::new(
+                                    "E0611",
                                     format!("unavailable type used as tail"),
                                     vec![(
                                         field.loc.clone(),
@@ -142,6 +158,7 @@ impl Stack {
                         }
                     } else {
                         return Err(Error::new(
+                            "E0612",
                             format!("flat type used as tail"),
                             vec![(
                                 field.loc.clone(),
@@ -156,6 +173,7 @@ impl Stack {
                 ast::Tail::Static(_, _) => {
                     if tail != &ast::Tail::None {
                         return Err(Error::new(
+                            "E0613",
                             format!("struct declared as having a tail, but has no tail"),
                             vec![(field.loc.clone(), format!("this is not a tail"))],
                         ));
@@ -167,6 +185,7 @@ impl Stack {
                     } else {
                         if tail != &ast::Tail::None {
                             return Err(Error::new(
+                                "E0613",
                                 format!("struct declared as having a tail, but has no tail"),
                                 vec![(field.loc.clone(), format!("this is not a tail"))],
                             ));
@@ -175,6 +194,7 @@ impl Stack {
                 }
                 ast::Tail::Bind(_, _) => {
                     return Err(Error::new(
+                        "E0614",
                         format!("tail sized binding on struct declaration is invalid"),
                         vec![(
                             field.loc.clone(),
@@ -562,14 +582,15 @@ impl Stack {
                             tags.insert("mut".to_string(), String::new(), loc.clone());
                         }
 
-                        if array.is_some() {
+                        if !matches!(array, ast::Array::None) {
                             return Err(Error::new(
+                                "E0327",
                                 format!("new stack initialization cannot be array"),
                                 vec![(loc.clone(), "this new statement is invalid".to_string())],
                             ));
                         }
                         if assign.is_none() {
-                            return Err(Error::new(format!("new stack initialization requires function call to constructor"), vec![
+                            return Err(Error::new("E0328", format!("new stack initialization requires function call to constructor"), vec![
                                 (loc.clone(), "this new statement is uninitialized".to_string()),
                             ]));
                         }
@@ -593,7 +614,7 @@ impl Stack {
                                         for (n, arg) in args.iter().enumerate() {
                                             if let Some(v) = arg.tags.get("new") {
                                                 for (_,v) in v {
-                                                    return Err(Error::new(format!("invalid use of new tag"), vec![
+                                                    return Err(Error::new("E0329", format!("invalid use of new tag"), vec![
                                                                           (v.clone(), "a local scope variable cannot be \"new\". you probably wanted the tag on the pointer".to_string()),
                                                     ]));
                                                 }
@@ -608,19 +629,19 @@ impl Stack {
                                             }
 
                                             if ! (n == args.len() - 1 || n == 0) {
-                                                return Err(Error::new(format!("new must be first or last argument"), vec![
+                                                return Err(Error::new("E0330", format!("new must be first or last argument"), vec![
                                                     (arg.loc.clone(), "cannot insert new into the middle or arglist".to_string()),
                                                 ]));
                                             }
 
                                             if nuargpos.is_some() {
-                                                return Err(Error::new(format!("only one argument can be new"), vec![
+                                                return Err(Error::new("E0331", format!("only one argument can be new"), vec![
                                                     (arg.loc.clone(), "second new argument".to_string()),
                                                 ]));
                                             }
 
                                             if arg.typed.ptr.len() != 1 {
-                                                return Err(Error::new(format!("new stack initialization requires function call to constructor"), vec![
+                                                return Err(Error::new("E0328", format!("new stack initialization requires function call to constructor"), vec![
                                                     (loc.clone(), "incorrect new argument pointer length".to_string()),
                                                 ]));
                                             }
@@ -645,23 +666,23 @@ impl Stack {
                                         }
 
                                     } else {
-                                        return Err(Error::new(format!("new stack initialization requires function call to constructor"), vec![
+                                        return Err(Error::new("E0328", format!("new stack initialization requires function call to constructor"), vec![
                                             (loc.clone(), "this new statement is invalid".to_string()),
                                         ]));
                                     }
                                 } else {
-                                    return Err(Error::new(format!("new stack initialization requires function call to constructor"), vec![
+                                    return Err(Error::new("E0328", format!("new stack initialization requires function call to constructor"), vec![
                                         (loc.clone(), "this new statement is invalid".to_string()),
                                     ]));
                                 }
                             } else {
-                                return Err(Error::new(format!("new stack initialization requires function call to constructor"), vec![
+                                return Err(Error::new("E0328", format!("new stack initialization requires function call to constructor"), vec![
                                     (loc.clone(), format!("this new statement is {:?}", fname)),
                                 ]));
                             }
 
                             if nuargpos.is_none() {
-                                return Err(Error::new(format!("new stack initialization requires function call to constructor"), vec![
+                                return Err(Error::new("E0328", format!("new stack initialization requires function call to constructor"), vec![
                                     (loc.clone(), "function has no argument with a \"new\" pointer".to_string()),
                                 ]));
                             }
@@ -681,7 +702,7 @@ impl Stack {
                                 args.push(nua);
                             }
                         } else {
-                            return Err(Error::new(format!("new stack initialization requires function call to constructor"), vec![
+                            return Err(Error::new("E0328", format!("new stack initialization requires function call to constructor"), vec![
                                 (loc.clone(), "this new statement is invalid".to_string()),
                             ]));
                         }
@@ -706,7 +727,7 @@ impl Stack {
                         len += 1;
                     } else {
                         let mut typed = typed.clone();
-                        if array.is_some() {
+                        if !matches!(array, ast::Array::None) {
                             typed.ptr.push(ast::Pointer {
                                 loc: loc.clone(),
                                 tags: Tags::new(),
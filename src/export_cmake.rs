@@ -1,33 +1,159 @@
+use super::emitter_common;
 use super::make::Make;
-use std::fs;
-use std::io::Write;
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::path::Path;
 
-pub fn export(make: Make) {
-    let pdir_ = format!("target/cmake/{}/", make.artifact.name);
-    let pdir = std::path::Path::new(&pdir_);
+// a target that can be used with add_subdirectory(target/cmake/<artifact>) and linked against.
+// artifacts with a main module like the default exe artifact become an executable
+pub fn export(mut make: Make, ldflags: Vec<String>) {
+    let td = super::project::target_dir();
+    let pdir = td.join("cmake").join(&make.artifact.name);
     std::fs::create_dir_all(&pdir).unwrap();
+    let pdir = pdir.canonicalize().unwrap();
+    let root = std::env::current_dir().expect("current dir");
 
-    let p = pdir.join("CMakeLists.txt");
-    let mut f = fs::File::create(&p).expect(&format!("cannot create {:?}", p));
-
-    write!(
-        f,
-        r#"
-include_directories("${{CMAKE_CURRENT_LIST_DIR}}/../../{}/")
-set({}_SOURCES
-"#,
-        make.stage, make.artifact.name
-    )
-    .unwrap();
+    for step in &make.steps {
+        make.lflags.extend(step.lflags.iter().cloned());
+    }
 
+    // include directories go to target_include_directories, the rest are compile options
+    let mut includes = BTreeSet::new();
+    let mut sources = Vec::new();
     for step in &make.steps {
-        write!(
+        // zz adds "-c source -o object" last, cmake does that itself
+        let n = step.args.len().saturating_sub(4);
+        assert!(
+            step.args.get(n).map(|v| v == "-c").unwrap_or(false),
+            "ICE: unexpected compiler args {:?}",
+            step.args
+        );
+
+        let mut options = Vec::new();
+        let mut args = step.args[..n].iter().peekable();
+        while let Some(arg) = args.next() {
+            if make.pkgflags.contains(arg) {
+                continue;
+            }
+            if arg == "-I" {
+                if let Some(path) = args.next_if(|v| !v.starts_with('-')) {
+                    includes.insert(path.clone());
+                }
+                continue;
+            }
+            if let Some(path) = arg.strip_prefix("-I") {
+                includes.insert(path.to_string());
+                continue;
+            }
+            options.push(arg.clone());
+        }
+
+        let source = emitter_common::path_rel(&pdir, root.join(&step.source));
+        sources.push((format!("${{CMAKE_CURRENT_LIST_DIR}}/{}", source.to_string_lossy()), options));
+    }
+
+    // options every source has are set on the target, the others on the source
+    let common: Vec<String> = match sources.first() {
+        None => Vec::new(),
+        Some((_, first)) => first
+            .iter()
+            .filter(|o| sources.iter().all(|(_, options)| options.contains(o)))
+            .cloned()
+            .collect(),
+    };
+
+    let name = &make.artifact.name;
+    let exe = make.artifact.main.ends_with("::main");
+
+    let mut f = String::new();
+    writeln!(f, "# generated by zz. do not edit").unwrap();
+    writeln!(f, "cmake_minimum_required(VERSION 3.13)").unwrap();
+    writeln!(f).unwrap();
+
+    if !make.pkgconfig.is_empty() {
+        writeln!(f, "find_package(PkgConfig REQUIRED)").unwrap();
+        for pkg in &make.pkgconfig {
+            writeln!(f, "pkg_check_modules({} REQUIRED IMPORTED_TARGET {})", pkg_var(pkg), quote(pkg)).unwrap();
+        }
+        writeln!(f).unwrap();
+    }
+
+    if exe {
+        writeln!(f, "add_executable({}", quote(name)).unwrap();
+    } else {
+        writeln!(f, "add_library({}", quote(name)).unwrap();
+    }
+    for (source, _) in &sources {
+        writeln!(f, "    {}", quote(source)).unwrap();
+    }
+    writeln!(f, ")").unwrap();
+    writeln!(f).unwrap();
+
+    for (source, options) in &sources {
+        let own: Vec<&str> = options.iter().filter(|o| !common.contains(o)).map(|o| o.as_str()).collect();
+        if own.is_empty() {
+            continue;
+        }
+        writeln!(
             f,
-            "     ${{CMAKE_CURRENT_LIST_DIR}}/../../../{}\n",
-            step.source.to_string_lossy()
+            "set_source_files_properties({} PROPERTIES COMPILE_OPTIONS {})",
+            quote(source),
+            quote(&own.join(";"))
         )
         .unwrap();
     }
 
-    write!(f, ")\n").unwrap();
+    writeln!(f, "target_compile_options({} PRIVATE", quote(name)).unwrap();
+    for option in &common {
+        writeln!(f, "    {}", quote(option)).unwrap();
+    }
+    writeln!(f, ")").unwrap();
+
+    // the exported header is in target/include and may include headers from the cincludes
+    writeln!(f, "target_include_directories({} PUBLIC", quote(name)).unwrap();
+    writeln!(f, "    {}", quote(&dir(&pdir, &root, &td.join("include")))).unwrap();
+    for include in &includes {
+        writeln!(f, "    {}", quote(&dir(&pdir, &root, Path::new(include)))).unwrap();
+    }
+    writeln!(f, ")").unwrap();
+
+    writeln!(f, "target_link_options({} PUBLIC", quote(name)).unwrap();
+    for flag in &ldflags {
+        writeln!(f, "    {}", quote(flag)).unwrap();
+    }
+    writeln!(f, ")").unwrap();
+
+    writeln!(f, "target_link_libraries({} PUBLIC", quote(name)).unwrap();
+    for pkg in &make.pkgconfig {
+        writeln!(f, "    PkgConfig::{}", pkg_var(pkg)).unwrap();
+    }
+    for flag in make.lflags.iter().filter(|v| !make.pkgflags.contains(*v)) {
+        writeln!(f, "    {}", quote(flag)).unwrap();
+    }
+    writeln!(f, ")").unwrap();
+
+    super::builddb::write_if_changed(&pdir.join("CMakeLists.txt"), f.as_bytes());
+}
+
+// paths in the project move with it, others like /usr/include stay where they are
+fn dir(pdir: &Path, root: &Path, path: &Path) -> String {
+    let path = root.join(path);
+    if !path.starts_with(root) {
+        return path.to_string_lossy().to_string();
+    }
+    let rel = pathdiff::diff_paths(&path, pdir).expect("pathdiff");
+    format!("${{CMAKE_CURRENT_LIST_DIR}}/{}", rel.to_string_lossy())
+}
+
+fn pkg_var(pkg: &str) -> String {
+    let pkg: String = pkg
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect();
+    format!("ZZ_PKG_{}", pkg)
+}
+
+// variables like ${CMAKE_CURRENT_LIST_DIR} are still expanded
+fn quote(v: &str) -> String {
+    format!("\"{}\"", v.replace('\\', "\\\\").replace('"', "\\\""))
 }
@@ -0,0 +1,193 @@
+use super::make::Make;
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+// a subproject in target/meson/<artifact>, with a wrap file next to it. meson doesn't let a
+// subproject reach outside of its directory, so the c files and headers are copied in,
+// in the same layout as in target/ because the generated c includes them by relative path.
+// include paths of the project go to project/, in the layout they have in the project
+pub fn export(mut make: Make, ldflags: Vec<String>) {
+    let td = super::project::target_dir();
+    let td = td.canonicalize().unwrap_or(td);
+    let sdir = td.join("meson");
+    let pdir = sdir.join(&make.artifact.name);
+    std::fs::create_dir_all(&pdir).unwrap();
+    let root = std::env::current_dir().expect("current dir");
+
+    for step in &make.steps {
+        make.lflags.extend(step.lflags.iter().cloned());
+    }
+
+    copy_headers(&td.join("c"), &pdir.join("c"), &td);
+    copy_headers(&td.join("include"), &pdir.join("include"), &td);
+
+    let mut includes = BTreeSet::new();
+    // sources are compiled with the options zz would have used,
+    // files that have different ones go into a library of their own
+    let mut libraries: Vec<(bool, Vec<String>, Vec<PathBuf>)> = Vec::new();
+    for step in &make.steps {
+        // zz adds "-c source -o object" last, meson does that itself
+        let n = step.args.len().saturating_sub(4);
+        assert!(
+            step.args.get(n).map(|v| v == "-c").unwrap_or(false),
+            "ICE: unexpected compiler args {:?}",
+            step.args
+        );
+
+        let mut options = Vec::new();
+        let mut args = step.args[..n].iter().peekable();
+        while let Some(arg) = args.next() {
+            if make.pkgflags.contains(arg) {
+                continue;
+            }
+            if arg == "-I" {
+                if let Some(path) = args.next_if(|v| !v.starts_with('-')) {
+                    includes.insert(root.join(path));
+                }
+                continue;
+            }
+            if let Some(path) = arg.strip_prefix("-I") {
+                includes.insert(root.join(path));
+                continue;
+            }
+            options.push(arg.clone());
+        }
+
+        let source = root.join(&step.source);
+        let rel = match source.canonicalize().unwrap_or_else(|_| source.clone()).strip_prefix(&td) {
+            Ok(v) => v.to_path_buf(),
+            Err(_) => PathBuf::from("extra").join(source.file_name().unwrap()),
+        };
+        copy(&source, &pdir.join(&rel));
+        match libraries.iter_mut().find(|(cxx, v, _)| *cxx == step.cxx && *v == options) {
+            Some((_, _, sources)) => sources.push(rel),
+            None => libraries.push((step.cxx, options, vec![rel])),
+        }
+    }
+
+    // include paths from target/ and from the project are copied in like the sources,
+    // only the ones outside of both, like /usr/include, are used from where they are
+    let includes = includes
+        .into_iter()
+        .map(|include| {
+            let include = include.canonicalize().unwrap_or(include);
+            let rel = if let Ok(rel) = include.strip_prefix(&td) {
+                rel.to_path_buf()
+            } else if let Ok(rel) = include.strip_prefix(&root) {
+                Path::new("project").join(rel).components().collect()
+            } else {
+                return include.to_string_lossy().to_string();
+            };
+            // meson wants include directories to exist, even without any headers
+            std::fs::create_dir_all(pdir.join(&rel)).unwrap();
+            copy_headers(&include, &pdir.join(&rel), &td);
+            rel.to_string_lossy().to_string()
+        })
+        .collect::<BTreeSet<String>>();
+
+    let name = &make.artifact.name;
+    let id = ident(name);
+    let languages = if make.steps.iter().any(|step| step.cxx) { "['c', 'cpp']" } else { "'c'" };
+
+    let mut f = String::new();
+    writeln!(f, "# generated by zz. do not edit").unwrap();
+    writeln!(f, "project({}, {}, meson_version: '>=0.54.0')", quote(name), languages).unwrap();
+    writeln!(f).unwrap();
+
+    writeln!(f, "{}_includes = include_directories(", id).unwrap();
+    writeln!(f, "  'include',").unwrap();
+    for include in includes.iter().filter(|v| *v != "include") {
+        writeln!(f, "  {},", quote(include)).unwrap();
+    }
+    writeln!(f, ")").unwrap();
+    writeln!(f).unwrap();
+
+    writeln!(f, "{}_deps = [", id).unwrap();
+    for pkg in &make.pkgconfig {
+        writeln!(f, "  dependency({}),", quote(pkg)).unwrap();
+    }
+    writeln!(f, "]").unwrap();
+    writeln!(f).unwrap();
+
+    let mut libs = Vec::new();
+    for (i, (cxx, options, sources)) in libraries.iter().enumerate() {
+        let lib = if i == 0 { name.to_string() } else { format!("{}_{}", name, i) };
+        writeln!(f, "{}_lib = static_library({},", ident(&lib), quote(&lib)).unwrap();
+        writeln!(f, "  files(").unwrap();
+        for source in sources {
+            writeln!(f, "    {},", quote(&source.to_string_lossy())).unwrap();
+        }
+        writeln!(f, "  ),").unwrap();
+        writeln!(f, "  {}: [", if *cxx { "cpp_args" } else { "c_args" }).unwrap();
+        for option in options {
+            writeln!(f, "    {},", quote(option)).unwrap();
+        }
+        writeln!(f, "  ],").unwrap();
+        writeln!(f, "  include_directories: {}_includes,", id).unwrap();
+        writeln!(f, "  dependencies: {}_deps,", id).unwrap();
+        writeln!(f, ")").unwrap();
+        writeln!(f).unwrap();
+        libs.push(format!("{}_lib", ident(&lib)));
+    }
+
+    // consumers link the compiled library and only see the exported headers
+    writeln!(f, "{}_dep = declare_dependency(", id).unwrap();
+    writeln!(f, "  link_with: [{}],", libs.join(", ")).unwrap();
+    writeln!(f, "  include_directories: include_directories('include'),").unwrap();
+    writeln!(f, "  link_args: [").unwrap();
+    for flag in ldflags.iter().chain(make.lflags.iter().filter(|v| !make.pkgflags.contains(*v))) {
+        writeln!(f, "    {},", quote(flag)).unwrap();
+    }
+    writeln!(f, "  ],").unwrap();
+    writeln!(f, "  dependencies: {}_deps,", id).unwrap();
+    writeln!(f, ")").unwrap();
+    writeln!(f).unwrap();
+    writeln!(f, "meson.override_dependency({}, {}_dep)", quote(name), id).unwrap();
+
+    super::builddb::write_if_changed(&pdir.join("meson.build"), f.as_bytes());
+
+    // the directory is already there, so the wrap never downloads anything
+    let wrap = format!("[wrap-file]\ndirectory = {}\n\n[provide]\n{} = {}_dep\n", name, name, id);
+    super::builddb::write_if_changed(&sdir.join(format!("{}.wrap", name)), wrap.as_bytes());
+}
+
+fn copy(from: &Path, to: &Path) {
+    let content = std::fs::read(from).unwrap_or_else(|e| panic!("cannot read {:?}: {}", from, e));
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent).unwrap();
+    }
+    super::builddb::write_if_changed(to, &content);
+}
+
+// everything but what's in target/, which an include path of the whole project would copy into itself
+fn copy_headers(from: &Path, to: &Path, td: &Path) {
+    let entries = match std::fs::read_dir(from) {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+    for entry in entries {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            if path == td {
+                continue;
+            }
+            copy_headers(&path, &to.join(path.file_name().unwrap()), td);
+        } else if path.extension().map(|v| v == "h" || v == "hpp").unwrap_or(false) {
+            copy(&path, &to.join(path.file_name().unwrap()));
+        }
+    }
+}
+
+// variable names are built from the artifact name, which meson only takes if it is an identifier
+fn ident(v: &str) -> String {
+    let mut id: String = v.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+    if !id.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        id.insert(0, '_');
+    }
+    id
+}
+
+fn quote(v: &str) -> String {
+    format!("'{}'", v.replace('\\', "\\\\").replace('\'', "\\'"))
+}
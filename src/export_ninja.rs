@@ -0,0 +1,138 @@
+use super::compile_commands::quote;
+use super::emitter_common;
+use super::make::Make;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+// a self contained build.ninja with the same flags zz would have used itself.
+// everything is relative to target/ninja/<artifact>, which is where ninja runs the commands
+pub fn export(mut make: Make, ldflags: Vec<String>) {
+    let td = super::project::target_dir();
+    let pdir = td.join("ninja").join(&make.artifact.name);
+    std::fs::create_dir_all(&pdir).unwrap();
+    let pdir = pdir.canonicalize().unwrap();
+    let root = std::env::current_dir().expect("current dir");
+
+    for step in &make.steps {
+        make.lflags.extend(step.lflags.iter().cloned());
+    }
+    let has_cxx = make.steps.iter().any(|step| step.cxx);
+
+    let mut f = String::new();
+    writeln!(f, "# generated by zz. do not edit").unwrap();
+    writeln!(f, "ninja_required_version = 1.3").unwrap();
+    writeln!(f).unwrap();
+    writeln!(f, "cc = {}", escape(&make.cc)).unwrap();
+    writeln!(f, "cxx = {}", escape(&make.cxx)).unwrap();
+    writeln!(f, "ar = {}", escape(&make.ar)).unwrap();
+    writeln!(f, "ld = {}", escape(if has_cxx { &make.cxx } else { &make.cc })).unwrap();
+    writeln!(f, "ldflags = {}", args(&pdir, &root, &ldflags)).unwrap();
+    writeln!(f, "libs = {}", args(&pdir, &root, &make.lflags)).unwrap();
+    writeln!(
+        f,
+        r#"
+rule cc
+  command = $cc -MD -MF $out.d $cflags -c $in -o $out
+  description = cc $in
+  depfile = $out.d
+  deps = gcc
+
+rule cxx
+  command = $cxx -MD -MF $out.d $cflags -c $in -o $out
+  description = cxx $in
+  depfile = $out.d
+  deps = gcc
+
+rule ar
+  command = rm -f $out && $ar rcs $out $in
+  description = ar $out
+
+rule link
+  command = $ld $ldflags $in $libs -o $out
+  description = ld $out
+"#
+    )
+    .unwrap();
+
+    let mut objs = Vec::new();
+    for step in &make.steps {
+        // zz adds "-c source -o object" last, ninja passes $in and $out instead
+        let n = step.args.len().saturating_sub(4);
+        assert!(
+            step.args.get(n).map(|v| v == "-c").unwrap_or(false),
+            "ICE: unexpected compiler args {:?}",
+            step.args
+        );
+
+        let object = Path::new(&step.outp).file_name().unwrap().to_string_lossy().to_string();
+        let object = format!("obj/{}", object);
+        let source = emitter_common::path_rel(&pdir, root.join(&step.source));
+        writeln!(
+            f,
+            "build {}: {} {}",
+            escape_path(&object),
+            if step.cxx { "cxx" } else { "cc" },
+            escape_path(&source.to_string_lossy())
+        )
+        .unwrap();
+        writeln!(f, "  cflags = {}", args(&pdir, &root, &step.args[..n])).unwrap();
+        objs.push(escape_path(&object));
+    }
+
+    let name = &make.artifact.name;
+    writeln!(f).unwrap();
+    writeln!(f, "build lib{}.a: ar {}", escape_path(name), objs.join(" ")).unwrap();
+    // the objects are only position independent when the stage compiles them with -fPIC
+    if make.stage.pic {
+        writeln!(f, "build lib{}.so: link {}", escape_path(name), objs.join(" ")).unwrap();
+        writeln!(f, "  ldflags = $ldflags -shared").unwrap();
+    }
+    writeln!(f, "build {}: link {}", escape_path(name), objs.join(" ")).unwrap();
+    writeln!(f).unwrap();
+    writeln!(f, "default lib{}.a", escape_path(name)).unwrap();
+
+    super::builddb::write_if_changed(&pdir.join("build.ninja"), f.as_bytes());
+}
+
+// include paths are relative to the project, the build runs in the ninja directory
+fn args(pdir: &Path, root: &Path, args: &[String]) -> String {
+    let mut r = Vec::new();
+    let mut path_follows = false;
+    for arg in args {
+        let mut arg = arg.clone();
+        if path_follows && !arg.starts_with('-') {
+            arg = rebase(pdir, root, &arg);
+        } else {
+            for flag in &["-I", "-L", "-isystem", "-iquote", "-include"] {
+                if arg.len() > flag.len() && arg.starts_with(flag) && !arg[flag.len()..].starts_with('-') {
+                    arg = format!("{}{}", flag, rebase(pdir, root, &arg[flag.len()..]));
+                    break;
+                }
+            }
+        }
+        path_follows = ["-I", "-L", "-isystem", "-iquote", "-include"].contains(&arg.as_str());
+        r.push(escape(&quote(&arg)));
+    }
+    r.join(" ")
+}
+
+fn rebase(pdir: &Path, root: &Path, path: &str) -> String {
+    let path = PathBuf::from(path);
+    if path.is_absolute() {
+        return path.to_string_lossy().to_string();
+    }
+    pathdiff::diff_paths(root.join(path), pdir)
+        .expect("pathdiff")
+        .to_string_lossy()
+        .to_string()
+}
+
+// variable values
+fn escape(v: &str) -> String {
+    v.replace('$', "$$")
+}
+
+// paths in build statements
+fn escape_path(v: &str) -> String {
+    v.replace('$', "$$").replace(' ', "$ ").replace(':', "$:")
+}
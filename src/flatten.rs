@@ -120,8 +120,11 @@ fn stm_deps(cr: &mut Collector, stm: &ast::Statement) -> Vec<(Name, TypeComplete
             }
             deps
         }
-        ast::Statement::For { e1, e2, e3, body, ..} => {
+        ast::Statement::For { e1, e2, e3, invariants, body, ..} => {
             let mut deps = Vec::new();
+            for s in invariants {
+                deps.extend(expr_deps(cr, s));
+            }
             for s in e1 {
                 deps.extend(stm_deps(cr, s));
             }
@@ -134,9 +137,12 @@ fn stm_deps(cr: &mut Collector, stm: &ast::Statement) -> Vec<(Name, TypeComplete
             deps.extend(block_deps(cr, body));
             deps
         }
-        ast::Statement::While { expr, body } => {
+        ast::Statement::While { expr, invariants, body } => {
             let mut deps = Vec::new();
             deps.extend(expr_deps(cr, expr));
+            for s in invariants {
+                deps.extend(expr_deps(cr, s));
+            }
             deps.extend(block_deps(cr, body));
             deps
         }
@@ -163,10 +169,8 @@ fn stm_deps(cr: &mut Collector, stm: &ast::Statement) -> Vec<(Name, TypeComplete
             ..
         } => {
             let mut deps = Vec::new();
-            if let Some(array) = &array {
-                if let Some(array) = &array {
-                    deps.extend(expr_deps(cr, array));
-                }
+            if let ast::Array::Sized(array) = &array {
+                deps.extend(expr_deps(cr, array));
             }
             if let Some(assign) = &assign {
                 deps.extend(expr_deps(cr, assign));
@@ -350,13 +354,14 @@ pub fn flatten(md: &ast::Module, all_modules: &HashMap<Name, loader::Module>, ex
 
             if !name.is_absolute() {
                 emit_error(
+                    "E0218",
                     format!("undefined type '{}' during flatten of '{}'", name, md.name),
                     &[(
                         loc.clone(),
                         &format!("type '{}' unavailable in this scope", name),
                     )],
                 );
-                std::process::exit(9);
+                super::exit(9);
             }
 
             let mut module_name = name.clone();
@@ -368,6 +373,7 @@ pub fn flatten(md: &ast::Module, all_modules: &HashMap<Name, loader::Module>, ex
                 local = ext.ext.lock().unwrap().get(&module_name).cloned();
                 if local.is_none() {
                     emit_error(
+                        "E0909",
                         format!(
                             "ICE ext module {} unavable or somehow we're missing local {}",
                             module_name, local_name
@@ -377,7 +383,7 @@ pub fn flatten(md: &ast::Module, all_modules: &HashMap<Name, loader::Module>, ex
                             &format!("type '{}' unavailable in this scope", name),
                         )],
                     );
-                    std::process::exit(9);
+                    super::exit(9);
                 }
             } else {
                 let module = loop {
@@ -400,13 +406,14 @@ pub fn flatten(md: &ast::Module, all_modules: &HashMap<Name, loader::Module>, ex
                                 }
 
                                 emit_error(
+                                    "E0910",
                                     format!("ice: unknown module {}", module_name),
                                     &[(
                                         loc.clone(),
                                         &format!("type '{}' unavailable in this scope", name),
                                     )],
                                 );
-                                std::process::exit(9);
+                                super::exit(9);
                             }
                             Some(loader::Module::C(_)) => panic!("not implemented"),
                             Some(loader::Module::ZZ(ast)) => ast,
@@ -439,13 +446,14 @@ pub fn flatten(md: &ast::Module, all_modules: &HashMap<Name, loader::Module>, ex
                 Some(v) => v,
                 None => {
                     emit_error(
+                        "E0212",
                         format!("module {} does not contain {}", module_name, local_name),
                         &[(
                             loc.clone(),
                             &format!("type '{}' unavailable in this scope", name),
                         )],
                     );
-                    std::process::exit(9);
+                    super::exit(9);
                 }
             };
 
@@ -515,10 +523,11 @@ pub fn flatten(md: &ast::Module, all_modules: &HashMap<Name, loader::Module>, ex
                         if arg.name == "self" {
                             if i != 0 {
                                 emit_error(
+                                    "E0109",
                                     format!("self arg must be first"),
                                     &[(arg.loc.clone(), "self argument in wrong position")],
                                 );
-                                std::process::exit(9);
+                                super::exit(9);
                             }
                             if let ast::Type::Other(name) = &arg.typed.t {
                                 let mut ns = module_name.clone();
@@ -802,7 +811,10 @@ pub fn flatten(md: &ast::Module, all_modules: &HashMap<Name, loader::Module>, ex
     let mut sorted = Vec::new();
     let mut sorted_mark = HashMap::new();
     let mut more = HashSet::new();
-    for (name, complete) in &thisobject.0 {
+    // in name order, so an unchanged module is emitted as the same c
+    let mut roots: Vec<(&Name, &TypeComplete)> = thisobject.0.iter().collect();
+    roots.sort_by(|a, b| a.0.cmp(b.0));
+    for (name, complete) in roots {
         sort_visit(
             &mut sorted,
             &mut sorted_mark,
@@ -815,7 +827,9 @@ pub fn flatten(md: &ast::Module, all_modules: &HashMap<Name, loader::Module>, ex
             &mut more,
         );
     }
-    for name in std::mem::replace(&mut more, HashSet::new()) {
+    let mut discovered: Vec<Name> = std::mem::take(&mut more).into_iter().collect();
+    discovered.sort();
+    for name in discovered {
         sort_visit(
             &mut sorted,
             &mut sorted_mark,
@@ -913,6 +927,7 @@ fn sort_visit(
                     estack.push((here.clone(), format!("type incomplete in this scope")));
                 }
                 emit_error(
+                    "E0326",
                     format!("recursive type {} will never complete", name),
                     &estack,
                 );
@@ -925,7 +940,7 @@ fn sort_visit(
                 for (name, _) in unsorted {
                     debug!("  {}", name);
                 }
-                std::process::exit(10);
+                super::exit(10);
             }
         },
     };
@@ -0,0 +1,688 @@
+use super::parser::{Rule, ZZParser};
+use pest::iterators::Pair;
+use pest::Parser;
+use std::path::{Path, PathBuf};
+
+// zz fmt reprints every token of a file in one canonical layout.
+// the grammar drops comments as silent whitespace, so they are picked up again
+// from the source text between the tokens pest does report.
+// line breaks within expressions are kept, everything else is decided here.
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Kind {
+    Code,
+    LineComment,
+    BlockComment,
+    Doc,
+}
+
+#[derive(Debug)]
+struct Token {
+    text: String,
+    kind: Kind,
+    // rules of all pairs containing this token, outermost first
+    rules: Vec<Rule>,
+    // depths of the pairs this token is the first token of
+    starts: Vec<usize>,
+    // the outermost atomic pair this token is in, by start offset
+    atomic: Option<usize>,
+    // line breaks in the source between this and the previous token
+    newlines: usize,
+}
+
+impl Token {
+    fn rule(&self) -> Option<Rule> {
+        self.rules.last().cloned()
+    }
+
+    fn parent(&self) -> Option<Rule> {
+        if self.rules.len() < 2 {
+            return None;
+        }
+        self.rules.get(self.rules.len() - 2).cloned()
+    }
+
+    fn is(&self, text: &str, rule: Rule) -> bool {
+        self.text == text && self.rule() == Some(rule)
+    }
+
+    fn is_comment(&self) -> bool {
+        self.kind != Kind::Code
+    }
+
+    // starts a pair of one of these rules inside a pair of the other
+    fn starts_in(&self, rules: &[Rule], within: &[Rule]) -> bool {
+        self.starts
+            .iter()
+            .any(|d| *d > 0 && rules.contains(&self.rules[*d]) && within.contains(&self.rules[*d - 1]))
+    }
+
+    fn starts_item(&self) -> bool {
+        self.starts.contains(&1) || self.rules.len() == 1
+    }
+
+    fn item(&self) -> Option<Rule> {
+        self.rules.get(1).cloned()
+    }
+}
+
+const BLOCKS: &[Rule] = &[Rule::block, Rule::struct_d, Rule::switch_stm, Rule::ienum, Rule::testcase];
+
+fn is_block_open(t: &Token) -> bool {
+    t.text == "{" && t.atomic.is_none() && t.rule().map(|r| BLOCKS.contains(&r)).unwrap_or(false)
+}
+
+fn is_block_close(t: &Token) -> bool {
+    t.text == "}" && t.atomic.is_none() && t.rule().map(|r| BLOCKS.contains(&r)).unwrap_or(false)
+}
+
+fn is_statement(t: &Token) -> bool {
+    t.starts.iter().any(|d| {
+        *d > 0
+            && t.rules[*d - 1] == Rule::block
+            && t.rules[*d] != Rule::else_stm
+            && t.rules[*d] != Rule::elseif_stm
+    })
+}
+
+fn is_member(t: &Token) -> bool {
+    t.starts_in(&[Rule::struct_f, Rule::doc_comment], &[Rule::struct_d])
+        || t.starts_in(&[Rule::case_stm], &[Rule::switch_stm])
+        || t.starts_in(&[Rule::testfield], &[Rule::testcase])
+        || t.starts_in(&[Rule::enum_i], &[Rule::ienum])
+}
+
+fn is_clause(t: &Token) -> bool {
+    t.starts_in(&[Rule::call_assert, Rule::call_effect, Rule::macrocall], &[Rule::function])
+        || t.starts_in(&[Rule::loop_invariant], &[Rule::while_stm, Rule::for_stm])
+}
+
+fn is_else(t: &Token) -> bool {
+    t.starts_in(&[Rule::else_stm, Rule::elseif_stm], &[Rule::block, Rule::gblock])
+}
+
+// declarations that always get a blank line around them
+fn is_big(item: Option<Rule>) -> bool {
+    match item {
+        Some(Rule::function) | Some(Rule::struct_d) | Some(Rule::ienum) | Some(Rule::imacro)
+        | Some(Rule::testcase) | Some(Rule::flags) => true,
+        _ => false,
+    }
+}
+
+fn is_atomic(rule: Rule) -> bool {
+    match rule {
+        Rule::doc_comment
+        | Rule::string_literal
+        | Rule::string_literal1
+        | Rule::string_literal2
+        | Rule::bool_literal
+        | Rule::char_literal
+        | Rule::number_literal
+        | Rule::int_literal
+        | Rule::hex_literal
+        | Rule::bit_literal
+        | Rule::ident
+        | Rule::qident
+        | Rule::qident_inner
+        | Rule::namespace
+        | Rule::tail
+        | Rule::type_name
+        | Rule::tag_name
+        | Rule::type_name_with_tail
+        | Rule::macro_ident
+        | Rule::cimport
+        | Rule::importname
+        | Rule::syspath
+        | Rule::localpath
+        | Rule::label
+        | Rule::mark_stm
+        | Rule::vararg => true,
+        _ => false,
+    }
+}
+
+// rules that are non-atomic again inside an atomic one
+fn is_nonatomic(rule: Rule) -> bool {
+    match rule {
+        Rule::param_short | Rule::params | Rule::anon_type | Rule::named_type | Rule::ptr | Rule::cblock
+        | Rule::break_stm => true,
+        _ => false,
+    }
+}
+
+// printed exactly as written
+fn is_opaque(rule: Rule) -> bool {
+    match rule {
+        Rule::doc_comment
+        | Rule::string_literal
+        | Rule::char_literal
+        | Rule::number_literal
+        | Rule::qident
+        | Rule::cimport
+        | Rule::cblock
+        | Rule::importname => true,
+        _ => false,
+    }
+}
+
+struct Tokenizer<'a> {
+    source: &'a str,
+    at: usize,
+    newlines: usize,
+    starts: Vec<usize>,
+    tokens: Vec<Token>,
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, pest::error::Error<Rule>> {
+    let mut file = ZZParser::parse(Rule::top_level_declarations, source)?;
+    let mut t = Tokenizer {
+        source,
+        at: 0,
+        newlines: 0,
+        starts: Vec::new(),
+        tokens: Vec::new(),
+    };
+    let mut rules = Vec::new();
+    t.walk(file.next().unwrap(), &mut rules, None);
+    t.gap(source.len(), &[], None);
+    Ok(t.tokens)
+}
+
+impl<'a> Tokenizer<'a> {
+    fn walk(&mut self, pair: Pair<Rule>, rules: &mut Vec<Rule>, atomic: Option<usize>) {
+        let rule = pair.as_rule();
+        let span = pair.as_span();
+        let atomic = if is_nonatomic(rule) {
+            None
+        } else if atomic.is_none() && is_atomic(rule) {
+            Some(span.start())
+        } else {
+            atomic
+        };
+
+        let depth = rules.len();
+        rules.push(rule);
+        self.starts.push(depth);
+
+        let leaf = pair.clone().into_inner().next().is_none();
+        let text = span.as_str();
+        if leaf && !is_opaque(rule) && (BLOCKS.contains(&rule) || text.contains(char::is_whitespace)) {
+            // an empty block, or a keyword with whitespace
+            self.gap(span.end(), rules, atomic);
+        } else if is_opaque(rule) || leaf {
+            if !text.is_empty() {
+                let (text, kind) = match rule {
+                    Rule::importname => (normalize_import(text), Kind::Code),
+                    Rule::doc_comment => (text.trim_end().to_string(), Kind::Doc),
+                    _ => (text.to_string(), Kind::Code),
+                };
+                self.push(text, kind, rules, atomic);
+                // a /// doc comment ends with the newline
+                if span.as_str().ends_with('\n') {
+                    self.newlines = 1;
+                }
+            }
+            self.at = span.end();
+        } else {
+            for inner in pair.into_inner() {
+                self.gap(inner.as_span().start(), rules, atomic);
+                self.walk(inner, rules, atomic);
+            }
+            self.gap(span.end(), rules, atomic);
+        }
+
+        self.starts.retain(|d| *d != depth);
+        rules.pop();
+    }
+
+    fn push(&mut self, text: String, kind: Kind, rules: &[Rule], atomic: Option<usize>) {
+        let starts = std::mem::replace(&mut self.starts, Vec::new());
+        self.tokens.push(Token {
+            text,
+            kind,
+            rules: rules.to_vec(),
+            starts: starts.into_iter().filter(|d| *d < rules.len()).collect(),
+            atomic,
+            newlines: self.newlines,
+        });
+        self.newlines = 0;
+    }
+
+    // the text between two pairs is whitespace, comments and the literals of the grammar
+    fn gap(&mut self, end: usize, rules: &[Rule], atomic: Option<usize>) {
+        if end <= self.at {
+            return;
+        }
+        let text = &self.source[self.at..end];
+        let bytes = text.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            let rest = &text[i..];
+            let c = bytes[i];
+            if c == b'\n' {
+                self.newlines += 1;
+                i += 1;
+            } else if c.is_ascii_whitespace() {
+                i += 1;
+            } else if rest.starts_with("//") {
+                let len = rest.find('\n').unwrap_or(rest.len());
+                self.push(rest[..len].trim_end().to_string(), Kind::LineComment, rules, atomic);
+                i += len;
+            } else if rest.starts_with("/*") {
+                let len = rest.find("*/").map(|e| e + 2).unwrap_or(rest.len());
+                self.push(rest[..len].to_string(), Kind::BlockComment, rules, atomic);
+                i += len;
+            } else if is_word_char(c as char) {
+                let len = rest.find(|c: char| !is_word_char(c)).unwrap_or(rest.len());
+                self.push(rest[..len].to_string(), Kind::Code, rules, atomic);
+                i += len;
+            } else {
+                let len = ["::", "->", "=>", "...", "@{{", "}}@"]
+                    .iter()
+                    .find(|p| rest.starts_with(*p))
+                    .map(|p| p.len())
+                    .unwrap_or(rest.chars().next().unwrap().len_utf8());
+                self.push(rest[..len].to_string(), Kind::Code, rules, atomic);
+                i += len;
+            }
+        }
+        self.at = end;
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+// using foo::{ a,b  as c } becomes using foo::{a, b as c}
+fn normalize_import(text: &str) -> String {
+    let mut out = String::new();
+    let mut chars = text.chars().peekable();
+    let mut space = false;
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            space = true;
+            continue;
+        }
+        if c == ',' {
+            out.push_str(", ");
+            space = false;
+            continue;
+        }
+        if c == '}' && out.ends_with(", ") {
+            out.truncate(out.len() - 2);
+        }
+        if space && c != '}' && !out.ends_with(|p| p == '{' || p == ' ' || p == ':') {
+            out.push(' ');
+        }
+        space = false;
+        out.push(c);
+        let close = match c {
+            '<' => '>',
+            '"' => '"',
+            '(' => ')',
+            _ => continue,
+        };
+        while let Some(c) = chars.next() {
+            out.push(c);
+            if c == close {
+                break;
+            }
+        }
+    }
+    out.trim_end().to_string()
+}
+
+struct Printer {
+    out: String,
+    // the indent of blocks
+    indent: usize,
+    // the indent of the line the current statement started on
+    stmt: usize,
+    // the indent of the current line
+    line: usize,
+    // open brackets, and the indent of the line they were opened on
+    open: Vec<(bool, usize)>,
+    // the function or loop has where, model or invariant clauses
+    clauses: bool,
+}
+
+pub fn format(source: &str) -> Result<String, pest::error::Error<Rule>> {
+    let tokens = tokenize(source)?;
+    let mut p = Printer {
+        out: String::new(),
+        indent: 0,
+        stmt: 0,
+        line: 0,
+        open: Vec::new(),
+        clauses: false,
+    };
+    for i in 0..tokens.len() {
+        p.token(&tokens, i);
+    }
+    let mut out = p.out.trim_end().to_string();
+    out.push('\n');
+    Ok(out)
+}
+
+impl Printer {
+    fn token(&mut self, tokens: &[Token], i: usize) {
+        let cur = &tokens[i];
+
+        if is_block_close(cur) {
+            self.indent = self.indent.saturating_sub(1);
+        }
+
+        if i > 0 {
+            match self.break_before(tokens, i) {
+                Some((blank, indent)) => self.newline(blank, indent),
+                None => {
+                    if space(&tokens[i - 1], cur) {
+                        self.out.push(' ');
+                    }
+                }
+            }
+        }
+        self.out.push_str(&cur.text);
+
+        if cur.kind != Kind::Code || cur.atomic.is_some() {
+            return;
+        }
+        if is_block_open(cur) {
+            self.open.push((true, self.line));
+            self.indent += 1;
+        } else if is_block_close(cur) {
+            self.open.pop();
+        } else {
+            match cur.text.as_str() {
+                "(" | "[" | "{" => self.open.push((false, self.line)),
+                ")" | "]" | "}" => {
+                    self.open.pop();
+                }
+                _ => (),
+            }
+        }
+    }
+
+    // None keeps the token on the current line
+    fn break_before(&mut self, tokens: &[Token], i: usize) -> Option<(bool, usize)> {
+        let cur = &tokens[i];
+        let prev = &tokens[i - 1];
+        let blank = cur.newlines > 1 && !is_block_open(prev);
+        let forced = match prev.kind {
+            Kind::LineComment | Kind::Doc => true,
+            _ => prev.rule() == Some(Rule::cblock),
+        };
+
+        // trailing comments stay where they are
+        if cur.is_comment() && cur.kind != Kind::Doc && cur.newlines == 0 && !forced {
+            return None;
+        }
+
+        if is_block_close(cur) {
+            if is_block_open(prev) {
+                return None;
+            }
+            return self.structural(false, self.indent);
+        }
+
+        if cur.starts_item() {
+            let item = if cur.item().is_none() || cur.item() == Some(Rule::doc_comment) {
+                tokens[i..]
+                    .iter()
+                    .find(|t| !t.is_comment() && t.item() != Some(Rule::doc_comment))
+                    .and_then(|t| t.item())
+            } else {
+                cur.item()
+            };
+            // a trailing comment belongs to the item before it
+            let prev = tokens[..i]
+                .iter()
+                .rev()
+                .find(|t| !(t.kind == Kind::LineComment || t.kind == Kind::BlockComment) || t.newlines > 0)
+                .unwrap_or(prev);
+            let blank = if prev.item().is_none() || prev.item() == Some(Rule::doc_comment) {
+                cur.newlines > 1
+            } else {
+                cur.newlines > 1 || is_big(prev.item()) || is_big(item)
+            };
+            return self.structural(blank, 0);
+        }
+
+        if is_else(cur) && is_block_close(prev) {
+            return None;
+        }
+
+        if is_statement(cur) || is_member(cur) {
+            return self.structural(blank, self.indent);
+        }
+
+        if is_clause(cur) {
+            self.clauses = true;
+            return self.structural(false, self.indent + 1);
+        }
+
+        if is_block_open(cur) {
+            if self.clauses {
+                self.clauses = false;
+                return self.structural(false, self.indent);
+            }
+            if forced {
+                return Some((false, self.indent));
+            }
+            return None;
+        }
+
+        if forced || is_block_open(prev) || cur.newlines > 0 {
+            // comments line up with what follows them
+            let next = tokens[i..].iter().find(|t| !t.is_comment()).unwrap_or(cur);
+            if is_block_close(next) || is_statement(next) || is_member(next) || next.starts_item() {
+                return Some((blank, self.indent));
+            }
+            if is_clause(next) {
+                return Some((blank, self.indent + 1));
+            }
+            if cur.atomic.is_none() {
+                match cur.text.as_str() {
+                    ")" | "]" | "}" => {
+                        if let Some((false, line)) = self.open.last() {
+                            return Some((false, *line));
+                        }
+                    }
+                    "(" if cur.rule() == Some(Rule::theory_impl) => {
+                        return Some((false, self.stmt));
+                    }
+                    _ => (),
+                }
+            }
+            let cont = match self.open.last() {
+                Some((false, line)) => *line + 1,
+                _ => self.stmt + 1,
+            };
+            return Some((blank && cur.is_comment(), cont));
+        }
+
+        None
+    }
+
+    fn structural(&mut self, blank: bool, indent: usize) -> Option<(bool, usize)> {
+        self.stmt = indent;
+        Some((blank, indent))
+    }
+
+    fn newline(&mut self, blank: bool, indent: usize) {
+        let len = self.out.trim_end_matches(' ').len();
+        self.out.truncate(len);
+        self.out.push('\n');
+        if blank {
+            self.out.push('\n');
+        }
+        for _ in 0..indent {
+            self.out.push_str("    ");
+        }
+        self.line = indent;
+    }
+}
+
+fn space(p: &Token, c: &Token) -> bool {
+    // atomic rules don't allow whitespace, other than around "is"
+    if p.atomic.is_some() && p.atomic == c.atomic {
+        return p.is("is", Rule::mark_stm) || c.is("is", Rule::mark_stm);
+    }
+    space_between(p, c) || merges(&p.text, &c.text)
+}
+
+fn space_between(p: &Token, c: &Token) -> bool {
+    if c.is_comment() || p.is_comment() {
+        return true;
+    }
+
+    if c.is(";", Rule::semicolon) && p.text == "for" {
+        return true;
+    }
+    match c.text.as_str() {
+        "," | ";" | ")" | "]" | "::" | ":" => return false,
+        _ => (),
+    }
+    match p.text.as_str() {
+        "(" | "[" | "@" | "#" | "::" => return false,
+        "{" if p.atomic.is_none() && !is_block_open(p) => return false,
+        "{" if c.text == "}" => return false,
+        _ => (),
+    }
+    if c.text == "}" && c.atomic.is_none() && !is_block_close(c) {
+        return false;
+    }
+
+    let tight = |t: &Token| match t.rule() {
+        Some(Rule::ptraccess) | Some(Rule::memberaccess) | Some(Rule::callstart) | Some(Rule::arraystart) => true,
+        _ => false,
+    };
+    if tight(p) || tight(c) {
+        return false;
+    }
+
+    // prefix and postfix operators
+    match p.rule() {
+        Some(Rule::boolnot) | Some(Rule::bitnot) => return false,
+        Some(Rule::increment) | Some(Rule::decrement) if p.parent() == Some(Rule::unarypre) => return false,
+        Some(Rule::deref) | Some(Rule::takeref) if p.text == "*" || p.text == "&" => return false,
+        _ => (),
+    }
+    match c.rule() {
+        Some(Rule::increment) | Some(Rule::decrement) if c.parent() == Some(Rule::unarypost) => return false,
+        // pointers stick to the type
+        Some(Rule::ptr) => return false,
+        _ => (),
+    }
+
+    // no space between a name and its arguments
+    if c.text == "(" {
+        match c.rule() {
+            Some(Rule::function) | Some(Rule::closure) | Some(Rule::theory) | Some(Rule::macrocall)
+            | Some(Rule::imacro) | Some(Rule::unsafe_expr) | Some(Rule::cpp_expr) => return false,
+            _ => (),
+        }
+    }
+    if c.text.starts_with('[') && (c.rule() == Some(Rule::array) || c.rule() == Some(Rule::params)) {
+        return false;
+    }
+    if c.text == "{" && c.rule() == Some(Rule::struct_init) {
+        return false;
+    }
+    if c.text == "+" && c.rule() == Some(Rule::tail) && p.text != "," {
+        return false;
+    }
+
+    // casts
+    let cast = |t: &Token| t.rule() == Some(Rule::cast) || t.rule() == Some(Rule::unsafe_expr);
+    if p.text == ")" && p.rule() == Some(Rule::cast) || (p.text == ">" || p.text == "<") && cast(p) {
+        return false;
+    }
+    if (c.text == "<" || c.text == ">") && cast(c) {
+        return false;
+    }
+
+    true
+}
+
+// two tokens that would read as a different one without a space
+fn merges(a: &str, b: &str) -> bool {
+    let (a, b) = match (a.chars().last(), b.chars().next()) {
+        (Some(a), Some(b)) => (a, b),
+        _ => return false,
+    };
+    if is_word_char(a) && is_word_char(b) {
+        return true;
+    }
+    let pair: String = [a, b].iter().collect();
+    [
+        "::", "->", "=>", "==", "!=", "<=", ">=", "<<", ">>", "&&", "||", "++", "--", "+=", "-=", "|=", "&=", "//",
+        "/*", "*/", "..",
+    ]
+    .contains(&pair.as_str())
+}
+
+// format a file, and make sure the result means the same
+pub fn format_file(path: &Path) -> Result<(String, String), String> {
+    let source = std::fs::read_to_string(path).map_err(|e| format!("cannot read {:?}: {}", path, e))?;
+    let formatted = format(&source).map_err(|e| format!("syntax error\n{}", e.with_path(&path.to_string_lossy())))?;
+
+    let before = tokenize(&source).unwrap();
+    let after = tokenize(&formatted).map_err(|e| format!("formatting {:?} produced a syntax error\n{}", path, e))?;
+    let same = before.len() == after.len()
+        && before
+            .iter()
+            .zip(after.iter())
+            .all(|(a, b)| a.text == b.text && a.kind == b.kind && a.rules == b.rules);
+    if !same {
+        return Err(format!("formatting {:?} would change its meaning. leaving it alone", path));
+    }
+
+    Ok((source, formatted))
+}
+
+// every zz file of the project in the current directory
+pub fn project_files() -> Vec<PathBuf> {
+    let (root, _) = super::project::load_cwd();
+    let mut files = Vec::new();
+    for dir in &["src", "tests"] {
+        if let Ok(entries) = std::fs::read_dir(root.join(dir)) {
+            for entry in entries.filter_map(|e| e.ok()) {
+                let path = entry.path();
+                if path.is_file() && path.extension().map(|e| e == "zz").unwrap_or(false) {
+                    files.push(path);
+                }
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+// returns false if anything was not formatted
+pub fn fmt(files: &[PathBuf], check: bool) -> bool {
+    let mut ok = true;
+    for path in files {
+        match format_file(path) {
+            Err(e) => {
+                error!("{}", e);
+                ok = false;
+            }
+            Ok((source, formatted)) => {
+                if source == formatted {
+                    continue;
+                }
+                if check {
+                    println!("{}", path.to_string_lossy());
+                    ok = false;
+                } else if let Err(e) = std::fs::write(path, formatted) {
+                    error!("cannot write {:?}: {}", path, e);
+                    ok = false;
+                }
+            }
+        }
+    }
+    ok
+}
@@ -9,6 +9,10 @@ extern crate rayon;
 
 pub mod abs;
 pub mod ast;
+pub mod builddb;
+pub mod compile_commands;
+pub mod datamodel;
+pub mod diagnostics;
 pub mod emitter;
 pub mod emitter_docs;
 pub mod emitter_js;
@@ -19,32 +23,90 @@ pub mod emitter_common;
 pub mod expand;
 pub mod export_make;
 pub mod export_cmake;
+pub mod export_ninja;
+pub mod export_meson;
 pub mod export_esp;
 pub mod flatten;
+pub mod fmt;
+pub mod lint;
 pub mod loader;
+pub mod lsp;
 pub mod make;
 pub mod makro;
 pub mod name;
 pub mod parser;
 pub mod pipeline;
+pub mod portfolio;
+pub mod proofcache;
 pub mod project;
 pub mod repos;
+pub mod sarif;
 pub mod smt;
 pub mod symbolic;
+#[cfg(feature = "z3")]
+pub mod z3;
 pub mod mergecc;
 
 use name::Name;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// the language server runs the compiler in process, where an error it can't
+// recover from has to end the analysis rather than the server
+pub static UNWIND_ON_EXIT: AtomicBool = AtomicBool::new(false);
+
+// what the analysis unwinds with instead of exiting
+pub struct Exit(pub i32);
+
+pub fn exit(code: i32) -> ! {
+    builddb::flush();
+    if UNWIND_ON_EXIT.load(Ordering::SeqCst) {
+        std::panic::panic_any(Exit(code));
+    }
+    std::process::exit(code)
+}
+
+// for running the compiler in process. the panic hook stays quiet about an Exit
+pub fn unwind_on_exit() {
+    UNWIND_ON_EXIT.store(true, Ordering::SeqCst);
+    static HOOK: std::sync::Once = std::sync::Once::new();
+    HOOK.call_once(|| {
+        let hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            if info.payload().downcast_ref::<Exit>().is_none() {
+                hook(info);
+            }
+        }));
+    });
+}
+
+// runs f in process, and returns the code if it exits instead
+pub fn until_exit<R>(f: impl FnOnce() -> R) -> Result<R, i32> {
+    unwind_on_exit();
+    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)) {
+        Ok(v) => Ok(v),
+        Err(payload) => match payload.downcast::<Exit>() {
+            Ok(exit) => Err(exit.0),
+            Err(payload) => std::panic::resume_unwind(payload),
+        },
+    }
+}
 
 pub struct Error {
+    // stable code from diagnostics::CODES
+    code: &'static str,
     message: String,
     details: Vec<(ast::Location, String)>,
 }
 
 impl Error {
-    pub fn new(message: String, details: Vec<(ast::Location, String)>) -> Self {
-        Self { message, details }
+    pub fn new(code: &'static str, message: String, details: Vec<(ast::Location, String)>) -> Self {
+        Self { code, message, details }
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.code
     }
 }
 
@@ -61,10 +123,30 @@ pub enum BuildSet {
 pub fn build(buildset: BuildSet, variant: &str, stage: make::Stage, _slow: bool) {
     let (root, mut project) = project::load_cwd();
     std::env::set_current_dir(&root).unwrap();
-    //
-    //
-    let mut searchpaths : Vec<std::path::PathBuf> = repos::index(&project).into_iter().collect();
-
+    let stage = stage.with_variant(variant);
+    if let Some(target) = &project.project.target {
+        match target.parse::<datamodel::DataModel>() {
+            Ok(dm) => dm.set_default(),
+            Err(e) => {
+                error!("{}", e);
+                exit(9);
+            }
+        }
+    }
+    if let Some(solver) = &project.smt.solver {
+        if let Err(e) = smt::SolverKind::set_default(solver) {
+            parser::emit_error(e.code, e.message, &e.details);
+            exit(9);
+        }
+    }
+    if let Err(e) = smt::SolverKind::set_portfolio(&project.smt.portfolio) {
+        parser::emit_error(e.code, e.message, &e.details);
+        exit(9);
+    }
+    if let Err(e) = lint::configure(root.clone(), &project.lints) {
+        error!("{}", e);
+        exit(9);
+    }
     let td = project::target_dir();
     std::fs::create_dir_all(td.join(stage.to_string()).join("c"))
         .expect("create target dir");
@@ -77,6 +159,25 @@ pub fn build(buildset: BuildSet, variant: &str, stage: make::Stage, _slow: bool)
     std::fs::create_dir_all(td.join("include").join("zz"))
         .expect("create target dir");
 
+    let modules = load(&root, &mut project, &stage);
+
+    // every file is parsed before giving up, so all syntax errors are reported at once
+    if parser::SYNTAX_ERRORS.load(std::sync::atomic::Ordering::SeqCst) {
+        exit(9);
+    }
+
+    let pipeline = pipeline::Pipeline::new(project, stage, variant.to_string(), modules);
+    pipeline.build(buildset);
+}
+
+// parses the project's sources, its tests and every dependency's sources
+pub fn load(
+    root: &std::path::Path,
+    project: &mut project::Config,
+    stage: &make::Stage,
+) -> HashMap<Name, loader::Module> {
+    let mut searchpaths : Vec<std::path::PathBuf> = repos::index(project).into_iter().collect();
+
     let project_name = Name(vec![String::new(), project.project.name.clone()]);
     let project_tests_name = Name(vec![
         String::new(),
@@ -91,7 +192,7 @@ pub fn build(buildset: BuildSet, variant: &str, stage: make::Stage, _slow: bool)
             &project.project,
             &project_name,
             &root.join("src"),
-            &stage,
+            stage,
         );
     }
     if root.join("tests").exists() {
@@ -100,7 +201,7 @@ pub fn build(buildset: BuildSet, variant: &str, stage: make::Stage, _slow: bool)
             &project.project,
             &project_tests_name,
             &root.join("tests").canonicalize().unwrap(),
-            &stage,
+            stage,
         );
     }
 
@@ -142,18 +243,17 @@ pub fn build(buildset: BuildSet, variant: &str, stage: make::Stage, _slow: bool)
                         &mut modules,
                         &mut project.project,
                         &mut searchpaths,
-                        &stage,
+                        stage,
                     );
                 }
                 _ => (),
             }
 
-            std::env::set_current_dir(&root).unwrap();
+            std::env::set_current_dir(root).unwrap();
         }
     }
 
-    let pipeline = pipeline::Pipeline::new(project, stage, variant.to_string(), modules);
-    pipeline.build(buildset);
+    modules
 }
 
 fn getdep(
@@ -185,7 +285,7 @@ fn getdep(
                 "dependency \"{}\" not found in any of {:#?}",
                 name, searchpaths
             );
-            std::process::exit(9);
+            exit(9);
         }
     };
 
@@ -0,0 +1,353 @@
+use super::ast;
+use super::parser::{emit_error, emit_warn};
+use super::Error;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Level {
+    Allow,
+    Warn,
+    Deny,
+}
+
+impl std::str::FromStr for Level {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "allow" => Ok(Level::Allow),
+            "warn" => Ok(Level::Warn),
+            "deny" => Ok(Level::Deny),
+            _ => Err(format!("unknown lint level '{}'. expected allow, warn or deny", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Level::Allow => f.pad("allow"),
+            Level::Warn => f.pad("warn"),
+            Level::Deny => f.pad("deny"),
+        }
+    }
+}
+
+pub struct Lint {
+    pub name: &'static str,
+    pub default: Level,
+    pub about: &'static str,
+    // lints on the generated c are passed to the c compiler as -W flags
+    pub cflag: Option<&'static str>,
+}
+
+pub static LINTS: &[Lint] = &[
+    Lint {
+        name: "unproven_float",
+        default: Level::Warn,
+        about: "floating point arithmetic with a solver that has no floating point theory",
+        cflag: None,
+    },
+    Lint {
+        name: "unnecessary_branch",
+        default: Level::Warn,
+        about: "branch conditions that are always true or always false",
+        cflag: None,
+    },
+    Lint {
+        name: "unused_import",
+        default: Level::Warn,
+        about: "imported names that are never used",
+        cflag: None,
+    },
+    Lint {
+        name: "unused_theory",
+        default: Level::Warn,
+        about: "private theories that are never used",
+        cflag: None,
+    },
+    Lint {
+        name: "unchecked_err",
+        default: Level::Warn,
+        about: "returning while a local err::Err may hold an error nobody looked at",
+        cflag: None,
+    },
+    Lint {
+        name: "shadowed_builtin",
+        default: Level::Deny,
+        about: "locals named like a builtin theory",
+        cflag: None,
+    },
+    Lint {
+        name: "c_pedantic",
+        default: Level::Warn,
+        about: "strict iso c warnings in the generated c",
+        cflag: Some("pedantic"),
+    },
+    Lint {
+        name: "c_all",
+        default: Level::Warn,
+        about: "the c compiler's -Wall set of warnings in the generated c",
+        cflag: Some("all"),
+    },
+    Lint {
+        name: "c_implicit_function_declaration",
+        default: Level::Deny,
+        about: "calls to c functions without a prototype",
+        cflag: Some("implicit-function-declaration"),
+    },
+    Lint {
+        name: "c_incompatible_pointer_types",
+        default: Level::Deny,
+        about: "c pointer conversions between unrelated types",
+        cflag: Some("incompatible-pointer-types"),
+    },
+    Lint {
+        name: "c_return_type",
+        default: Level::Deny,
+        about: "c functions that fall off the end without returning a value",
+        cflag: Some("return-type"),
+    },
+    Lint {
+        name: "c_pointer_sign",
+        default: Level::Deny,
+        about: "c pointer conversions that change signedness",
+        cflag: Some("pointer-sign"),
+    },
+    Lint {
+        name: "c_int_to_pointer_cast",
+        default: Level::Deny,
+        about: "c casts from integers of a different size to pointers",
+        cflag: Some("int-to-pointer-cast"),
+    },
+];
+
+// set when a denied lint fired. the build stops before anything is cached or compiled
+pub static DENIED: AtomicBool = AtomicBool::new(false);
+
+// --deny-warnings, every warning is an error
+pub static DENY_WARNINGS: AtomicBool = AtomicBool::new(false);
+
+lazy_static::lazy_static! {
+    // from zz.toml
+    static ref CONFIG: Mutex<HashMap<&'static str, Level>> = Mutex::new(HashMap::new());
+    // from -A -W -D, which win over zz.toml
+    static ref CLI: Mutex<HashMap<&'static str, Level>> = Mutex::new(HashMap::new());
+    // from @allow @warn @deny on declarations, which win over both
+    static ref DECLARED: Mutex<Vec<Declared>> = Mutex::new(Vec::new());
+    // lints only apply to the project, not to its dependencies
+    static ref ROOT: Mutex<Option<PathBuf>> = Mutex::new(None);
+}
+
+struct Declared {
+    file: String,
+    start: usize,
+    end: usize,
+    lint: &'static str,
+    level: Level,
+}
+
+pub fn get(name: &str) -> Option<&'static Lint> {
+    LINTS.iter().find(|l| l.name == name)
+}
+
+fn lookup(name: &str) -> Result<&'static Lint, String> {
+    get(name).ok_or_else(|| format!("unknown lint '{}'. see zz lints", name))
+}
+
+pub fn set_cli(name: &str, level: Level) -> Result<(), String> {
+    let lint = lookup(name)?;
+    CLI.lock().unwrap().insert(lint.name, level);
+    Ok(())
+}
+
+pub fn configure(root: PathBuf, lints: &HashMap<String, String>) -> Result<(), String> {
+    let mut config = CONFIG.lock().unwrap();
+    for (name, level) in lints {
+        config.insert(lookup(name)?.name, level.parse()?);
+    }
+    // source locations are canonical paths
+    *ROOT.lock().unwrap() = Some(root.canonicalize().unwrap_or(root));
+    Ok(())
+}
+
+// takes @allow(lint) @warn(lint) @deny(lint) out of a declarations derives
+pub fn declare(derives: &mut Vec<ast::Derive>, decl: &ast::Location) -> Result<(), Error> {
+    for derive in std::mem::take(derives) {
+        let level = match derive.makro.as_str() {
+            "allow" => Level::Allow,
+            "warn" => Level::Warn,
+            "deny" => Level::Deny,
+            _ => {
+                derives.push(derive);
+                continue;
+            }
+        };
+        for arg in &derive.args {
+            let name = match arg.as_ref() {
+                ast::Expression::Name(ast::Typed { t: ast::Type::Other(name), .. }) if name.len() == 1 => {
+                    name.0[0].clone()
+                }
+                _ => {
+                    return Err(Error::new(
+                        "E0713",
+                        format!("@{} expects lint names", derive.makro),
+                        vec![(arg.loc().clone(), "expected a lint name".to_string())],
+                    ));
+                }
+            };
+            let lint = match get(&name) {
+                Some(v) => v,
+                None => {
+                    return Err(Error::new(
+                        "E0712",
+                        format!("unknown lint '{}'", name),
+                        vec![(arg.loc().clone(), "run `zz lints` for a list of lints".to_string())],
+                    ));
+                }
+            };
+            if lint.cflag.is_some() {
+                return Err(Error::new(
+                    "E0714",
+                    format!("lint '{}' can only be set for the whole project", name),
+                    vec![(arg.loc().clone(), "lints on the generated c go into zz.toml".to_string())],
+                ));
+            }
+            // these are reported at imports and theories, which can't have any
+            if lint.name == "unused_import" || lint.name == "unused_theory" {
+                return Err(Error::new(
+                    "E0714",
+                    format!("lint '{}' can only be set for the whole project", name),
+                    vec![(arg.loc().clone(), "imports and theories take no lint levels, set it in zz.toml".to_string())],
+                ));
+            }
+            DECLARED.lock().unwrap().push(Declared {
+                file: decl.file.clone(),
+                start: decl.start,
+                end: decl.end,
+                lint: lint.name,
+                level,
+            });
+        }
+    }
+    Ok(())
+}
+
+fn configured(lint: &Lint) -> Level {
+    if let Some(level) = CLI.lock().unwrap().get(lint.name) {
+        return *level;
+    }
+    if let Some(level) = CONFIG.lock().unwrap().get(lint.name) {
+        return *level;
+    }
+    lint.default
+}
+
+pub fn in_project(loc: &ast::Location) -> bool {
+    match &*ROOT.lock().unwrap() {
+        None => true,
+        Some(root) => {
+            let file = std::path::Path::new(&loc.file);
+            file.starts_with(root) && !file.starts_with(root.join("target"))
+        }
+    }
+}
+
+pub fn level(name: &str, loc: &ast::Location) -> Level {
+    let lint = get(name).expect("ICE: unknown lint");
+    if !in_project(loc) {
+        return Level::Allow;
+    }
+
+    // the innermost declaration around the location decides
+    let declared = DECLARED
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|d| d.lint == lint.name && d.file == loc.file && d.start <= loc.start && loc.start <= d.end)
+        .min_by_key(|d| d.end - d.start)
+        .map(|d| d.level);
+
+    let level = declared.unwrap_or_else(|| configured(lint));
+    if level == Level::Warn && DENY_WARNINGS.load(Ordering::Relaxed) {
+        return Level::Deny;
+    }
+    level
+}
+
+pub fn emit<S2>(name: &str, code: &str, loc: &ast::Location, message: String, details: &[(ast::Location, S2)])
+where
+    S2: std::string::ToString,
+{
+    let level = level(name, loc);
+    if level == Level::Allow {
+        return;
+    }
+
+    // the first detail says which lint this was, so it can be allowed
+    let mut details: Vec<(ast::Location, String)> =
+        details.iter().map(|(loc, m)| (loc.clone(), m.to_string())).collect();
+    if let Some((_, first)) = details.first_mut() {
+        *first = format!("{} [{}]", first, name);
+    }
+
+    match level {
+        Level::Deny => {
+            DENIED.store(true, Ordering::Relaxed);
+            emit_error(code, message, &details);
+        }
+        _ => emit_warn(code, message, &details),
+    }
+}
+
+// the levels a cached proof or module was checked with. anything cached under
+// different levels might have reported something different
+pub fn fingerprint() -> String {
+    let mut hasher = Sha256::new();
+    for lint in LINTS {
+        hasher.input(format!("{}={};", lint.name, configured(lint)).as_bytes());
+    }
+    hasher.input(format!("{}", DENY_WARNINGS.load(Ordering::Relaxed)).as_bytes());
+    format!("{:x}", hasher.result())[..16].to_string()
+}
+
+pub fn denied() -> bool {
+    DENIED.load(Ordering::Relaxed)
+}
+
+// warning flags for the c compiler, replacing what used to be hardcoded in make
+pub fn cflags() -> Vec<String> {
+    let deny_warnings = DENY_WARNINGS.load(Ordering::Relaxed);
+    let mut flags = Vec::new();
+    for lint in LINTS {
+        let flag = match lint.cflag {
+            Some(v) => v,
+            None => continue,
+        };
+        let mut level = configured(lint);
+        if level == Level::Warn && deny_warnings {
+            level = Level::Deny;
+        }
+        match (flag, level) {
+            ("all", Level::Allow) => (),
+            ("all", Level::Warn) => flags.push("-Wall".to_string()),
+            ("all", Level::Deny) => {
+                flags.push("-Wall".to_string());
+                flags.push("-Werror".to_string());
+            }
+            (_, Level::Allow) => flags.push(format!("-Wno-{}", flag)),
+            (_, Level::Warn) => flags.push(format!("-W{}", flag)),
+            (_, Level::Deny) => flags.push(format!("-Werror={}", flag)),
+        }
+    }
+    flags
+}
+
+pub fn print_lints() {
+    for lint in LINTS {
+        println!("{:<34} {:<6} {}", lint.name, lint.default, lint.about);
+    }
+}
@@ -4,6 +4,7 @@ use super::name::Name;
 use super::parser;
 use pbr;
 use rayon::prelude::*;
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::path::Path;
 use std::path::PathBuf;
@@ -66,26 +67,24 @@ pub fn load(
 
             let (_, outname) = super::emitter::outname(&project, &stage, &module_name, false);
             let cachepath = format!("{}.parsecache", outname);
+            let hash = source_hash(&path);
 
-            if !is_cache_outdated(&path, &cachepath) {
-                match std::fs::File::open(&cachepath) {
-                    Ok(f) => {
-                        match rmp_serde::from_read(&f) {
-                            Ok(cf) => {
-                                if !silent {
-                                    //pb.lock().unwrap().message(&format!("cached {} ", module.name));
-                                    pb.lock().unwrap().inc();
-                                }
-                                return (module_name, Module::ZZ(cf));
-                            }
-                            Err(_) => {
-                                std::fs::remove_file(&cachepath)
-                                    .expect(&format!("cannot remove {}", cachepath));
+            if let Ok(f) = std::fs::File::open(&cachepath) {
+                match rmp_serde::from_read::<_, (String, ast::Module)>(&f) {
+                    Ok((cachehash, cf)) => {
+                        if Some(cachehash) == hash {
+                            if !silent {
+                                //pb.lock().unwrap().message(&format!("cached {} ", module.name));
+                                pb.lock().unwrap().inc();
                             }
+                            return (module_name, Module::ZZ(cf));
                         }
                     }
-                    Err(_) => (),
-                };
+                    Err(_) => {
+                        std::fs::remove_file(&cachepath)
+                            .unwrap_or_else(|e| panic!("cannot remove {}: {}", cachepath, e));
+                    }
+                }
             }
 
             if !silent {
@@ -99,16 +98,19 @@ pub fn load(
                 pb.lock().unwrap().inc();
             }
 
-            //TODO there's a serde bug somewhere, incorrectly serializing "char * f [];"
-            /*
-            let mut cachefile =
-                std::fs::File::create(&cachepath)
-                .expect(&format!("cannot create {}", cachepath));
-
-            cachefile.write(
-                &rmp_serde::to_vec(&m).expect(&format!("cannot encode {}", cachepath))[..]
-            ).expect(&format!("cannot write {}", cachepath));
-            */
+            // a module with syntax errors is only parsed as far as it goes, don't keep it
+            if let Some(hash) = hash {
+                if !parser::SYNTAX_ERRORS.load(Ordering::SeqCst) {
+                    if let Some(parent) = Path::new(&cachepath).parent() {
+                        std::fs::create_dir_all(parent)
+                            .unwrap_or_else(|e| panic!("cannot create {:?}: {}", parent, e));
+                    }
+                    let encoded = rmp_serde::to_vec(&(hash, &m))
+                        .unwrap_or_else(|e| panic!("cannot encode {}: {}", cachepath, e));
+                    std::fs::write(&cachepath, encoded)
+                        .unwrap_or_else(|e| panic!("cannot write {}: {}", cachepath, e));
+                }
+            }
 
             (m.name.clone(), Module::ZZ(m))
         })
@@ -121,20 +123,14 @@ pub fn load(
     }
 }
 
-pub fn is_cache_outdated(source_file: &PathBuf, cache_file: &str) -> bool {
-    let m1 = match std::fs::metadata(&source_file) {
-        Ok(v) => v,
-        Err(_) => return true,
-    };
-    let m1 = m1
-        .modified()
-        .expect(&format!("cannot stat {:?}", source_file));
-
-    let m2 = match std::fs::metadata(&cache_file) {
-        Ok(v) => v,
-        Err(_) => return true,
-    };
-    let m2 = m2.modified().expect(&format!("cannot stat {}", cache_file));
-
-    return m1 > m2;
+// the cache is keyed by what was parsed rather than by mtime, so touching a file,
+// checking out a branch or restoring target/ from ci doesn't throw it away.
+// the path is part of it, because locations in the ast refer to it
+fn source_hash(path: &Path) -> Option<String> {
+    let content = std::fs::read(path).ok()?;
+    let mut hasher = Sha256::new();
+    hasher.input(env!("CARGO_PKG_VERSION").as_bytes());
+    hasher.input(path.to_string_lossy().as_bytes());
+    hasher.input(&content);
+    Some(format!("{:x}", hasher.result()))
 }
@@ -0,0 +1,909 @@
+use super::abs;
+use super::ast;
+use super::lint;
+use super::loader;
+use super::make::Stage;
+use super::name::Name;
+use super::parser::{self, Collected, JsonError, JsonNote, JsonSpan, Rule, ZZParser};
+use super::project;
+use pest::Parser;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+
+// the server keeps every module of a project as parsed, re-parses an open
+// document from what the editor has whenever it changes and runs abs over a
+// copy of all modules to publish its errors and to answer hover and
+// go-to-definition. proving needs the whole pipeline, so a save also runs
+// "zz check" in the background, whose errors are about the files as saved.
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct Range {
+    pub file: String,
+    pub line: usize,
+    pub character: usize,
+    pub line_end: usize,
+    pub character_end: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: String,
+    pub range: Range,
+    pub signature: String,
+    pub contracts: Vec<String>,
+    pub doc: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Reference {
+    pub name: String,
+    pub range: Range,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Index {
+    pub defs: BTreeMap<String, Symbol>,
+    pub refs: Vec<Reference>,
+}
+
+struct Text {
+    path: String,
+    text: String,
+}
+
+// open documents are read as the editor has them, everything else from disk
+struct Texts<'a> {
+    documents: &'a HashMap<PathBuf, String>,
+    files: RefCell<HashMap<String, Option<Rc<Text>>>>,
+}
+
+impl<'a> Texts<'a> {
+    fn new(documents: &'a HashMap<PathBuf, String>) -> Self {
+        Self {
+            documents,
+            files: RefCell::new(HashMap::new()),
+        }
+    }
+
+    fn get(&self, file: &str) -> Option<Rc<Text>> {
+        self.files
+            .borrow_mut()
+            .entry(file.to_string())
+            .or_insert_with(|| {
+                let path = std::fs::canonicalize(file).ok()?;
+                let text = match self.documents.get(&path) {
+                    Some(v) => v.clone(),
+                    None => std::fs::read_to_string(&path).ok()?,
+                };
+                Some(Rc::new(Text {
+                    path: path.to_string_lossy().to_string(),
+                    text,
+                }))
+            })
+            .clone()
+    }
+}
+
+// every module after abs resolved its names to their definitions
+fn index(modules: &HashMap<Name, loader::Module>, texts: &Texts) -> Index {
+    let mut index = Index::default();
+    let mut declarations = HashMap::new();
+    for module in modules.values() {
+        let module = match module {
+            loader::Module::ZZ(v) => v,
+            loader::Module::C(_) => continue,
+        };
+        for local in &module.locals {
+            let text = match texts.get(&local.loc.file) {
+                Some(v) => v,
+                None => continue,
+            };
+            let spans = declarations
+                .entry(local.loc.file.clone())
+                .or_insert_with(|| spans(&text.text));
+            if let Some(symbol) = symbol(&module.name, local, spans, texts) {
+                index.defs.insert(symbol.name.clone(), symbol);
+            }
+        }
+    }
+
+    for module in modules.values() {
+        if let loader::Module::ZZ(module) = module {
+            for local in &module.locals {
+                if texts.get(&local.loc.file).is_none() {
+                    continue;
+                }
+                let v = serde_json::to_value(local).unwrap();
+                references(&v, &index.defs, &mut index.refs, texts);
+            }
+        }
+    }
+    index
+}
+
+// some locals only remember where their name is, so the declaration text
+// comes from the top level declaration around it
+fn spans(source: &str) -> Vec<(usize, usize)> {
+    match ZZParser::parse(Rule::top_level_declarations, source) {
+        Ok(mut pairs) => pairs
+            .next()
+            .unwrap()
+            .into_inner()
+            .map(|decl| (decl.as_span().start(), decl.as_span().end()))
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+fn symbol(module: &Name, local: &ast::Local, spans: &[(usize, usize)], texts: &Texts) -> Option<Symbol> {
+    let mut name = module.clone();
+    name.push(local.name.clone());
+
+    let source = texts.get(&local.loc.file)?;
+    let (start, end) = spans
+        .iter()
+        .find(|(start, end)| *start <= local.loc.start && local.loc.end <= *end)
+        .cloned()
+        .unwrap_or((local.loc.start, local.loc.end));
+    let text = source.text.get(start..end)?;
+
+    let mut contracts = Vec::new();
+    let (kind, signature, nameloc) = match &local.def {
+        ast::Def::Function { nameloc, .. } => {
+            let mut signature = text;
+            if let Ok(mut pairs) = ZZParser::parse(Rule::function, text) {
+                for part in pairs.next().unwrap().into_inner() {
+                    match part.as_rule() {
+                        Rule::call_assert | Rule::call_effect | Rule::macrocall | Rule::gblock => {
+                            if signature.len() == text.len() {
+                                signature = &text[..part.as_span().start()];
+                            }
+                            if part.as_rule() != Rule::gblock && part.as_rule() != Rule::macrocall {
+                                contracts.push(collapse(part.as_str()));
+                            }
+                        }
+                        _ => (),
+                    }
+                }
+            }
+            ("fn", collapse(signature), nameloc.clone())
+        }
+        ast::Def::Closure { nameloc, .. } => ("closure", collapse(text), nameloc.clone()),
+        ast::Def::Theory { .. } => ("theory", collapse(text), find_name(local, start, text)),
+        ast::Def::Struct { union: true, .. } => ("union", text.trim().to_string(), find_name(local, start, text)),
+        ast::Def::Struct { .. } => ("struct", text.trim().to_string(), find_name(local, start, text)),
+        ast::Def::Enum { .. } => ("enum", text.trim().to_string(), find_name(local, start, text)),
+        ast::Def::Const { .. } => ("const", collapse(text), find_name(local, start, text)),
+        ast::Def::Static { .. } => ("static", collapse(text), find_name(local, start, text)),
+        ast::Def::Type { .. } => ("type", collapse(text), find_name(local, start, text)),
+        ast::Def::Symbol { .. } => ("symbol", collapse(text), find_name(local, start, text)),
+        ast::Def::Macro { .. } => (
+            "macro",
+            collapse(text.split('{').next().unwrap()),
+            find_name(local, start, text),
+        ),
+        ast::Def::Include { .. } | ast::Def::Testcase { .. } | ast::Def::Flags { .. } => return None,
+    };
+
+    Some(Symbol {
+        name: name.human_name(),
+        kind: kind.to_string(),
+        range: range(&nameloc, texts)?,
+        signature,
+        contracts,
+        doc: local
+            .doc
+            .lines()
+            .map(|l| l.strip_prefix(' ').unwrap_or(l))
+            .collect::<Vec<&str>>()
+            .join("\n"),
+    })
+}
+
+fn collapse(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+// only functions and closures remember exactly where their name is
+fn find_name(local: &ast::Local, start: usize, text: &str) -> ast::Location {
+    let word = |c: Option<char>| c.map(|c| c.is_alphanumeric() || c == '_').unwrap_or(false);
+    for (at, _) in text.match_indices(local.name.as_str()) {
+        let end = at + local.name.len();
+        if !word(text[..at].chars().last()) && !word(text[end..].chars().next()) {
+            return ast::Location {
+                file: local.loc.file.clone(),
+                line: local.loc.line,
+                start: start + at,
+                end: start + end,
+            };
+        }
+    }
+    local.loc.clone()
+}
+
+// every resolved name in the ast is a Typed with an absolute Type::Other
+fn references(v: &Value, defs: &BTreeMap<String, Symbol>, into: &mut Vec<Reference>, texts: &Texts) {
+    match v {
+        Value::Object(o) => {
+            if let (Some(t), Some(loc)) = (o.get("t"), o.get("loc")) {
+                if let (Some(other), Ok(loc)) = (
+                    t.get("other"),
+                    serde_json::from_value::<ast::Location>(loc.clone()),
+                ) {
+                    if let Ok(name) = serde_json::from_value::<Name>(other.clone()) {
+                        if !name.is_absolute() {
+                            return;
+                        }
+                        let name = name.human_name();
+                        if defs.contains_key(&name) {
+                            if let Some(range) = range(&loc, texts) {
+                                into.push(Reference { name, range });
+                            }
+                        }
+                    }
+                }
+            }
+            for v in o.values() {
+                references(v, defs, into, texts);
+            }
+        }
+        Value::Array(a) => {
+            for v in a {
+                references(v, defs, into, texts);
+            }
+        }
+        _ => (),
+    }
+}
+
+fn range(loc: &ast::Location, texts: &Texts) -> Option<Range> {
+    let source = texts.get(&loc.file)?;
+    let (line, character) = position(&source.text, loc.start);
+    let (line_end, character_end) = position(&source.text, loc.end);
+    Some(Range {
+        file: source.path.clone(),
+        line,
+        character,
+        line_end,
+        character_end,
+    })
+}
+
+// lsp counts lines from zero and columns in utf16 units
+fn position(source: &str, offset: usize) -> (usize, usize) {
+    let mut offset = offset.min(source.len());
+    while !source.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let before = &source[..offset];
+    let line = before.matches('\n').count();
+    let col = before[before.rfind('\n').map(|v| v + 1).unwrap_or(0)..]
+        .encode_utf16()
+        .count();
+    (line, col)
+}
+
+impl Range {
+    fn contains(&self, file: &str, line: usize, character: usize) -> bool {
+        self.file == file
+            && (line, character) >= (self.line, self.character)
+            && (line, character) <= (self.line_end, self.character_end)
+    }
+
+    fn to_lsp(&self) -> Value {
+        json!({
+            "start": {"line": self.line, "character": self.character},
+            "end":   {"line": self.line_end, "character": self.character_end},
+        })
+    }
+
+    fn location(&self) -> Value {
+        json!({
+            "uri": uri(Path::new(&self.file)),
+            "range": self.to_lsp(),
+        })
+    }
+}
+
+impl Symbol {
+    fn hover(&self) -> String {
+        let mut s = format!("```zz\n{}", self.signature);
+        for c in &self.contracts {
+            s += &format!("\n    {}", c);
+        }
+        s += "\n```";
+        if !self.doc.is_empty() {
+            s += &format!("\n\n{}", self.doc);
+        }
+        s
+    }
+}
+
+fn uri(path: &Path) -> String {
+    match url::Url::from_file_path(path) {
+        Ok(v) => v.to_string(),
+        Err(_) => format!("file://{}", path.to_string_lossy()),
+    }
+}
+
+fn path(uri: &str) -> Option<PathBuf> {
+    let path = url::Url::parse(uri).ok()?.to_file_path().ok()?;
+    Some(std::fs::canonicalize(&path).unwrap_or(path))
+}
+
+// diagnostics by uri
+type Diagnostics = HashMap<String, Vec<Value>>;
+
+// the first detail is where the error is, the rest are notes on how we got there
+fn diagnostics(collected: Vec<Collected>, texts: &Texts, into: &mut Diagnostics) {
+    for e in collected {
+        let mut details = e.details.iter().filter_map(|(loc, message)| Some((range(loc, texts)?, message)));
+        // errors without a location have nowhere to go
+        let (at, label) = match details.next() {
+            Some(v) => v,
+            None => continue,
+        };
+        let related: Vec<Value> = details
+            .map(|(range, message)| {
+                json!({
+                    "location": range.location(),
+                    "message": message,
+                })
+            })
+            .collect();
+        let message = if label.is_empty() || *label == e.message {
+            e.message
+        } else {
+            format!("{}\n{}", e.message, label)
+        };
+        into.entry(uri(Path::new(&at.file))).or_default().push(json!({
+            "range": at.to_lsp(),
+            "severity": if e.severity == "warning" { 2 } else { 1 },
+            "code": e.code,
+            "source": "zz",
+            "message": message,
+            "relatedInformation": related,
+        }));
+    }
+}
+
+// the compiler's errors end the analysis by unwinding with an Exit
+fn analyse<R>(f: impl FnOnce() -> R) -> Option<R> {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)).ok()
+}
+
+struct Project {
+    // every module as the parser left it, before abs changes it
+    parsed: HashMap<Name, loader::Module>,
+    // errors parsing each file, by uri
+    parsing: Diagnostics,
+    // errors resolving names in all modules
+    resolving: Diagnostics,
+    // kept from the last time abs got through every module
+    index: Index,
+}
+
+enum Event {
+    Message(Value),
+    Closed,
+    // results of zz check in a project, up to the save with that number
+    Checked(PathBuf, usize, Diagnostics),
+}
+
+struct Server {
+    root: Option<PathBuf>,
+    documents: HashMap<PathBuf, String>,
+    projects: HashMap<PathBuf, Project>,
+    saves: usize,
+    checks: mpsc::Sender<(PathBuf, usize)>,
+    // the last save in each project and what zz check found once it got to it
+    checked: HashMap<PathBuf, (usize, Option<Diagnostics>)>,
+    published: Diagnostics,
+    shutdown: bool,
+    output: Box<dyn Write>,
+}
+
+pub fn main() {
+    let exe = std::env::current_exe().expect("current exe");
+    let code = serve(std::io::BufReader::new(std::io::stdin()), std::io::stdout(), exe);
+    std::process::exit(code);
+}
+
+// talks lsp over input and output until the client exits, and returns the exit code.
+// zz is the executable to run zz check with
+pub fn serve(
+    mut input: impl BufRead + Send + 'static,
+    output: impl Write + 'static,
+    zz: PathBuf,
+) -> i32 {
+    // keep the analysis from printing anything but diagnostics, which it hands to us instead
+    parser::ERRORS_AS_JSON.store(true, Ordering::SeqCst);
+    super::unwind_on_exit();
+
+    let (events, incoming) = mpsc::channel();
+    let (checks, requests) = mpsc::channel();
+
+    let messages = events.clone();
+    std::thread::spawn(move || {
+        while let Some(msg) = read(&mut input) {
+            if messages.send(Event::Message(msg)).is_err() {
+                return;
+            }
+        }
+        messages.send(Event::Closed).ok();
+    });
+    std::thread::spawn(move || checker(&zz, requests, events));
+
+    let mut server = Server {
+        root: None,
+        documents: HashMap::new(),
+        projects: HashMap::new(),
+        saves: 0,
+        checks,
+        checked: HashMap::new(),
+        published: HashMap::new(),
+        shutdown: false,
+        output: Box::new(output),
+    };
+    for event in incoming {
+        match event {
+            Event::Message(msg) => {
+                if let Some(code) = server.handle(msg) {
+                    return code;
+                }
+            }
+            Event::Closed => break,
+            Event::Checked(root, save, diagnostics) => {
+                if let Some((last, checked)) = server.checked.get_mut(&root) {
+                    if *last == save {
+                        *checked = Some(diagnostics);
+                        server.publish();
+                    }
+                }
+            }
+        }
+    }
+    0
+}
+
+fn read(r: &mut impl BufRead) -> Option<Value> {
+    loop {
+        let mut len = None;
+        loop {
+            let mut line = String::new();
+            if r.read_line(&mut line).ok()? == 0 {
+                return None;
+            }
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some(v) = line.strip_prefix("Content-Length:") {
+                len = v.trim().parse::<usize>().ok();
+            }
+        }
+        let len = match len {
+            Some(v) => v,
+            None => continue,
+        };
+        let mut body = vec![0; len];
+        r.read_exact(&mut body).ok()?;
+        match serde_json::from_slice(&body) {
+            Ok(v) => return Some(v),
+            Err(e) => warn!("lsp: invalid message: {}", e),
+        }
+    }
+}
+
+
+// runs zz check for every save, but only once for saves that came in while it was busy
+fn checker(zz: &Path, requests: mpsc::Receiver<(PathBuf, usize)>, events: mpsc::Sender<Event>) {
+    while let Ok(first) = requests.recv() {
+        let mut latest: HashMap<PathBuf, usize> = HashMap::new();
+        for (root, save) in std::iter::once(first).chain(requests.try_iter()) {
+            latest.insert(root, save);
+        }
+        for (root, save) in latest {
+            let diagnostics = check(zz, &root);
+            if events.send(Event::Checked(root, save, diagnostics)).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+// zz prints 1-based lines and columns relative to where it ran
+fn location(root: &Path, span: &JsonSpan) -> (PathBuf, Value) {
+    let file = root.join(&span.file_name);
+    let file = std::fs::canonicalize(&file).unwrap_or(file);
+    let range = json!({
+        "start": {"line": span.line_start.saturating_sub(1), "character": span.column_start.saturating_sub(1)},
+        "end":   {"line": span.line_end.saturating_sub(1), "character": span.column_end.saturating_sub(1)},
+    });
+    (file, range)
+}
+
+fn check(zz: &Path, root: &Path) -> Diagnostics {
+    let mut diagnostics: Diagnostics = HashMap::new();
+    let output = match std::process::Command::new(zz)
+        .current_dir(root)
+        .arg("check")
+        .stdin(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .output()
+    {
+        Ok(v) => v,
+        Err(e) => {
+            warn!("lsp: cannot run zz check: {}", e);
+            return diagnostics;
+        }
+    };
+
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        // the loader's progress bar doesn't end its lines
+        let line = match line.find("{\"code\"") {
+            Some(at) => &line[at..],
+            None => continue,
+        };
+        let e: JsonError = match serde_json::from_str(line) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        // errors without a location have nowhere to go
+        let (label, span) = match e.primary {
+            Some(JsonNote { message, span: Some(span), .. }) => (message, span),
+            _ => continue,
+        };
+        let (file, range) = location(root, &span);
+
+        let related: Vec<Value> = e
+            .related
+            .iter()
+            .filter_map(|note| {
+                let span = note.span.as_ref()?;
+                let (file, range) = location(root, span);
+                Some(json!({
+                    "location": {"uri": uri(&file), "range": range},
+                    "message": note.message,
+                }))
+            })
+            .collect();
+
+        let message = if label.is_empty() || label == e.message {
+            e.message
+        } else {
+            format!("{}\n{}", e.message, label)
+        };
+        diagnostics.entry(uri(&file)).or_default().push(json!({
+            "range": range,
+            "severity": if e.severity == "warning" { 2 } else { 1 },
+            "code": e.code,
+            "source": "zz",
+            "message": message,
+            "relatedInformation": related,
+        }));
+    }
+    diagnostics
+}
+
+impl Server {
+    fn send(&mut self, v: Value) {
+        let body = v.to_string();
+        write!(self.output, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+        self.output.flush().unwrap();
+    }
+
+    // the exit code once the client says exit
+    fn handle(&mut self, msg: Value) -> Option<i32> {
+        let method = msg["method"].as_str().unwrap_or("").to_string();
+        let params = &msg["params"];
+        let id = msg.get("id").cloned();
+        let file = params["textDocument"]["uri"].as_str().and_then(path);
+
+        let result = match method.as_str() {
+            "initialize" => {
+                self.root = params["rootUri"]
+                    .as_str()
+                    .and_then(path)
+                    .or(params["rootPath"].as_str().map(PathBuf::from));
+                Ok(json!({
+                    "capabilities": {
+                        "textDocumentSync": {
+                            "openClose": true,
+                            "change": 1,
+                            "save": {"includeText": false},
+                        },
+                        "hoverProvider": true,
+                        "definitionProvider": true,
+                    },
+                    "serverInfo": {"name": "zz", "version": env!("CARGO_PKG_VERSION")},
+                }))
+            }
+            "initialized" => {
+                if let Some(root) = self.root.clone().and_then(|v| self.project(&v)) {
+                    self.load(&root);
+                    self.save(root);
+                    self.publish();
+                }
+                return None;
+            }
+            "textDocument/didOpen" => {
+                if let (Some(file), Some(text)) = (file, params["textDocument"]["text"].as_str()) {
+                    self.documents.insert(file.clone(), text.to_string());
+                    self.changed(&file);
+                }
+                return None;
+            }
+            "textDocument/didChange" => {
+                // full sync, so the last change is the whole document
+                let text = params["contentChanges"]
+                    .as_array()
+                    .and_then(|v| v.last())
+                    .and_then(|v| v["text"].as_str());
+                if let (Some(file), Some(text)) = (file, text) {
+                    self.documents.insert(file.clone(), text.to_string());
+                    self.changed(&file);
+                }
+                return None;
+            }
+            "textDocument/didClose" => {
+                // whatever wasn't saved is gone
+                if let Some(file) = file {
+                    if self.documents.remove(&file).is_some() {
+                        self.changed(&file);
+                    }
+                }
+                return None;
+            }
+            "textDocument/didSave" => {
+                if let Some(root) = file.and_then(|v| self.project(&v)) {
+                    self.save(root);
+                }
+                return None;
+            }
+            "textDocument/hover" => Ok(match self.lookup(params) {
+                Some(s) => json!({
+                    "contents": {"kind": "markdown", "value": s.hover()},
+                }),
+                None => Value::Null,
+            }),
+            "textDocument/definition" => Ok(match self.lookup(params) {
+                Some(s) => s.range.location(),
+                None => Value::Null,
+            }),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Value::Null)
+            }
+            "exit" => {
+                return Some(if self.shutdown { 0 } else { 1 });
+            }
+            _ => Err(json!({"code": -32601, "message": format!("{} not supported", method)})),
+        };
+
+        // notifications don't get a response
+        let id = match id {
+            Some(v) => v,
+            None => return None,
+        };
+        match result {
+            Ok(v) => self.send(json!({"jsonrpc": "2.0", "id": id, "result": v})),
+            Err(e) => self.send(json!({"jsonrpc": "2.0", "id": id, "error": e})),
+        }
+        None
+    }
+
+    fn lookup(&self, params: &Value) -> Option<&Symbol> {
+        let file = params["textDocument"]["uri"].as_str().and_then(path)?;
+        let index = &self.projects.get(&self.project(&file)?)?.index;
+        let file = file.to_string_lossy();
+        let line = params["position"]["line"].as_u64()? as usize;
+        let character = params["position"]["character"].as_u64()? as usize;
+
+        for r in &index.refs {
+            if r.range.contains(&file, line, character) {
+                return index.defs.get(&r.name);
+            }
+        }
+        index.defs.values().find(|s| s.range.contains(&file, line, character))
+    }
+
+    // the project a file belongs to is the closest directory with a zz.toml
+    fn project(&self, file: &Path) -> Option<PathBuf> {
+        let mut search = Some(file);
+        while let Some(dir) = search {
+            if dir.join("zz.toml").exists() {
+                return Some(dir.to_path_buf());
+            }
+            search = dir.parent();
+        }
+        None
+    }
+
+    fn save(&mut self, root: PathBuf) {
+        self.saves += 1;
+        self.checked.insert(root.clone(), (self.saves, None));
+        self.checks.send((root, self.saves)).ok();
+    }
+
+    // parses the project and its dependencies, and the open documents as they are
+    fn load(&mut self, root: &Path) {
+        std::env::set_current_dir(root).ok();
+        parser::SYNTAX_ERRORS.store(false, Ordering::SeqCst);
+        let (loaded, collected) = parser::collecting(|| {
+            analyse(|| {
+                let (root, mut config) = project::load(root);
+                if let Err(e) = lint::configure(root.clone(), &config.lints) {
+                    warn!("lsp: {}", e);
+                }
+                super::load(&root, &mut config, &Stage::test())
+            })
+        });
+        let parsed = match loaded {
+            Some(v) => v,
+            None => {
+                warn!("lsp: cannot load the project in {:?}", root);
+                return;
+            }
+        };
+
+        let mut parsing = HashMap::new();
+        diagnostics(collected, &Texts::new(&self.documents), &mut parsing);
+        self.projects.insert(
+            root.to_path_buf(),
+            Project {
+                parsed,
+                parsing,
+                resolving: HashMap::new(),
+                index: Index::default(),
+            },
+        );
+
+        let open: Vec<PathBuf> = self.documents.keys().filter(|v| v.starts_with(root)).cloned().collect();
+        for file in open {
+            self.parse(root, &file);
+        }
+        self.resolve(root);
+    }
+
+    fn changed(&mut self, file: &Path) {
+        let root = match self.project(file) {
+            Some(v) => v,
+            None => return,
+        };
+        if !self.projects.contains_key(&root) {
+            self.load(&root);
+        } else if self.parse(&root, file) {
+            self.resolve(&root);
+        }
+        self.publish();
+    }
+
+    // replaces the module of a file with what it parses to now. false if it isn't part of the project
+    fn parse(&mut self, root: &Path, file: &Path) -> bool {
+        let project = match self.projects.get_mut(root) {
+            Some(v) => v,
+            None => return false,
+        };
+        let name = project.parsed.iter().find_map(|(name, module)| match module {
+            loader::Module::ZZ(m) if std::fs::canonicalize(&m.source).ok().as_deref() == Some(file) => {
+                Some(name.clone())
+            }
+            _ => None,
+        });
+        let name = match name {
+            Some(v) => v,
+            None => return false,
+        };
+        let text = match self.documents.get(file) {
+            Some(v) => v.clone(),
+            None => match std::fs::read_to_string(file) {
+                Ok(v) => v,
+                Err(_) => return false,
+            },
+        };
+
+        std::env::set_current_dir(root).ok();
+        let (module, collected) = parser::collecting(|| analyse(|| parser::parse_text(file, &text, &Stage::test())));
+
+        let texts = Texts::new(&self.documents);
+        let mut parsing = HashMap::new();
+        diagnostics(collected, &texts, &mut parsing);
+        let project = self.projects.get_mut(root).unwrap();
+        project.parsing.remove(&uri(file));
+        project.parsing.extend(parsing);
+        if let Some(mut module) = module {
+            module.name = name.clone();
+            project.parsed.insert(name, loader::Module::ZZ(module));
+        }
+        true
+    }
+
+    // resolves names in a copy of every module, to index what they refer to
+    fn resolve(&mut self, root: &Path) {
+        let project = match self.projects.get_mut(root) {
+            Some(v) => v,
+            None => return,
+        };
+        std::env::set_current_dir(root).ok();
+        let mut modules = project.parsed.clone();
+        let (complete, collected) = parser::collecting(|| {
+            analyse(|| {
+                let ext = abs::Ext::new();
+                let mut complete = true;
+                for name in modules.keys().cloned().collect::<Vec<Name>>() {
+                    let mut md = modules.remove(&name).unwrap();
+                    if let loader::Module::ZZ(ast) = &mut md {
+                        complete &= abs::abs(ast, &modules, ext.clone(), false, true);
+                    }
+                    modules.insert(name, md);
+                }
+                complete
+            })
+        });
+
+        let texts = Texts::new(&self.documents);
+        project.resolving.clear();
+        diagnostics(collected, &texts, &mut project.resolving);
+        // an index is only built once every module got through abs. until then, keep what we had
+        if complete.is_some() {
+            project.index = index(&modules, &texts);
+        }
+    }
+
+    // a file that was changed since the last save only has what we found.
+    // otherwise zz check knows more, once it's done
+    fn publish(&mut self) {
+        let mut diagnostics: Diagnostics = HashMap::new();
+        for (root, project) in &self.projects {
+            let mut analysed = project.parsing.clone();
+            for (uri, list) in &project.resolving {
+                analysed.entry(uri.clone()).or_default().extend(list.iter().cloned());
+            }
+            let checked = match self.checked.get(root) {
+                Some((_, Some(v))) => v,
+                _ => {
+                    diagnostics.extend(analysed);
+                    continue;
+                }
+            };
+            let uris: HashSet<&String> = analysed.keys().chain(checked.keys()).collect();
+            for uri in uris {
+                let dirty = path(uri)
+                    .and_then(|file| Some(self.documents.get(&file)? != &std::fs::read_to_string(&file).ok()?))
+                    .unwrap_or(false);
+                let list = if dirty { analysed.get(uri) } else { checked.get(uri) };
+                diagnostics.insert(uri.clone(), list.cloned().unwrap_or_default());
+            }
+        }
+
+        // files that had errors last time and don't anymore need an empty list
+        for uri in self.published.keys() {
+            diagnostics.entry(uri.clone()).or_default();
+        }
+        for (uri, list) in diagnostics {
+            let last = self.published.get(&uri).map(|v| v.as_slice()).unwrap_or(&[]);
+            if last == list.as_slice() {
+                continue;
+            }
+            self.send(json!({
+                "jsonrpc": "2.0",
+                "method": "textDocument/publishDiagnostics",
+                "params": {"uri": uri, "diagnostics": list},
+            }));
+            if list.is_empty() {
+                self.published.remove(&uri);
+            } else {
+                self.published.insert(uri, list);
+            }
+        }
+    }
+}
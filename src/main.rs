@@ -31,6 +31,49 @@ fn main() {
                 .required(false)
                 .long("smt-timeout"),
         )
+        .arg(
+            Arg::with_name("target")
+                .takes_value(true)
+                .required(false)
+                .long("target"),
+        )
+        .arg(
+            Arg::with_name("allow")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .global(true)
+                .long("allow")
+                .short("A")
+                .help("allow a lint"),
+        )
+        .arg(
+            Arg::with_name("warn")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .global(true)
+                .long("warn")
+                .short("W")
+                .help("warn about a lint"),
+        )
+        .arg(
+            Arg::with_name("deny")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .global(true)
+                .long("deny")
+                .short("D")
+                .help("make a lint an error"),
+        )
+        .arg(
+            Arg::with_name("deny-warnings")
+                .takes_value(false)
+                .global(true)
+                .long("deny-warnings")
+                .help("make every warning an error"),
+        )
         .subcommand(
             SubCommand::with_name("check")
                 .about("check the current project")
@@ -66,6 +109,21 @@ fn main() {
                         .required(false)
                         .index(1),
                 )
+                .arg(
+                    Arg::with_name("emit-counterexamples")
+                        .takes_value(false)
+                        .required(false)
+                        .long("emit-counterexamples"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .takes_value(true)
+                        .required(false)
+                        .possible_values(&["json", "sarif"])
+                        .default_value("json")
+                        .help("print diagnostics as json lines or as a sarif 2.1 log")
+                        .long("format"),
+                )
         )
         .subcommand(
             SubCommand::with_name("build")
@@ -113,6 +171,13 @@ fn main() {
         .subcommand(
             SubCommand::with_name("bench")
                 .about("benchmark tests/*.zz")
+                .arg(
+                    Arg::with_name("variant")
+                        .takes_value(true)
+                        .required(false)
+                        .long("variant")
+                        .short("s"),
+                )
                 .arg(
                     Arg::with_name("testname")
                         .takes_value(true)
@@ -123,6 +188,13 @@ fn main() {
         .subcommand(
             SubCommand::with_name("test")
                 .about("execute tests/*.zz")
+                .arg(
+                    Arg::with_name("variant")
+                        .takes_value(true)
+                        .required(false)
+                        .long("variant")
+                        .short("s"),
+                )
                 .arg(
                     Arg::with_name("testname")
                         .takes_value(true)
@@ -131,6 +203,53 @@ fn main() {
                 ),
         )
         .subcommand(SubCommand::with_name("init").about("init zz project in current directory"))
+        .subcommand(
+            SubCommand::with_name("fmt")
+                .about("format zz source files")
+                .arg(
+                    Arg::with_name("check")
+                        .takes_value(false)
+                        .required(false)
+                        .long("check"),
+                )
+                .arg(
+                    Arg::with_name("files")
+                        .takes_value(true)
+                        .multiple(true)
+                        .required(false)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("lsp").about("language server over stdio"),
+        )
+        .subcommand(
+            SubCommand::with_name("explain")
+                .about("show what can be proven about each name in scope at file.zz:LINE:COL")
+                .arg(
+                    Arg::with_name("json")
+                        .takes_value(false)
+                        .required(false)
+                        .long("json"),
+                )
+                .arg(
+                    Arg::with_name("position")
+                        .takes_value(true)
+                        .required(true)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("explain-error")
+                .about("explain an error code like E0401, or list all codes")
+                .arg(
+                    Arg::with_name("code")
+                        .takes_value(true)
+                        .required(false)
+                        .index(1),
+                ),
+        )
+        .subcommand(SubCommand::with_name("lints").about("list lints and their default levels"))
         .subcommand(
             SubCommand::with_name("run")
                 .about("build and run")
@@ -164,6 +283,13 @@ fn main() {
         .subcommand(
             SubCommand::with_name("fuzz")
                 .about("execute tests/*.zz with afl fuzzer")
+                .arg(
+                    Arg::with_name("variant")
+                        .takes_value(true)
+                        .required(false)
+                        .long("variant")
+                        .short("s"),
+                )
                 .arg(
                     Arg::with_name("testname")
                         .takes_value(true)
@@ -176,11 +302,82 @@ fn main() {
     if let Some(t) = matches.value_of("smt-timeout") {
         zz::smt::TIMEOUT.store(t.parse().unwrap(), Ordering::Relaxed);
     }
+    if let Some(t) = matches.value_of("target") {
+        match t.parse::<zz::datamodel::DataModel>() {
+            Ok(dm) => dm.set(),
+            Err(e) => {
+                error!("{}", e);
+                std::process::exit(9);
+            }
+        }
+    }
+
+    // for a lint given more than once, -D wins over -W wins over -A
+    for (arg, level) in &[
+        ("allow", zz::lint::Level::Allow),
+        ("warn", zz::lint::Level::Warn),
+        ("deny", zz::lint::Level::Deny),
+    ] {
+        for name in matches.values_of(arg).into_iter().flatten() {
+            if let Err(e) = zz::lint::set_cli(name, *level) {
+                error!("{}", e);
+                std::process::exit(9);
+            }
+        }
+    }
+    if matches.is_present("deny-warnings") {
+        zz::lint::DENY_WARNINGS.store(true, Ordering::Relaxed);
+    }
 
     match matches.subcommand() {
         ("init", Some(_submatches)) => {
             zz::project::init();
         }
+        ("fmt", Some(submatches)) => {
+            let files = match submatches.values_of("files") {
+                Some(v) => v.map(std::path::PathBuf::from).collect(),
+                None => zz::fmt::project_files(),
+            };
+            if !zz::fmt::fmt(&files, submatches.is_present("check")) {
+                std::process::exit(1);
+            }
+        }
+        ("lsp", Some(_submatches)) => {
+            zz::lsp::main();
+        }
+        ("explain", Some(submatches)) => {
+            let at = match zz::symbolic::explain_at(submatches.value_of("position").unwrap()) {
+                Ok(v) => v,
+                Err(e) => {
+                    error!("{}", e);
+                    std::process::exit(9);
+                }
+            };
+            if submatches.is_present("json") {
+                zz::parser::ERRORS_AS_JSON.store(true, Ordering::SeqCst);
+            }
+            let src = std::path::PathBuf::from(&at.file);
+            *zz::symbolic::EXPLAIN.lock().unwrap() = Some(at);
+            zz::build(zz::BuildSet::Check(Some(src.clone())), "default", zz::make::Stage::test(), false);
+
+            // the executor exits once it explained
+            error!("{:?} is not a module of this project", src);
+            std::process::exit(1);
+        }
+        ("explain-error", Some(submatches)) => {
+            match submatches.value_of("code") {
+                None => zz::diagnostics::print_codes(),
+                Some(code) => {
+                    if !zz::diagnostics::print_explanation(code) {
+                        error!("{} is not a zz error code", code);
+                        std::process::exit(1);
+                    }
+                }
+            }
+        }
+        ("lints", Some(_submatches)) => {
+            zz::lint::print_lints();
+        }
         ("clean", Some(_submatches)) => {
             let td = zz::project::target_dir();
             if td.exists() {
@@ -191,7 +388,7 @@ fn main() {
             let bench = matches.subcommand().0 == "bench";
 
             let variant = submatches.value_of("variant").unwrap_or("default");
-            let stage = zz::make::Stage::test();
+            let stage = zz::make::Stage::test().with_variant(variant);
             zz::build(zz::BuildSet::Tests, variant, stage.clone(), false);
             let (_root, mut project) = zz::project::load_cwd();
 
@@ -362,6 +559,7 @@ fn main() {
                 zz::make::Stage::test()
             };
             let variant = submatches.value_of("variant").unwrap_or("default");
+            let stage = stage.with_variant(variant);
             zz::build(zz::BuildSet::Run, variant, stage.clone(), false);
             let (_root, mut project) = zz::project::load_cwd();
 
@@ -393,7 +591,7 @@ fn main() {
         }
         ("fuzz", Some(submatches)) => {
             let variant = submatches.value_of("variant").unwrap_or("default");
-            let stage = zz::make::Stage::fuzz();
+            let stage = zz::make::Stage::fuzz().with_variant(variant);
             zz::build(zz::BuildSet::Tests, variant, stage.clone(), false);
             let (_root, mut project) = zz::project::load_cwd();
 
@@ -484,16 +682,23 @@ fn main() {
             return;
         }
         ("check", Some(submatches)) => {
-
             let mut src = None;
             if let Some(v) = submatches.value_of("sourcefile") {
                src = Some(std::path::PathBuf::from(v).canonicalize().expect("sourcefile"));
             }
+            let variant = submatches.value_of("variant").unwrap_or("default");
+
+            if submatches.is_present("emit-counterexamples") {
+                zz::symbolic::EMIT_COUNTEREXAMPLES.store(true, Ordering::Relaxed);
+            }
+            if submatches.value_of("format") == Some("sarif") {
+                std::process::exit(zz::sarif::check(src, variant));
+            }
 
             zz::parser::ERRORS_AS_JSON.store(true, Ordering::SeqCst);
             zz::build(
                 zz::BuildSet::Check(src),
-                submatches.value_of("variant").unwrap_or("default"),
+                variant,
                 zz::make::Stage::test(),
                 false,
             )
@@ -1,10 +1,14 @@
 use super::project::{Artifact, Config};
+use crate::builddb;
+use crate::compile_commands;
 use crate::emitter_js;
 use crate::emitter_py;
 use crate::emitter_go;
 use crate::emitter_rs;
 use crate::export_make;
 use crate::export_cmake;
+use crate::export_ninja;
+use crate::export_meson;
 use crate::export_esp;
 use crate::mergecc;
 use metrohash::MetroHash128;
@@ -33,6 +37,7 @@ pub struct Stage {
     pub asan: bool,
     pub fuzz: bool,
     pub pic: bool,
+    pub variant: String,
 }
 
 impl Stage {
@@ -45,6 +50,7 @@ impl Stage {
             asan: false,
             fuzz: false,
             pic: !cfg!(windows),
+            variant: "default".to_string(),
         }
     }
     pub fn test() -> Self {
@@ -62,6 +68,7 @@ impl Stage {
             asan,
             fuzz: false,
             pic: !cfg!(windows),
+            variant: "default".to_string(),
         }
     }
     pub fn debug() -> Self {
@@ -73,6 +80,7 @@ impl Stage {
             asan: false,
             fuzz: false,
             pic: !cfg!(windows),
+            variant: "default".to_string(),
         }
     }
     pub fn fuzz() -> Self {
@@ -90,13 +98,25 @@ impl Stage {
             asan,
             fuzz: true,
             pic: !cfg!(windows),
+            variant: "default".to_string(),
         }
     }
+
+    // variants build into their own target subdirectory,
+    // so switching between them doesn't thrash the object and build caches
+    pub fn with_variant(mut self, variant: &str) -> Self {
+        self.variant = variant.to_string();
+        self
+    }
 }
 
 impl std::fmt::Display for Stage {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.name)
+        if self.variant == "default" {
+            write!(f, "{}", self.name)
+        } else {
+            write!(f, "{}-{}", self.name, self.variant)
+        }
     }
 }
 
@@ -126,6 +146,9 @@ pub struct Make {
     pub lobjs: Vec<String>,
     pub variant: String,
     pub stage: Stage,
+    pub pkgconfig: Vec<String>,
+    // what pkg-config said, which exporters replace with their own pkg-config lookup
+    pub pkgflags: HashSet<String>,
 }
 
 impl Make {
@@ -179,16 +202,39 @@ impl Make {
             cxx = "afl-clang++".to_string();
         }
 
-        let cincludes = config.project.cincludes.clone();
-        let pkgconfig = config.project.pkgconfig.clone();
-        let cobjects = std::mem::replace(&mut config.project.cobjects, Vec::new());
-        let user_cflags = config.project.cflags.clone();
+        let mut cincludes = config.project.cincludes.clone();
+        let mut pkgconfig = config.project.pkgconfig.clone();
+        let mut cobjects = std::mem::replace(&mut config.project.cobjects, Vec::new());
+        let mut user_cflags = config.project.cflags.clone();
+
+        match config.variants.remove(variant) {
+            Some(v) => {
+                cincludes.extend(v.cincludes);
+                pkgconfig.extend(v.pkgconfig);
+                cobjects.extend(v.cobjects);
+                user_cflags.extend(v.cflags);
+                lflags.extend(v.lflags);
+                for define in v.defines {
+                    user_cflags.push(format!("-D{}", define));
+                }
+            }
+            None if variant == "default" => (),
+            None => {
+                super::parser::emit_error(
+                    "E0807",
+                    format!("variant \"{}\" is not declared in zz.toml", variant),
+                    &Vec::<(super::ast::Location, String)>::new(),
+                );
+                super::exit(9);
+            }
+        }
 
         for cinc in &cincludes {
             cflags.push("-I".into());
             cflags.push(cinc.clone());
         }
 
+        let mut pkgflags = HashSet::new();
         for pkg in &pkgconfig {
             let flags = Command::new("pkg-config")
                 .arg("--cflags")
@@ -200,6 +246,7 @@ impl Make {
             let flags = flags.split_whitespace();
             for flag in flags {
                 cflags.push(flag.to_string());
+                pkgflags.insert(flag.to_string());
             }
 
             let flags = Command::new("pkg-config")
@@ -212,6 +259,7 @@ impl Make {
             let flags = flags.split_whitespace();
             for flag in flags {
                 lflags.push(flag.to_string());
+                pkgflags.insert(flag.to_string());
             }
         }
         cflags.push("-I".into());
@@ -241,6 +289,8 @@ impl Make {
             cxx,
             host_cxx,
             cincludes,
+            pkgconfig,
+            pkgflags,
         };
 
         for c in cobjects {
@@ -365,7 +415,7 @@ impl Make {
                     None => continue,
                     Some(l) => l,
                 };
-                println!(">>{}<<", line);
+                debug!(">>{}<<", line);
                 let mut line = line.split(",");
                 let     key = match line.next() {
                     None => continue,
@@ -382,7 +432,7 @@ impl Make {
                     val.remove(val.len() - 1);
                 }
                 if key == "linker" {
-                    println!(">>{}<<", val);
+                    debug!(">>{}<<", val);
                     cf.lflags.push(val.to_string());
                 }
             }
@@ -397,11 +447,7 @@ impl Make {
         args.push("-fno-asynchronous-unwind-tables".into());
         args.push("-fno-unwind-tables".into());
 
-        args.push("-Werror=implicit-function-declaration".to_string());
-        args.push("-Werror=incompatible-pointer-types".to_string());
-        args.push("-Werror=return-type".to_string());
-        args.push("-Wpedantic".to_string());
-        args.push("-Wall".to_string());
+        args.extend(super::lint::cflags());
         args.push("-Wno-unused-function".to_string());
         args.push("-Wno-parentheses-equality".to_string());
 
@@ -409,9 +455,6 @@ impl Make {
         args.push("-Wno-flexible-array-extensions".to_string());
         args.push("-Wno-gnu-variable-sized-type-not-at-end".to_string());
 
-        args.push("-Werror=pointer-sign".to_string());
-        args.push("-Werror=int-to-pointer-cast".to_string());
-
         if self.stage.pic {
             args.push("-fPIC".into());
         }
@@ -485,6 +528,8 @@ impl Make {
             _ => false,
         };
 
+        compile_commands::write(&self);
+
         let has_used_cxx = AtomicBool::new(false);
         let pb = Arc::new(Mutex::new(pbr::ProgressBar::new(self.steps.len() as u64)));
         pb.lock().unwrap().show_speed = false;
@@ -497,6 +542,15 @@ impl Make {
                 self.lflags.append(&mut step.lflags.clone());
             }
 
+            // headers merged into target/c need to be current before deciding what to build
+            let mut headers = HashSet::new();
+            for step in &self.steps {
+                headers.extend(builddb::deps(Path::new(&step.outp)));
+            }
+            for header in headers {
+                mergecc::refresh(&self.cincludes, &td.join("c"), &header);
+            }
+
 
             self.steps.par_iter_mut().for_each(|step| {
                 if ABORT.load(Ordering::Relaxed) {
@@ -513,14 +567,24 @@ impl Make {
                     .message(&format!("{} {:?} ", cmd, step.source));
 
 
-                if step.is_dirty() {
+                let outp = Path::new(&step.outp);
+                let key = step.key(&cmd, &builddb::deps(outp));
+                if !builddb::fresh(outp, &key) {
+                    // the compiler lists every header it included, which decides when to build again
+                    let depfile = format!("{}.d", step.outp);
                     debug!("{} {:?}", cmd, step.args);
                     let status = Command::new(&cmd)
                         .env("AFL_USE_ASAN", "1")
                         .args(&step.args)
+                        .arg("-MD")
+                        .arg("-MF")
+                        .arg(&depfile)
                         .status()
                         .expect("failed to execute cc");
-                    if !status.success() {
+                    if status.success() {
+                        let headers = read_depfile(Path::new(&depfile));
+                        builddb::record_with_deps(outp, step.key(&cmd, &headers), headers);
+                    } else {
                         error!("cc: [{}] args: [{}]", cmd, step.args.join(" "));
                         ABORT.store(true, Ordering::Relaxed);
                     }
@@ -533,10 +597,13 @@ impl Make {
                     "failed [{:?}] {}",
                     self.artifact.typ, self.artifact.name
                 ));
-                std::process::exit(11);
+                super::exit(11);
             }
         }
 
+        // everything that was built is recorded by now, linking and exporting isn't
+        builddb::flush();
+
         let mut cmd = if has_used_cxx.load(Ordering::Relaxed) {
             self.cxx.clone()
         } else {
@@ -581,7 +648,7 @@ impl Make {
                 return;
             }
             super::project::ArtifactType::CMake => {
-                export_cmake::export(self);
+                export_cmake::export(self, args);
                 return;
             }
             super::project::ArtifactType::Make => {
@@ -592,6 +659,14 @@ impl Make {
                 export_esp::export(self);
                 return;
             }
+            super::project::ArtifactType::Ninja => {
+                export_ninja::export(self, args);
+                return;
+            }
+            super::project::ArtifactType::Meson => {
+                export_meson::export(self, args);
+                return;
+            }
             super::project::ArtifactType::Staticlib => {
                 std::fs::create_dir_all(td
                     .join(self.stage.to_string())
@@ -715,7 +790,7 @@ impl Make {
             .status()
             .expect("failed to execute linker");
         if !status.success() {
-            std::process::exit(status.code().unwrap_or(3));
+            super::exit(status.code().unwrap_or(3));
         }
 
         pb.lock().unwrap().finish_print(&format!(
@@ -727,26 +802,57 @@ impl Make {
 }
 
 impl Step {
-    fn is_dirty(&self) -> bool {
-        let itarget = match std::fs::metadata(&self.outp) {
-            Ok(v) => v,
-            Err(_) => return true,
-        };
-        let itarget = itarget
-            .modified()
-            .expect(&format!("cannot stat {}", self.outp));
-
-        for source in &self.deps {
-            let isource = std::fs::metadata(source).expect(&format!("cannot stat {:?}", source));
-
-            let isource = isource
-                .modified()
-                .expect(&format!("cannot stat {:?}", source));
+    fn key(&self, cmd: &str, headers: &[PathBuf]) -> String {
+        let mut hasher = builddb::Hasher::new();
+        hasher.compiler(cmd);
+        for arg in &self.args {
+            hasher.str(arg);
+        }
+        hasher.file(&self.source);
+        hasher.files(&self.deps);
+        hasher.files(headers);
+        hasher.finish()
+    }
+}
 
-            if isource > itarget {
-                return true;
+// a make rule, "out.o: a.c b.h \
+//  c.h". spaces in names are escaped with a backslash
+pub fn read_depfile(path: &Path) -> Vec<PathBuf> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(v) => v,
+        Err(_) => return Vec::new(),
+    };
+    let content = content.replace("\\\r\n", " ").replace("\\\n", " ");
+
+    // the target can be a windows path with a drive letter, the rule starts after ": "
+    let rule = match content.find(": ") {
+        Some(at) => &content[at + 2..],
+        None => return Vec::new(),
+    };
+
+    let mut deps = Vec::new();
+    let mut dep = String::new();
+    let mut chars = rule.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&' ') => {
+                dep.push(' ');
+                chars.next();
+            }
+            '$' if chars.peek() == Some(&'$') => {
+                dep.push('$');
+                chars.next();
             }
+            c if c.is_whitespace() => {
+                if !dep.is_empty() {
+                    deps.push(PathBuf::from(std::mem::take(&mut dep)));
+                }
+            }
+            c => dep.push(c),
         }
-        return false;
     }
+    if !dep.is_empty() {
+        deps.push(PathBuf::from(dep));
+    }
+    deps.into_iter().map(|dep| dep.canonicalize().unwrap_or(dep)).collect()
 }
@@ -38,6 +38,7 @@ pub fn expr(
 
     if !mp.exists() {
         return Err(Error::new(
+            "E0702",
             format!("macro {} is unavailable (exe {:?})", name, mp),
             vec![(
                 loc.clone(),
@@ -65,7 +66,7 @@ pub fn expr(
 
     if !cmd.wait().unwrap().success() {
         eprintln!("failed to execute macro {}\n {:?} < {:?}", name,  mp, debug_out);
-        std::process::exit(9);
+        super::exit(9);
     }
 
     let mut n = String::new();
@@ -77,6 +78,7 @@ pub fn expr(
         Ok(v) => v,
         Err(e) => {
             return Err(Error::new(
+                "E0703",
                 format!("syntax error in proc macro return: {}", e),
                 vec![(loc.clone(), "in this macro invocation".to_string())],
             ));
@@ -118,6 +120,7 @@ pub fn stm(
 
     if !mp.exists() {
         return Err(Error::new(
+            "E0701",
             format!("macro {} is unavailable", name),
             vec![(
                 loc.clone(),
@@ -145,7 +148,7 @@ pub fn stm(
 
     if !cmd.wait().unwrap().success() {
         eprintln!("failed to execute macro {}\n {:?} < {:?}", name,  mp, debug_out);
-        std::process::exit(9);
+        super::exit(9);
     }
 
     let mut n = String::new();
@@ -157,6 +160,7 @@ pub fn stm(
         Ok(v) => v,
         Err(e) => {
             return Err(Error::new(
+                "E0703",
                 format!("syntax error in proc macro return: {}", e),
                 vec![(loc.clone(), "in this macro invocation".to_string())],
             ));
@@ -200,6 +204,7 @@ pub fn derive (
 
     if !mp.exists() {
         return Err(Error::new(
+            "E0701",
             format!("macro {} is unavailable", name),
             vec![(
                 loc.clone(),
@@ -227,7 +232,7 @@ pub fn derive (
 
     if !cmd.wait().unwrap().success() {
         eprintln!("failed to execute macro {}\n {:?} < {:?}", name,  mp, debug_out);
-        std::process::exit(9);
+        super::exit(9);
     }
 
     let mut n = String::new();
@@ -239,6 +244,7 @@ pub fn derive (
         Ok(v) => v,
         Err(e) => {
             return Err(Error::new(
+                "E0703",
                 format!("syntax error in proc macro return: {}", e),
                 vec![(loc.clone(), "in this macro invocation".to_string())],
             ));
@@ -249,6 +255,7 @@ pub fn derive (
         Ok(v) => v,
         Err(e) => {
             return Err(Error::new(
+                "E0703",
                 format!("syntax error in proc macro return: {}", e),
                 vec![(loc.clone(), "in this macro invocation".to_string())],
             ));
@@ -1275,7 +1275,7 @@ impl Stack {
                 let ptr = self.local(Some(typed.clone()), Name::from(&*name), loc.clone(), tags.clone());
 
 
-                if let Some(Some(expr)) = array {
+                if let ast::Array::Sized(expr) = array {
                     debug!("    initializing {} with array expr", ptr);
                     let ptr2 = self.check_expr(expr)?;
                     match &self.storage[ptr2].value {
@@ -1535,7 +1535,7 @@ pub fn check(md: &mut flatten::Module) {
         }
     }
     if ABORT.load(Ordering::Relaxed) {
-        std::process::exit(9);
+        super::exit(9);
     }
 }
 
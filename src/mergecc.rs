@@ -3,6 +3,7 @@ use std::path::PathBuf;
 use std::fs;
 use std::io::{self, BufRead, Write};
 use std::collections::HashMap;
+use super::builddb;
 
 
 #[derive(Default)]
@@ -23,6 +24,14 @@ pub fn mergecc(includes: &Vec<String>, outbase: &Path, inp: &Path) -> PathBuf {
     m.copy(inp.into())
 }
 
+// a copy is only made again when something copies it, which an unchanged file
+// including it doesn't. this redoes a copy the c compiler said it included
+pub fn refresh(includes: &Vec<String>, outbase: &Path, merged: &Path) {
+    if let Some(inp) = builddb::deps(merged).first() {
+        mergecc(includes, outbase, inp);
+    }
+}
+
 
 impl M {
 
@@ -40,17 +49,18 @@ fn copy(&mut self, inp: PathBuf) -> PathBuf
 
     self.visited.insert(inp.clone(), merged.clone());
 
-    if let Ok(target) = std::fs::metadata(&merged) {
-        if let Ok(source) = std::fs::metadata(&inp) {
-            let itarget = target.modified().expect(&format!("cannot stat {:?}", target));
-            let isource = source.modified().expect(&format!("cannot stat {:?}", source));
-            if itarget > isource {
-                return merged;
-            }
-        }
+    // which includes get merged depends on the include paths
+    let mut hasher = builddb::Hasher::new();
+    hasher.file(&inp);
+    for include in &self.includes {
+        hasher.str(include);
+    }
+    let key = hasher.finish();
+    if builddb::fresh(&merged, &key) {
+        return merged;
     }
 
-    let mut w = fs::File::create(&merged).expect(&format!("cannot create {:?}", merged));
+    let mut w = Vec::new();
 
     let iff = fs::File::open(&inp).expect(&format!("cannot open {:?}", inp));
 
@@ -91,6 +101,9 @@ fn copy(&mut self, inp: PathBuf) -> PathBuf
         w.write(b"\n").unwrap();
     }
 
+    builddb::write_if_changed(&merged, &w);
+    builddb::record_with_deps(&merged, key, vec![inp]);
+
     return merged;
 }
 }
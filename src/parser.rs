@@ -7,6 +7,7 @@ use std::collections::HashMap;
 use std::convert::TryInto;
 use std::path::Path;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
 
 #[derive(Parser)]
 #[grammar = "zz.pest"]
@@ -14,46 +15,237 @@ pub struct ZZParser;
 
 pub static ERRORS_AS_JSON: AtomicBool = AtomicBool::new(false);
 
+// set when any file had a syntax error. loading carries on, so errors in other files are reported too
+pub static SYNTAX_ERRORS: AtomicBool = AtomicBool::new(false);
+
+// after this many syntax errors in one file, the rest are likely follow ups
+static MAX_SYNTAX_ERRORS: usize = 50;
+
 pub fn parse(n: &Path, stage: &Stage) -> Module {
-    match p(&n, stage) {
-        Err(e) => {
-            let e = e.with_path(&n.to_string_lossy().to_string());
-            if ERRORS_AS_JSON.load(Ordering::SeqCst) {
-                let mut j = JsonError::default();
-                j.message = format!("syntax error:\n{}", e);
-                j.level = "error".to_string();
-                j.file_name = n.to_string_lossy().to_string();
-
-                match e.line_col {
-                    pest::error::LineColLocation::Span((l1, c1), (l2, c2)) => {
-                        j.line_start = l1;
-                        j.column_start = c1;
-                        j.line_end = l2;
-                        j.column_end = c2;
-                    }
-                    pest::error::LineColLocation::Pos((l1, c1)) => {
-                        j.line_start = l1;
-                        j.column_start = c1;
-                        j.line_end = l1;
-                        j.column_end = c1;
-                    }
-                };
-                println!("{}", serde_json::to_string(&j).unwrap());
-            } else {
-                error!("syntax error\n{}", e);
+    let (original, _) = read_source(n.to_string_lossy().to_string());
+    parse_text(n, original, stage)
+}
+
+// parses what an editor has, which might not be saved yet
+pub fn parse_text(n: &Path, original: &str, stage: &Stage) -> Module {
+    let path = n.to_string_lossy().to_string();
+
+    // errors after the first blank out what they're in, all in one copy of the source
+    let mut blanked: Option<Vec<u8>> = None;
+    let mut errors = Vec::new();
+    let mut resume = 0;
+    let module = loop {
+        let source = match &blanked {
+            Some(v) => std::str::from_utf8(v).expect("ICE: blanking split a character"),
+            None => original,
+        };
+        let e = match p(n, stage, source) {
+            Ok(md) => break Some(md),
+            Err(e) => e,
+        };
+        let at = match e.location {
+            pest::error::InputLocation::Pos(at) => at,
+            pest::error::InputLocation::Span((at, _)) => at,
+        };
+        // an error inside what was just blanked out is caused by the blanking
+        if at < resume {
+            break None;
+        }
+        errors.push(in_original(e, original, at));
+        if errors.len() >= MAX_SYNTAX_ERRORS {
+            break None;
+        }
+        match recover(blanked.get_or_insert_with(|| original.as_bytes().to_vec()), at) {
+            Some(end) => resume = end,
+            None => break None,
+        }
+    };
+
+    if errors.is_empty() {
+        return module.unwrap();
+    }
+    for e in errors {
+        emit_syntax_error(&path, e);
+    }
+    SYNTAX_ERRORS.store(true, Ordering::SeqCst);
+
+    // whatever parsed is good enough to keep loading the other files
+    let mut module = module.unwrap_or_default();
+    module.source = n.to_path_buf();
+    module.sources.insert(n.canonicalize().unwrap());
+    module
+}
+
+// errors after the first are found in a blanked copy of the file, but should show the real line
+fn in_original(e: pest::error::Error<Rule>, original: &str, at: usize) -> pest::error::Error<Rule> {
+    let variant = e.variant.clone();
+    match e.location {
+        pest::error::InputLocation::Span((start, end)) => match pest::Span::new(original, start, end) {
+            Some(span) => pest::error::Error::new_from_span(variant, span),
+            None => e,
+        },
+        pest::error::InputLocation::Pos(_) => match pest::Position::new(original, at) {
+            Some(pos) => pest::error::Error::new_from_pos(variant, pos),
+            None => e,
+        },
+    }
+}
+
+fn emit_syntax_error(path: &str, e: pest::error::Error<Rule>) {
+    let e = e.with_path(path);
+    let (start, end) = match e.location {
+        pest::error::InputLocation::Pos(at) => (at, at),
+        pest::error::InputLocation::Span(v) => v,
+    };
+    let line = match e.line_col {
+        pest::error::LineColLocation::Pos((line, _)) => line,
+        pest::error::LineColLocation::Span((line, _), _) => line,
+    };
+    let loc = Location { file: path.to_string(), line, start, end };
+    if collected("E0101", "error", "syntax error", &[(loc, expected(&e))]) {
+        return;
+    }
+    if ERRORS_AS_JSON.load(Ordering::SeqCst) {
+        let (start, end) = match e.line_col {
+            pest::error::LineColLocation::Span(start, end) => (start, end),
+            pest::error::LineColLocation::Pos(start) => (start, start),
+        };
+        let j = JsonError {
+            code: "E0101".to_string(),
+            severity: "error".to_string(),
+            message: "syntax error".to_string(),
+            primary: Some(JsonNote {
+                kind: "primary".to_string(),
+                message: expected(&e),
+                condition: None,
+                span: Some(JsonSpan {
+                    file_name: path.to_string(),
+                    line_start: start.0,
+                    column_start: start.1,
+                    line_end: end.0,
+                    column_end: end.1,
+                }),
+            }),
+            related: Vec::new(),
+        };
+        println!("{}", serde_json::to_string(&j).unwrap());
+    } else {
+        let code = "E0101";
+        error!("{}: syntax error\n{}{}", code, e, explain_hint(code));
+    }
+}
+
+// pest renders "expected ..." as the last line of the error
+fn expected(e: &pest::error::Error<Rule>) -> String {
+    e.to_string()
+        .lines()
+        .last()
+        .unwrap_or("")
+        .trim_start_matches(&[' ', '='][..])
+        .to_string()
+}
+
+// blanks out the statement around a syntax error, or the whole top level declaration
+// when the error isn't inside braces, so parsing can go on to find the next error.
+// everything but newlines becomes a space, so offsets and lines stay the same.
+// returns where the blanked part ends
+fn recover(b: &mut [u8], at: usize) -> Option<usize> {
+    // brace depth before every byte, and whether it is code rather than a comment or literal
+    let mut depth = vec![0i64; b.len() + 1];
+    let mut code = vec![true; b.len() + 1];
+    let mut d = 0;
+    let mut i = 0;
+    while i < b.len() {
+        let skip = if b[i..].starts_with(b"//") {
+            b[i..].iter().position(|c| *c == b'\n').unwrap_or(b.len() - i)
+        } else if b[i..].starts_with(b"/*") {
+            b[i + 2..].windows(2).position(|w| w == b"*/").map(|v| v + 4).unwrap_or(b.len() - i)
+        } else if b[i] == b'"' || b[i] == b'\'' {
+            let mut j = i + 1;
+            while j < b.len() && b[j] != b[i] && b[j] != b'\n' {
+                j += if b[j] == b'\\' { 2 } else { 1 };
+            }
+            (j + 1).min(b.len()) - i
+        } else {
+            0
+        };
+        if skip > 0 {
+            for j in i..i + skip {
+                depth[j] = d;
+                code[j] = false;
+            }
+            i += skip;
+            continue;
+        }
+        depth[i] = d;
+        match b[i] {
+            b'{' => d += 1,
+            b'}' => d -= 1,
+            _ => (),
+        }
+        i += 1;
+    }
+    depth[b.len()] = d;
+
+    let at = at.min(b.len());
+    let mut region = None;
+
+    // a statement starts after the previous ; { or } and ends with the next ; or the end of its block
+    if depth[at] > 0 {
+        let start = (0..at)
+            .rev()
+            .find(|i| code[*i] && (b[*i] == b';' || b[*i] == b'{' || b[*i] == b'}'))
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        let mut end = b.len();
+        for i in start..b.len() {
+            if !code[i] {
+                continue;
             }
-            std::process::exit(9);
+            if b[i] == b';' && depth[i] == depth[start] {
+                end = i + 1;
+                break;
+            }
+            if b[i] == b'}' && depth[i] == depth[start] {
+                end = i;
+                break;
+            }
+        }
+        if start < end && end > at {
+            region = Some((start, end));
+        }
+    }
+
+    // a top level declaration starts at the beginning of a line outside of any braces
+    if region.is_none() {
+        let decl = |i: usize| {
+            (i == 0 || b[i - 1] == b'\n')
+                && depth[i] == 0
+                && code[i]
+                && i < b.len()
+                && (b[i].is_ascii_alphabetic() || b[i] == b'_' || b[i] == b'@')
+        };
+        let start = (0..=at).rev().find(|i| decl(*i)).unwrap_or(0);
+        let end = (at + 1..b.len()).find(|i| decl(*i)).unwrap_or(b.len());
+        if start < end {
+            region = Some((start, end));
+        }
+    }
+
+    let (start, end) = region?;
+    for c in &mut b[start..end] {
+        if *c != b'\n' && *c != b'\r' {
+            *c = b' ';
         }
-        Ok(md) => md,
     }
+    Some(end)
 }
 
 fn p(
     n: &Path,
     stage: &Stage,
+    file_str: &str,
 ) -> Result<Module, pest::error::Error<Rule>> {
-    let (file_str, _) = read_source(n.to_string_lossy().to_string());
-
     let file = ZZParser::parse(Rule::top_level_declarations, file_str)?;
 
     let mut module = parse_module(&n.to_string_lossy().to_string(), stage, file)?;
@@ -71,7 +263,7 @@ fn p(
 pub(crate) fn parse_module(
     n: &str,
     stage: &Stage,
-    mut decl: pest::iterators::Pairs<'static, Rule>,
+    mut decl: pest::iterators::Pairs<'_, Rule>,
 ) -> Result<Module, pest::error::Error<Rule>>
 {
     let mut module = Module::default();
@@ -176,10 +368,11 @@ pub(crate) fn parse_module(
                                 if arg.as_rule() == Rule::vararg {
                                     if declrule != Rule::function {
                                         emit_error(
+                                            "E0104",
                                             "vararg not possible here",
                                             &[(argloc, "nope")],
                                         );
-                                        std::process::exit(9);
+                                        super::exit(9);
                                     }
                                     vararg = true;
                                 } else {
@@ -192,11 +385,12 @@ pub(crate) fn parse_module(
                                         last_assign_arg_loc = Some(argloc.clone());
                                     } else if let Some(last) = last_assign_arg_loc {
                                         emit_error(
+                                            "E0105",
                                             "assign arguments can not be followed by non assign argument".to_string(),
                                             &[(last, "assign arguments must be last"),
                                             (argloc, "and cannot be followed by non-assign arguments")],
                                         );
-                                        std::process::exit(9);
+                                        super::exit(9);
                                     }
 
                                     args.push(NamedArg {
@@ -369,10 +563,11 @@ pub(crate) fn parse_module(
                                     Err(e) => {
                                         let loc = Location::from_span(n.into(), &part.as_span());
                                         emit_error(
+                                            "E0108",
                                             "enums must be positive integer literals",
                                             &[(loc, format!("{}", e))],
                                         );
-                                        std::process::exit(9);
+                                        super::exit(9);
                                     }
                                     Ok(v) => v,
                                 });
@@ -591,7 +786,7 @@ pub(crate) fn parse_module(
                                     part.as_span(),
                                 );
                                 error!("{} : {}", n, e);
-                                std::process::exit(9);
+                                super::exit(9);
                             } else {
                                 vis = Visibility::Shared;
                             }
@@ -607,7 +802,7 @@ pub(crate) fn parse_module(
                                     part.as_span(),
                                 );
                                 error!("{} : {}", n, e);
-                                std::process::exit(9);
+                                super::exit(9);
                             } else {
                                 vis = Visibility::Export;
                             }
@@ -634,13 +829,14 @@ pub(crate) fn parse_module(
                     Rule::constant => {
                         for (_, tag) in tags.0 {
                             emit_error(
+                                "E0101",
                                 "syntax error",
                                 &[(
                                     tag.iter().next().unwrap().1.clone(),
                                     "anonymous type cannot have storage tags (yet)",
                                 )],
                             );
-                            std::process::exit(9);
+                            super::exit(9);
                         }
 
                         module.locals.push(Local {
@@ -775,7 +971,7 @@ pub(crate) fn parse_module(
     Ok(module)
 }
 
-pub(crate) fn parse_derive(n: &str, decl: pest::iterators::Pair<'static, Rule>) -> Derive {
+pub(crate) fn parse_derive(n: &str, decl: pest::iterators::Pair<'_, Rule>) -> Derive {
     match decl.as_rule() {
         Rule::macrocall => {}
         _ => {
@@ -797,7 +993,7 @@ pub(crate) fn parse_derive(n: &str, decl: pest::iterators::Pair<'static, Rule>)
     Derive { loc, makro, args }
 }
 
-pub(crate) fn parse_expr(n: &str, decl: pest::iterators::Pair<'static, Rule>) -> Expression {
+pub(crate) fn parse_expr(n: &str, decl: pest::iterators::Pair<'_, Rule>) -> Expression {
     match decl.as_rule() {
         Rule::expr => {}
         Rule::expr_to_precedence_2 => {}
@@ -841,7 +1037,7 @@ pub(crate) fn parse_expr(n: &str, decl: pest::iterators::Pair<'static, Rule>) ->
             | Operator::new(Rule::arraystart, Assoc::Left),
     ]);
 
-    let reduce = |lhs: Expression, op: pest::iterators::Pair<'static, Rule>, rhs: Expression| {
+    let reduce = |lhs: Expression, op: pest::iterators::Pair<'_, Rule>, rhs: Expression| {
         let loc = Location::from_span(n.into(), &op.as_span());
 
         if op.as_rule() == Rule::memberaccess {
@@ -856,10 +1052,11 @@ pub(crate) fn parse_expr(n: &str, decl: pest::iterators::Pair<'static, Rule>) ->
                 }
             }
             emit_error(
+                "E0904",
                 format!("ICE: unexpected rhs {:?}", rhs),
                 &[(loc.clone(), "in this memberaccess ")],
             );
-            std::process::exit(9);
+            super::exit(9);
         } else if op.as_rule() == Rule::ptraccess {
             if let Expression::Name(typed) = &rhs {
                 if let Type::Other(n) = &typed.t {
@@ -872,10 +1069,11 @@ pub(crate) fn parse_expr(n: &str, decl: pest::iterators::Pair<'static, Rule>) ->
                 }
             }
             emit_error(
+                "E0904",
                 format!("ICE: unexpected rhs {:?}", rhs),
                 &[(loc.clone(), "in this ptraccess ")],
             );
-            std::process::exit(9);
+            super::exit(9);
         } else if op.as_rule() == Rule::callstart {
             if let Expression::Call { loc, args, .. } = &rhs {
                 return Expression::Call {
@@ -887,10 +1085,11 @@ pub(crate) fn parse_expr(n: &str, decl: pest::iterators::Pair<'static, Rule>) ->
                 };
             }
             emit_error(
+                "E0904",
                 format!("ICE: unexpected rhs {:?}", rhs),
                 &[(loc.clone(), "in this call ")],
             );
-            std::process::exit(9);
+            super::exit(9);
         } else if op.as_rule() == Rule::arraystart {
             return Expression::ArrayAccess {
                 loc: loc.clone(),
@@ -924,10 +1123,11 @@ pub(crate) fn parse_expr(n: &str, decl: pest::iterators::Pair<'static, Rule>) ->
                 Rule::bitor => crate::ast::InfixOperator::Bitor,
                 _ => {
                     emit_error(
+                        "E0905",
                         format!("ICE: unexpected operator {}", op),
                         &[(loc.clone(), "in this infix")],
                     );
-                    std::process::exit(9);
+                    super::exit(9);
                 }
             },
         }
@@ -935,7 +1135,7 @@ pub(crate) fn parse_expr(n: &str, decl: pest::iterators::Pair<'static, Rule>) ->
     climber.climb(decl.into_inner(), |pair| parse_expr_inner(n, pair), reduce)
 }
 
-pub(crate) fn parse_expr_inner(n: &str, expr: pest::iterators::Pair<'static, Rule>) -> Expression {
+pub(crate) fn parse_expr_inner(n: &str, expr: pest::iterators::Pair<'_, Rule>) -> Expression {
     let loc = Location::from_span(n.into(), &expr.as_span());
 
     let asrule = expr.as_rule();
@@ -949,8 +1149,8 @@ pub(crate) fn parse_expr_inner(n: &str, expr: pest::iterators::Pair<'static, Rul
                 Rule::increment => crate::ast::PrefixOperator::Increment,
                 Rule::decrement => crate::ast::PrefixOperator::Decrement,
                 _ => {
-                    emit_error("ICE: unexpected operator", &[(loc.clone(), "in this expr")]);
-                    std::process::exit(9);
+                    emit_error("E0906", "ICE: unexpected operator", &[(loc.clone(), "in this expr")]);
+                    super::exit(9);
                 }
             };
             let part = expr.next().unwrap();
@@ -995,8 +1195,8 @@ pub(crate) fn parse_expr_inner(n: &str, expr: pest::iterators::Pair<'static, Rul
                 Rule::increment => crate::ast::PostfixOperator::Increment,
                 Rule::decrement => crate::ast::PostfixOperator::Decrement,
                 _ => {
-                    emit_error("ICE: unexpected operator", &[(loc.clone(), "in this expr")]);
-                    std::process::exit(9);
+                    emit_error("E0906", "ICE: unexpected operator", &[(loc.clone(), "in this expr")]);
+                    super::exit(9);
                 }
             };
 
@@ -1174,7 +1374,7 @@ pub(crate) fn parse_expr_inner(n: &str, expr: pest::iterators::Pair<'static, Rul
 pub(crate) fn parse_statement(
     n: &str,
     stage: &Stage,
-    stm: pest::iterators::Pair<'static, Rule>,
+    stm: pest::iterators::Pair<'_, Rule>,
     into: &mut Vec<Box<Statement>>,
     current_if_statement: &mut Option<usize>,
 ) {
@@ -1252,9 +1452,14 @@ pub(crate) fn parse_statement(
             let mut stm = stm.into_inner();
             let part = stm.next().unwrap();
             let expr = parse_expr(n, part);
-            let part = stm.next().unwrap();
+            let mut invariants = Vec::new();
+            let mut part = stm.next().unwrap();
+            while part.as_rule() == Rule::loop_invariant {
+                invariants.push(parse_expr(n, part.into_inner().next().unwrap()));
+                part = stm.next().unwrap();
+            }
             let body = parse_block(n, stage, part);
-            into.push(Box::new(Statement::While { expr, body }));
+            into.push(Box::new(Statement::While { expr, invariants, body }));
         }
         Rule::if_stm => {
             let mut stm = stm.into_inner();
@@ -1276,13 +1481,14 @@ pub(crate) fn parse_statement(
             match *current_if_statement {
                 None => {
                     emit_error(
+                        "E0102",
                         "else without if",
                         &[(
                             loc.clone(),
                             "this else branch does not follow an if condition",
                         )],
                     );
-                    std::process::exit(9);
+                    super::exit(9);
                 }
                 Some(c) => {
                     if let Statement::If { ref mut branches } = *into[c] {
@@ -1298,13 +1504,14 @@ pub(crate) fn parse_statement(
             match *current_if_statement {
                 None => {
                     emit_error(
+                        "E0102",
                         "else without if",
                         &[(
                             loc.clone(),
                             "this else branch does not follow an if condition",
                         )],
                     );
-                    std::process::exit(9);
+                    super::exit(9);
                 }
                 Some(c) => {
                     if let Statement::If { ref mut branches } = *into[c] {
@@ -1320,6 +1527,7 @@ pub(crate) fn parse_statement(
             let mut expr1 = Vec::new();
             let mut expr2 = None;
             let mut expr3 = Vec::new();
+            let mut invariants = Vec::new();
             let mut block = None;
             let mut label = None;
 
@@ -1335,6 +1543,9 @@ pub(crate) fn parse_statement(
                     Rule::semicolon => {
                         cur += 1;
                     }
+                    Rule::loop_invariant => {
+                        invariants.push(parse_expr(n, part.into_inner().next().unwrap()));
+                    }
                     Rule::block if cur == 3 && block.is_none() => {
                         block = Some(parse_block(n, stage, part));
                     }
@@ -1358,6 +1569,7 @@ pub(crate) fn parse_statement(
                 e1: expr1,
                 e2: expr2,
                 e3: expr3,
+                invariants,
                 body: block.unwrap(),
             }));
         }
@@ -1365,7 +1577,7 @@ pub(crate) fn parse_statement(
             let stm = stm.into_inner();
             let mut typed = None;
             let mut assign = None;
-            let mut array = None;
+            let mut array = Array::None;
 
             for part in stm {
                 match part.as_rule() {
@@ -1377,9 +1589,9 @@ pub(crate) fn parse_statement(
                     }
                     Rule::array => {
                         if let Some(expr) = part.into_inner().next() {
-                            array = Some(Some(parse_expr(n, expr)));
+                            array = Array::Sized(parse_expr(n, expr));
                         } else {
-                            array = Some(None);
+                            array = Array::Unsized;
                         }
                     }
                     e => panic!("unexpected rule {:?} in vardecl", e),
@@ -1417,10 +1629,11 @@ pub(crate) fn parse_statement(
                             Rule::assigneq => AssignOperator::Eq,
                             _ => {
                                 emit_error(
+                                    "E0906",
                                     "ICE: unexpected operator",
                                     &[(loc.clone(), "in this assign expr")],
                                 );
-                                std::process::exit(9);
+                                super::exit(9);
                             }
                         });
                     }
@@ -1450,8 +1663,8 @@ pub(crate) fn parse_statement(
                 let ppart = part.next().unwrap();
                 if ppart.as_rule() == Rule::key_default {
                     if default.is_some() {
-                        emit_error("multiple default cases", &[(loc.clone(), "in this switch")]);
-                        std::process::exit(9);
+                        emit_error("E0103", "multiple default cases", &[(loc.clone(), "in this switch")]);
+                        super::exit(9);
                     } else {
                         default = Some(parse_block(n, stage, part.next().unwrap()));
                     }
@@ -1509,7 +1722,7 @@ pub(crate) fn parse_statement(
 pub(crate) fn parse_block(
     n: &str,
     stage: &Stage,
-    decl: pest::iterators::Pair<'static, Rule>,
+    decl: pest::iterators::Pair<'_, Rule>,
 ) -> Block {
     match decl.as_rule() {
         Rule::block => {}
@@ -1547,7 +1760,7 @@ pub(crate) struct TypedName {
     tags:   Tags,
 }
 
-fn parse_named_type_tail(n: &str, decl: pest::iterators::Pair<'static, Rule>) -> Tail {
+fn parse_named_type_tail(n: &str, decl: pest::iterators::Pair<'_, Rule>) -> Tail {
     let loc = Location::from_span(n.into(), &decl.as_span());
     let mut part = decl.as_str().to_string();
     part.remove(0);
@@ -1562,7 +1775,7 @@ fn parse_named_type_tail(n: &str, decl: pest::iterators::Pair<'static, Rule>) ->
     }
 }
 
-pub(crate) fn parse_named_type(n: &str, decl: pest::iterators::Pair<'static, Rule>) -> TypedName {
+pub(crate) fn parse_named_type(n: &str, decl: pest::iterators::Pair<'_, Rule>) -> TypedName {
     match decl.as_rule() {
         Rule::named_type => {}
         _ => {
@@ -1629,22 +1842,22 @@ pub(crate) fn parse_named_type(n: &str, decl: pest::iterators::Pair<'static, Rul
     }
 
     // the local variable name is on the right;
-    let mut decl: Vec<pest::iterators::Pair<'static, Rule>> = decl.collect();
+    let mut decl: Vec<pest::iterators::Pair<'_, Rule>> = decl.collect();
     let name_part = decl.pop().unwrap();
     let name = match name_part.as_rule() {
         Rule::ident => {
             let name = name_part.as_str().to_string();
             if name == "return" {
                 let loc = Location::from_span(n.into(), &name_part.as_span());
-                emit_error("syntax error", &[(loc, "llegal use of keyword 'return'")]);
-                std::process::exit(9);
+                emit_error("E0101", "syntax error", &[(loc, "llegal use of keyword 'return'")]);
+                super::exit(9);
             }
             name
         }
         _ => {
             let loc = Location::from_span(n.into(), &name_part.as_span());
-            emit_error("syntax error", &[(loc.clone(), "expected a name")]);
-            std::process::exit(9);
+            emit_error("E0101", "syntax error", &[(loc.clone(), "expected a name")]);
+            super::exit(9);
         }
     };
 
@@ -1690,7 +1903,7 @@ pub(crate) fn parse_named_type(n: &str, decl: pest::iterators::Pair<'static, Rul
     }
 }
 
-pub(crate) fn parse_anon_type(n: &str, decl: pest::iterators::Pair<'static, Rule>) -> Typed {
+pub(crate) fn parse_anon_type(n: &str, decl: pest::iterators::Pair<'_, Rule>) -> Typed {
     match decl.as_rule() {
         Rule::anon_type => {}
         _ => {
@@ -1798,13 +2011,14 @@ pub(crate) fn parse_anon_type(n: &str, decl: pest::iterators::Pair<'static, Rule
 
     for (_, tag) in tags.0 {
         emit_error(
+            "E0101",
             "syntax error",
             &[(
                 tag.iter().next().unwrap().1.clone(),
                 "anonymous type cannot have storage tags (yet)",
             )],
         );
-        std::process::exit(9);
+        super::exit(9);
     }
 
     Typed {
@@ -1864,7 +2078,7 @@ pub(crate) fn parse_importname(
     (Name(v), locals)
 }
 
-fn parse_call(n: &str, expr: pest::iterators::Pair<'static, Rule>) -> Expression {
+fn parse_call(n: &str, expr: pest::iterators::Pair<'_, Rule>) -> Expression {
     let loc = Location::from_span(n.into(), &expr.as_span());
     let expr = expr.into_inner();
     //let name = expr.next().unwrap();
@@ -1901,12 +2115,30 @@ fn parse_call(n: &str, expr: pest::iterators::Pair<'static, Rule>) -> Expression
     }
 }
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Default)]
+// one line per diagnostic with zz check
+#[derive(Serialize, Deserialize, Default)]
 pub struct JsonError {
+    pub code: String,
+    pub severity: String,
     pub message: String,
-    pub level: String,
+    pub primary: Option<JsonNote>,
+    pub related: Vec<JsonNote>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct JsonNote {
+    pub kind: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub condition: Option<bool>,
+    #[serde(flatten)]
+    pub span: Option<JsonSpan>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct JsonSpan {
     pub file_name: String,
     pub line_start: usize,
     pub line_end: usize,
@@ -1914,42 +2146,120 @@ pub struct JsonError {
     pub column_end: usize,
 }
 
-pub fn emit_error<'a, S1, S2, I>(message: S1, v: I)
+impl JsonSpan {
+    fn from_loc(loc: &Location) -> Option<Self> {
+        if loc.file.is_empty() {
+            return None;
+        }
+        let span = loc.to_span();
+        Some(Self {
+            file_name: loc.file.clone(),
+            line_start: span.start_pos().line_col().0,
+            column_start: span.start_pos().line_col().1,
+            line_end: span.end_pos().line_col().0,
+            column_end: span.end_pos().line_col().1,
+        })
+    }
+}
+
+// a diagnostic the language server kept instead of printing it
+pub struct Collected {
+    pub code: String,
+    pub severity: &'static str,
+    pub message: String,
+    pub details: Vec<(Location, String)>,
+}
+
+static COLLECTING: Mutex<Option<Vec<Collected>>> = Mutex::new(None);
+
+pub fn collecting<R>(f: impl FnOnce() -> R) -> (R, Vec<Collected>) {
+    *COLLECTING.lock().unwrap() = Some(Vec::new());
+    let r = f();
+    (r, COLLECTING.lock().unwrap().take().unwrap_or_default())
+}
+
+fn collected(code: &str, severity: &'static str, message: &str, details: &[(Location, String)]) -> bool {
+    match &mut *COLLECTING.lock().unwrap() {
+        Some(v) => {
+            v.push(Collected {
+                code: code.to_string(),
+                severity,
+                message: message.to_string(),
+                details: details.to_vec(),
+            });
+            true
+        }
+        None => false,
+    }
+}
+
+impl Collected {
+    pub fn to_json(&self) -> JsonError {
+        JsonError::new(&self.code, self.severity, self.message.clone(), &self.details)
+    }
+}
+
+fn emit_json<'a, S2, I>(code: &str, severity: &str, message: String, v: I)
 where
-    S1: std::string::ToString,
     S2: std::string::ToString + 'a,
     I: std::iter::IntoIterator<Item = &'a (Location, S2)>,
 {
-    if ERRORS_AS_JSON.load(Ordering::SeqCst) {
-        let mut j = JsonError::default();
-        j.message = message.to_string();
-        j.level = "error".to_string();
-        j.file_name = "<anon>".to_string();
+    println!("{}", serde_json::to_string(&JsonError::new(code, severity, message, v)).unwrap());
+}
 
-        let mut first = true;
+impl JsonError {
+    // the first detail is where the error is, the rest are notes on how we got there
+    pub fn new<'a, S2, I>(code: &str, severity: &str, message: String, v: I) -> Self
+    where
+        S2: std::string::ToString + 'a,
+        I: std::iter::IntoIterator<Item = &'a (Location, S2)>,
+    {
+        let mut j = JsonError {
+            code: code.to_string(),
+            severity: severity.to_string(),
+            message,
+            ..Default::default()
+        };
         for (loc, message) in v.into_iter() {
-            let span = loc.to_span();
-            j.file_name = loc.file.clone();
-            j.line_start = span.start_pos().line_col().0;
-            j.column_start = span.start_pos().line_col().1;
-            j.line_end = span.end_pos().line_col().0;
-            j.column_end = span.end_pos().line_col().1;
-
-            if first {
-                println!("{}", serde_json::to_string(&j).unwrap());
-                first = false;
+            let message = message.to_string();
+            let (kind, condition) = super::diagnostics::note_kind(&message);
+            let note = JsonNote {
+                kind: kind.to_string(),
+                message,
+                condition,
+                span: JsonSpan::from_loc(loc),
+            };
+            if j.primary.is_none() && j.related.is_empty() {
+                j.primary = Some(JsonNote {
+                    kind: "primary".to_string(),
+                    ..note
+                });
+            } else {
+                j.related.push(note);
             }
-
-            j.level = "W".to_string();
-            j.message = message.to_string();
-            println!("{}", serde_json::to_string(&j).unwrap());
         }
+        j
+    }
+}
 
+pub fn emit_error<'a, S1, S2, I>(code: &str, message: S1, v: I)
+where
+    S1: std::string::ToString,
+    S2: std::string::ToString + 'a,
+    I: std::iter::IntoIterator<Item = &'a (Location, S2)>,
+{
+    let message = message.to_string();
+    let v: Vec<(Location, String)> = v.into_iter().map(|(loc, m)| (loc.clone(), m.to_string())).collect();
+    if collected(code, "error", &message, &v) {
+        return;
+    }
+    if ERRORS_AS_JSON.load(Ordering::SeqCst) {
+        emit_json(code, "error", message, &v);
         return;
     }
 
-    let mut s: String = message.to_string();
-    for (loc, message) in v.into_iter() {
+    let mut s = format!("{}: {}", code, message);
+    for (loc, message) in v.iter() {
         let span = loc.to_span();
 
         let e = pest::error::Error::<Rule>::new_from_span(
@@ -1961,36 +2271,44 @@ where
         .with_path(&loc.file);
         s += &format!("\n{}\n", e);
     }
+    s += &explain_hint(code);
     error!("{}", s);
 }
 
-pub fn emit_warn<'a, S1, S2, I>(message: S1, v: I)
+fn explain_hint(code: &str) -> String {
+    if super::diagnostics::explanation(code).is_some() {
+        format!("\nrun `zz explain-error {}` for more about this error\n", code)
+    } else {
+        String::new()
+    }
+}
+
+pub fn emit_warn<'a, S1, S2, I>(code: &str, message: S1, v: I)
 where
     S1: std::string::ToString,
     S2: std::string::ToString + 'a,
     I: std::iter::IntoIterator<Item = &'a (Location, S2)>,
 {
+    let message = message.to_string();
+    let v: Vec<(Location, String)> = v.into_iter().map(|(loc, m)| (loc.clone(), m.to_string())).collect();
+    // like lints, --deny-warnings is about the project and not its dependencies
+    if super::lint::DENY_WARNINGS.load(Ordering::Relaxed) && v.first().map(|(loc, _)| super::lint::in_project(loc)).unwrap_or(true) {
+        super::lint::DENIED.store(true, Ordering::Relaxed);
+        emit_error(code, message, &v);
+        return;
+    }
+    // a cached proof repeats its warnings
+    super::proofcache::record(code, &message, &v);
+    if collected(code, "warning", &message, &v) {
+        return;
+    }
     if ERRORS_AS_JSON.load(Ordering::SeqCst) {
-        let mut j = JsonError::default();
-        j.message = message.to_string();
-        j.level = "warn".to_string();
-        j.file_name = "<anon>".to_string();
-
-        if let Some((loc, _)) = v.into_iter().next() {
-            let span = loc.to_span();
-            j.file_name = loc.file.clone();
-            j.line_start = span.start_pos().line_col().0;
-            j.column_start = span.start_pos().line_col().1;
-            j.line_end = span.end_pos().line_col().0;
-            j.column_end = span.end_pos().line_col().1;
-        }
-
-        println!("{}", serde_json::to_string(&j).unwrap());
+        emit_json(code, "warning", message, &v);
         return;
     }
 
-    let mut s: String = message.to_string();
-    for (loc, message) in v.into_iter() {
+    let mut s = format!("{}: {}", code, message);
+    for (loc, message) in v.iter() {
         let span = loc.to_span();
         let e = pest::error::Error::<Rule>::new_from_span(
             pest::error::ErrorVariant::CustomError {
@@ -2001,6 +2319,7 @@ where
         .with_path(&loc.file);
         s += &format!("\n{}", e);
     }
+    s += &explain_hint(code);
     warn!("{}", s);
 }
 
@@ -2044,10 +2363,11 @@ fn unescape(s: &str, loc: &Location) -> Vec<u8> {
                         .fold(0, |acc, c| acc * 16 + c.to_digit(16).unwrap());
                     if value > 255 {
                         emit_error(
+                            "E0106",
                             "hex value too big for char",
                             &[(loc.clone(), "in this literal string")],
                         );
-                        std::process::exit(9);
+                        super::exit(9);
                     }
                     value as u8
                 }
@@ -2064,10 +2384,11 @@ fn unescape(s: &str, loc: &Location) -> Vec<u8> {
                 Some('\'') => '\'' as u8,
                 _ => {
                     emit_error(
+                        "E0107",
                         "unsupported escape character",
                         &[(loc.clone(), "in this literal string")],
                     );
-                    std::process::exit(9);
+                    super::exit(9);
                 }
             }
         })
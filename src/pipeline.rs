@@ -1,18 +1,23 @@
 use super::abs;
 use super::ast;
+use super::builddb;
+use super::datamodel::DataModel;
 use super::emitter;
 use super::expand;
 use super::flatten;
+use super::lint;
 use super::loader;
 use super::make;
 use super::makro;
 use super::parser;
 use super::project;
+use super::smt;
 use super::symbolic;
 use super::Name;
 use rayon::prelude::*;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::path::Path;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::io::Write;
@@ -30,6 +35,7 @@ pub struct Pipeline {
 
     ext: abs::Ext,
     completed_abs: HashSet<Name>,
+    macro_modules: HashSet<Name>,
     macros_available: bool,
     working_on_these: Arc<Mutex<HashSet<String>>>,
 }
@@ -55,6 +61,7 @@ impl Pipeline {
             modules,
             ext: abs::Ext::new(),
             completed_abs: HashSet::new(),
+            macro_modules: HashSet::new(),
             macros_available: false,
             working_on_these: Arc::new(Mutex::new(HashSet::new())),
         }
@@ -72,6 +79,8 @@ impl Pipeline {
                 (project::ArtifactType::NodeModule, super::BuildSet::Export) => (),
                 (project::ArtifactType::CMake, super::BuildSet::Export) => (),
                 (project::ArtifactType::Esp32, super::BuildSet::Export) => (),
+                (project::ArtifactType::Ninja, super::BuildSet::Export) => (),
+                (project::ArtifactType::Meson, super::BuildSet::Export) => (),
                 (_, super::BuildSet::Export) => continue,
 
                 (_, super::BuildSet::Named(name)) if &artifact.name == name => (),
@@ -118,6 +127,8 @@ impl Pipeline {
                 typ: project::ArtifactType::Macro,
                 ..Default::default()
             };
+            // a copy of the module it was declared in, so its imports aren't its own
+            self.macro_modules.insert(macromod.name.clone());
             self.modules
                 .insert(macromod.name.clone(), loader::Module::ZZ(macromod));
             self.do_artifact(artifact, &super::BuildSet::Run);
@@ -140,7 +151,8 @@ impl Pipeline {
             match &mut md {
                 loader::Module::C(_) => (),
                 loader::Module::ZZ(ast) => {
-                    if !abs::abs(ast, &self.modules, self.ext.clone(), self.macros_available) {
+                    let lint_unused = !self.macro_modules.contains(&name);
+                    if !abs::abs(ast, &self.modules, self.ext.clone(), self.macros_available, lint_unused) {
                         self.completed_abs.remove(&name);
                     }
                 }
@@ -151,15 +163,21 @@ impl Pipeline {
     }
 
     fn do_emit(&self, ast: &mut ast::Module, make: &make::Make) -> Result<emitter::CFile, Option<super::Error>> {
-        if let Some(v) = self.from_buildcache(&ast.name) {
-            return Ok(v);
+        // explaining needs the executor to actually run
+        if symbolic::EXPLAIN.lock().unwrap().is_none() {
+            if let Some(v) = self.from_buildcache(&ast.name) {
+                for warning in &v.warnings {
+                    warning.emit();
+                }
+                return Ok(v);
+            }
         }
 
         let mut module = flatten::flatten(ast, &self.modules, self.ext.clone());
         expand::expand(&mut module).map_err(|e| Some(e))?;
-        let (ok, complete) = symbolic::execute(&mut module, false /*TODO*/);
+        let (ok, complete, warnings) = symbolic::execute(&mut module, false /*TODO*/);
         if !ok {
-            return Err(Some(super::Error::new("aborted due to previous smt errors".to_string(), Vec::new())));
+            return Err(Some(super::Error::new("E0521", "aborted due to previous smt errors".to_string(), Vec::new())));
         }
         if !complete {
             log::debug!("incomplete: {}", ast.name);
@@ -205,10 +223,11 @@ impl Pipeline {
         let em =
             super::emitter::Emitter::new(&self.project.project, self.stage.clone(), module, false);
         let mut cf = em.emit();
+        cf.warnings = warnings;
 
         make.getflags(&mut cf);
 
-        if complete {
+        if complete && !lint::denied() {
             self.to_buildcache(&cf);
         }
 
@@ -251,6 +270,7 @@ impl Pipeline {
                                 symbols: HashSet::new(),
                                 cflags: Vec::new(),
                                 lflags: Vec::new(),
+                                warnings: Vec::new(),
                             },
                         ))
                     }
@@ -270,7 +290,7 @@ impl Pipeline {
                                 None
                             }
                             Err(Some(e)) => {
-                                parser::emit_error(e.message.clone(), &e.details);
+                                parser::emit_error(e.code, e.message.clone(), &e.details);
                                 ABORT.store(true, Ordering::Relaxed);
                                 None
                             }
@@ -281,8 +301,10 @@ impl Pipeline {
             })
             .collect::<HashMap<Name, emitter::CFile>>();
 
-        if ABORT.load(Ordering::Relaxed) {
-            std::process::exit(1);
+        // keeps the modules that were proven, even when others weren't
+        builddb::flush();
+        if ABORT.load(Ordering::Relaxed) || lint::denied() {
+            super::exit(1);
         }
         if let super::BuildSet::Check(_) = &buildset {
             return;
@@ -345,7 +367,8 @@ impl Pipeline {
 
     fn to_buildcache(&self, cf: &emitter::CFile) {
         let (_, outname) = emitter::outname(&self.project.project, &self.stage, &cf.name, false);
-        let cachename = format!("{}.buildcache", outname);
+        // a cached module was only proven for the current data model, lint levels, solvers and timeout
+        let cachename = Self::buildcache_name(&outname);
 
         let mut cachefile =
             std::fs::File::create(&cachename).expect(&format!("cannot create {}", cachename));
@@ -353,12 +376,32 @@ impl Pipeline {
         cachefile.write(
             &rmp_serde::to_vec(&cf).expect(&format!("cannot encode {}", cachename))[..]
         ).expect(&format!("cannot write {}", cachename));
+
+        builddb::record(Path::new(&cachename), Self::buildcache_key(cf, &outname));
+    }
+
+    fn buildcache_name(outname: &str) -> String {
+        format!(
+            "{}.{}.{}.{}.buildcache",
+            outname,
+            DataModel::current(),
+            lint::fingerprint(),
+            smt::fingerprint()
+        )
+    }
+
+    // the sources the module was emitted from, and the c that came out
+    fn buildcache_key(cf: &emitter::CFile, outname: &str) -> String {
+        let mut hasher = builddb::Hasher::new();
+        hasher.files(&cf.sources);
+        hasher.file(Path::new(outname));
+        hasher.finish()
     }
 
     fn from_buildcache(&self, module: &Name) -> Option<emitter::CFile> {
         let (_, outname) = emitter::outname(&self.project.project, &self.stage, module, false);
 
-        let cachename = format!("{}.buildcache", outname);
+        let cachename = Self::buildcache_name(&outname);
         let cached: Option<emitter::CFile> = match std::fs::File::open(&cachename) {
             Ok(f) => match rmp_serde::from_read(&f) {
                 Ok(cf) => Some(cf),
@@ -372,7 +415,7 @@ impl Pipeline {
         };
 
         if let Some(cached) = cached {
-            if !cached.is_newer_than(&outname) && !cached.is_newer_than(&cachename) {
+            if builddb::fresh(Path::new(&cachename), &Self::buildcache_key(&cached, &outname)) {
                 return Some(cached);
             }
         }
@@ -392,7 +435,9 @@ impl Pipeline {
 
     fn pb_done(&self, action: &str, on: String) {
         self.working_on_these.lock().unwrap().remove(&on);
-        self.pb.lock().unwrap().inc();
+        if !self.silent {
+            self.pb.lock().unwrap().inc();
+        }
         self.pb_tick(action);
     }
 
@@ -0,0 +1,268 @@
+use crate::smt::SolverKind;
+use rsmt2::errors::ErrorKind;
+use rsmt2::SmtRes;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::time::Duration;
+
+// the same query stream goes to several solvers at once.
+// the first one to come back with sat or unsat wins, the others are killed
+// and the winner gets the rest of the stream on its own.
+pub struct Portfolio {
+    members: Vec<Member>,
+    lines: Receiver<(usize, String)>,
+    tee: std::fs::File,
+    record: PathBuf,
+    // what the winner is recorded for, so a changed function races again
+    fingerprint: String,
+    timeout: u64,
+}
+
+// how much longer than its own timeout a solver may take to answer before it counts as hung
+const GRACE: Duration = Duration::from_secs(2);
+
+struct Member {
+    id: usize,
+    kind: &'static SolverKind,
+    child: Child,
+    stdin: ChildStdin,
+}
+
+impl Portfolio {
+    pub fn new(
+        kinds: &[&'static SolverKind],
+        tee: &str,
+        record: PathBuf,
+        fingerprint: &str,
+        timeout: u64,
+    ) -> Self {
+        let (tx, lines) = channel();
+        let mut tee = std::fs::File::create(tee).unwrap();
+        writeln!(tee, "; portfolio of {}", kinds.iter().map(|k| k.name).collect::<Vec<&str>>().join(", "))
+            .unwrap();
+
+        let mut members = Vec::new();
+        for (id, kind) in kinds.iter().enumerate() {
+            let cmd = match kind.command() {
+                Some(cmd) => cmd,
+                None => continue,
+            };
+            let conf = kind.conf(cmd, timeout);
+            let mut child = match Command::new(conf.get_cmd())
+                .args(conf.get_options())
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+            {
+                Ok(child) => child,
+                Err(e) => {
+                    warn!("cannot start {}: {}", kind.name, e);
+                    continue;
+                }
+            };
+
+            let stdout = child.stdout.take().unwrap();
+            let tx = tx.clone();
+            std::thread::spawn(move || {
+                for line in BufReader::new(stdout).lines() {
+                    match line {
+                        Ok(line) => {
+                            if tx.send((id, line)).is_err() {
+                                break;
+                            }
+                        }
+                        Err(_) => break,
+                    }
+                }
+            });
+
+            let mut stdin = child.stdin.take().unwrap();
+            if conf.get_models() {
+                writeln!(stdin, "(set-option :produce-models true)").ok();
+            }
+            members.push(Member {
+                id,
+                kind,
+                child,
+                stdin,
+            });
+        }
+
+        Portfolio {
+            members,
+            lines,
+            tee,
+            record,
+            fingerprint: fingerprint.to_string(),
+            timeout,
+        }
+    }
+
+    // the solver that won this function last time, if the function didn't change since
+    pub fn recorded(record: &PathBuf, fingerprint: &str) -> Option<&'static SolverKind> {
+        let recorded = std::fs::read_to_string(record).ok()?;
+        let mut lines = recorded.lines();
+        let name = lines.next()?;
+        if lines.next() != Some(fingerprint) {
+            return None;
+        }
+        SolverKind::by_name(name.trim()).ok()
+    }
+
+    pub fn assert(&mut self, expr: &str) -> SmtRes<()> {
+        writeln!(self, "(assert {})", expr)?;
+        Ok(())
+    }
+
+    pub fn push(&mut self, n: u8) -> SmtRes<()> {
+        writeln!(self, "(push {})", n)?;
+        Ok(())
+    }
+
+    pub fn pop(&mut self, n: u8) -> SmtRes<()> {
+        writeln!(self, "(pop {})", n)?;
+        Ok(())
+    }
+
+    pub fn check_sat(&mut self) -> SmtRes<bool> {
+        writeln!(self, "(check-sat)")?;
+        self.flush()?;
+
+        let mut waiting: Vec<usize> = self.members.iter().map(|m| m.id).collect();
+        while !waiting.is_empty() {
+            let (id, line) = match self.next_line() {
+                Ok(v) => v,
+                Err(RecvTimeoutError::Timeout) => {
+                    // anything they say now would be taken as the answer to the next query
+                    for id in &waiting {
+                        warn!("{} did not answer within the timeout", self.name(*id));
+                    }
+                    self.kill(|m| waiting.contains(&m.id));
+                    return Err(ErrorKind::Timeout.into());
+                }
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+            // late output of a solver we killed
+            if !waiting.contains(&id) {
+                continue;
+            }
+            let r = match line.trim() {
+                "sat" => true,
+                "unsat" => false,
+                "unknown" | "timeout" => {
+                    waiting.retain(|w| *w != id);
+                    continue;
+                }
+                "success" | "" => continue,
+                _ => {
+                    // most likely an error. this one is out of the race
+                    warn!("{}: {}", self.name(id), line);
+                    waiting.retain(|w| *w != id);
+                    self.kill(|m| m.id == id);
+                    continue;
+                }
+            };
+            self.win(id);
+            return Ok(r);
+        }
+        Err(ErrorKind::Unknown.into())
+    }
+
+    // only called after check_sat, so there's only the winner left
+    pub fn get_value(&mut self, expr: &str) -> SmtRes<String> {
+        writeln!(self, "(get-value ({}))", expr)?;
+        self.flush()?;
+
+        let id = match self.members.first() {
+            Some(m) => m.id,
+            None => return Err(ErrorKind::SolverError(format!("all solvers died")).into()),
+        };
+
+        let mut out = String::new();
+        let mut depth = 0i32;
+        loop {
+            let (from, line) = self.next_line().map_err(|e| match e {
+                RecvTimeoutError::Timeout => {
+                    ErrorKind::SolverError(format!("{} did not answer within the timeout", self.name(id)))
+                }
+                RecvTimeoutError::Disconnected => ErrorKind::SolverError(format!("{} died", self.name(id))),
+            })?;
+            if from != id {
+                continue;
+            }
+            depth += line.matches('(').count() as i32 - line.matches(')').count() as i32;
+            out.push_str(line.trim());
+            out.push(' ');
+            if depth <= 0 && !out.trim().is_empty() {
+                break;
+            }
+        }
+        Ok(out.trim().to_string())
+    }
+
+    fn win(&mut self, id: usize) {
+        if self.members.len() < 2 {
+            return;
+        }
+        let name = self.name(id);
+        debug!("{} won {:?}", name, self.record);
+        writeln!(self.tee, "; {} won", name).ok();
+        self.kill(|m| m.id != id);
+        if let Some(parent) = self.record.parent() {
+            std::fs::create_dir_all(parent).ok();
+        }
+        std::fs::write(&self.record, format!("{}\n{}\n", name, self.fingerprint)).ok();
+    }
+
+    // every member is started with the timeout, one that is silent for much longer hangs
+    fn next_line(&self) -> Result<(usize, String), RecvTimeoutError> {
+        if self.timeout == 0 {
+            return self.lines.recv().map_err(|_| RecvTimeoutError::Disconnected);
+        }
+        self.lines.recv_timeout(Duration::from_millis(self.timeout) + GRACE)
+    }
+
+    fn kill<F: Fn(&Member) -> bool>(&mut self, which: F) {
+        for m in self.members.iter_mut().filter(|m| which(m)) {
+            m.child.kill().ok();
+            m.child.wait().ok();
+        }
+        self.members.retain(|m| !which(m));
+    }
+
+    fn name(&self, id: usize) -> &'static str {
+        self.members.iter().find(|m| m.id == id).map(|m| m.kind.name).unwrap_or("solver")
+    }
+}
+
+impl Write for Portfolio {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.tee.write_all(buf)?;
+        let mut dead = Vec::new();
+        for m in &mut self.members {
+            if m.stdin.write_all(buf).is_err() {
+                dead.push(m.id);
+            }
+        }
+        if !dead.is_empty() {
+            self.kill(|m| dead.contains(&m.id));
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        for m in &mut self.members {
+            m.stdin.flush().ok();
+        }
+        self.tee.flush()
+    }
+}
+
+impl Drop for Portfolio {
+    fn drop(&mut self) {
+        self.kill(|_| true);
+    }
+}
@@ -32,6 +32,10 @@ pub enum ArtifactType {
     Go,
     #[serde(rename = "make")]
     Make,
+    #[serde(rename = "ninja")]
+    Ninja,
+    #[serde(rename = "meson")]
+    Meson,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -69,6 +73,22 @@ pub struct Feature {
     pub cflags: Vec<String>,
 }
 
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Variant {
+    #[serde(default)]
+    pub cincludes: Vec<String>,
+    #[serde(default)]
+    pub cobjects: Vec<String>,
+    #[serde(default)]
+    pub pkgconfig: Vec<String>,
+    #[serde(default)]
+    pub cflags: Vec<String>,
+    #[serde(default)]
+    pub lflags: Vec<String>,
+    #[serde(default)]
+    pub defines: Vec<String>,
+}
+
 #[derive(Clone, Default, Serialize, Deserialize)]
 pub struct Project {
     pub version: String,
@@ -83,6 +103,9 @@ pub struct Project {
     pub pkgconfig: Vec<String>,
     #[serde(default)]
     pub cflags: Vec<String>,
+
+    // data model the prover assumes: lp64, ilp32 or lp32
+    pub target: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -99,6 +122,25 @@ pub struct Config {
 
     #[serde(default)]
     pub repos: HashMap<String, String>,
+
+    #[serde(default)]
+    pub variants: HashMap<String, Variant>,
+
+    #[serde(default)]
+    pub smt: Smt,
+
+    // lint name to allow, warn or deny
+    #[serde(default)]
+    pub lints: HashMap<String, String>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Smt {
+    // yices, z3, cvc5 or bitwuzla. functions can still override it with @solver
+    pub solver: Option<String>,
+    // race these solvers against each other for every function
+    #[serde(default)]
+    pub portfolio: Vec<String>,
 }
 
 pub fn init() {
@@ -120,6 +162,9 @@ pub fn init() {
         },
         dependencies: Some(dependencies),
         repos: HashMap::new(),
+        variants: HashMap::new(),
+        smt: Smt::default(),
+        lints: HashMap::new(),
     };
 
     if !std::env::current_dir().unwrap().join("zz.toml").exists() {
@@ -177,7 +222,7 @@ pub fn load_cwd() -> (PathBuf, Config) {
                         "error: could not find \"zz.toml\" in {:?} or any parent directory",
                         std::env::current_dir().unwrap()
                     );
-                    std::process::exit(9);
+                    super::exit(9);
                 }
             }
         } else {
@@ -203,7 +248,7 @@ pub fn target_dir() -> PathBuf {
                                 "error: could not find \"zz.toml\" in {:?} or any parent directory",
                                 std::env::current_dir().unwrap()
                             );
-                            std::process::exit(9);
+                            super::exit(9);
                         }
                     }
                 } else {
@@ -230,7 +275,7 @@ fn sanitize(s: &mut String) {
         error!(
             "invalid name in \"zz.toml\" cannot be emitted into C : \"{}\"", s
         );
-        std::process::exit(9);
+        super::exit(9);
     }
 }
 
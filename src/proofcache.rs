@@ -0,0 +1,236 @@
+use crate::ast;
+use crate::datamodel::DataModel;
+use crate::flatten;
+use crate::name::Name;
+use crate::smt::SolverKind;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+// a function that was proven before doesn't need to be proven again,
+// unless its own body changed, or the contract of anything it calls,
+// or any type, theory or static it touches, or the solvers and timeout it is proven with.
+// the key covers all of that, so a changed callee body with the same
+// where/model clauses keeps its callers cached.
+// locations are hashed relative to the function, so moving it around the file
+// or editing something above it doesn't invalidate the proof.
+
+// what is stored for a proven function
+#[derive(Serialize, Deserialize)]
+pub struct Entry {
+    pub local: ast::Local,
+    pub warnings: Vec<Warning>,
+}
+
+// a warning emitted while proving, repeated when the proof is taken from the cache
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Warning {
+    pub code: String,
+    pub message: String,
+    pub details: Vec<(ast::Location, String)>,
+}
+
+impl Warning {
+    pub fn emit(&self) {
+        crate::parser::emit_warn(&self.code, &self.message, &self.details);
+    }
+}
+
+thread_local! {
+    // warnings of the proof running on this thread
+    static RECORDING: RefCell<Option<Vec<Warning>>> = const { RefCell::new(None) };
+}
+
+// run a proof and collect the warnings it emits
+pub fn recording<R, F: FnOnce() -> R>(f: F) -> (R, Vec<Warning>) {
+    let outer = RECORDING.with(|r| r.replace(Some(Vec::new())));
+    let result = f();
+    let warnings = RECORDING.with(|r| r.replace(outer)).unwrap_or_default();
+    (result, warnings)
+}
+
+pub fn record(code: &str, message: &str, details: &[(ast::Location, String)]) {
+    RECORDING.with(|r| {
+        if let Some(warnings) = r.borrow_mut().as_mut() {
+            warnings.push(Warning {
+                code: code.to_string(),
+                message: message.to_string(),
+                details: details.to_vec(),
+            });
+        }
+    });
+}
+
+pub fn key(module: &flatten::Module, at: usize, solvers: &[&SolverKind], timeout: u64) -> String {
+    let mut hasher = Sha256::new();
+    hasher.input(env!("CARGO_PKG_VERSION").as_bytes());
+    hasher.input(DataModel::current().to_string().as_bytes());
+    hasher.input(crate::lint::fingerprint().as_bytes());
+
+    // another solver may not prove it, or prove it with different warnings
+    for kind in solvers {
+        hasher.input(format!("{} floats={};", kind.name, kind.floats).as_bytes());
+    }
+    hasher.input(format!("timeout={}", timeout).as_bytes());
+
+    let mut located = serde_json::to_value(&module.d[at].0).unwrap();
+    relative(&mut located, &module.d[at].0.loc);
+    hasher.input(located.to_string().as_bytes());
+    let own = json(&module.d[at].0);
+
+    // flatten doesn't keep a stable order of defs, and may have a forward
+    // declaration next to the complete one
+    let mut defs: BTreeMap<&str, &ast::Local> = BTreeMap::new();
+    for (d, complete) in &module.d {
+        if *complete == flatten::TypeComplete::Complete || !defs.contains_key(d.name.as_str()) {
+            defs.insert(&d.name, d);
+        }
+    }
+    defs.remove(module.d[at].0.name.as_str());
+
+    // transitive closure over everything mentioned by name
+    let mut deps = BTreeMap::new();
+    let mut todo = vec![own];
+    while let Some(text) = todo.pop() {
+        for (name, d) in &defs {
+            if deps.contains_key(name) {
+                continue;
+            }
+            if !text.contains(&serde_json::to_string(&Name::from(*name)).unwrap()) {
+                continue;
+            }
+            let dep = contract(d);
+            todo.push(dep.clone());
+            deps.insert(name, dep);
+        }
+    }
+    for dep in deps.values() {
+        hasher.input(dep.as_bytes());
+    }
+
+    format!("{:x}", hasher.result())
+}
+
+// a callee is only visible to its callers through its signature.
+// where it is doesn't matter
+fn contract(d: &ast::Local) -> String {
+    let mut v = match &d.def {
+        ast::Def::Function {
+            ret,
+            args,
+            attr,
+            vararg,
+            callassert,
+            calleffect,
+            callattests,
+            ..
+        } => serde_json::to_value((&d.name, ret, args, attr, vararg, callassert, calleffect, callattests)).unwrap(),
+        _ => serde_json::to_value(d).unwrap(),
+    };
+    strip(&mut v);
+    v.to_string()
+}
+
+// hashmaps in the ast have no stable order, but serde_json::Value does
+fn json(d: &ast::Local) -> String {
+    serde_json::to_value(d).unwrap().to_string()
+}
+
+fn is_location(v: &serde_json::Map<String, serde_json::Value>) -> bool {
+    v.len() == 4 && ["file", "line", "start", "end"].iter().all(|k| v.contains_key(*k))
+}
+
+fn strip(v: &mut serde_json::Value) {
+    match v {
+        serde_json::Value::Object(o) if is_location(o) => *v = serde_json::Value::Null,
+        serde_json::Value::Object(o) => o.values_mut().for_each(strip),
+        serde_json::Value::Array(a) => a.iter_mut().for_each(strip),
+        _ => (),
+    }
+}
+
+// locations inside the function become offsets from its start.
+// anything elsewhere stays where it is
+fn relative(v: &mut serde_json::Value, within: &ast::Location) {
+    match v {
+        serde_json::Value::Object(o) if is_location(o) => {
+            if let Ok(loc) = serde_json::from_value::<ast::Location>(v.clone()) {
+                if inside(&loc, within) {
+                    *v = serde_json::json!([loc.line - within.line, loc.start - within.start, loc.end - within.start]);
+                }
+            }
+        }
+        serde_json::Value::Object(o) => o.values_mut().for_each(|v| relative(v, within)),
+        serde_json::Value::Array(a) => a.iter_mut().for_each(|v| relative(v, within)),
+        _ => (),
+    }
+}
+
+fn inside(loc: &ast::Location, within: &ast::Location) -> bool {
+    loc.file == within.file && loc.start >= within.start && loc.end <= within.end && loc.line >= within.line
+}
+
+// the entry was stored when the function was somewhere else
+fn relocate(v: &mut serde_json::Value, from: &ast::Location, to: &ast::Location) {
+    match v {
+        serde_json::Value::Object(o) if is_location(o) => {
+            if let Ok(mut loc) = serde_json::from_value::<ast::Location>(v.clone()) {
+                if inside(&loc, from) {
+                    loc.file = to.file.clone();
+                    loc.line = loc.line - from.line + to.line;
+                    loc.start = loc.start - from.start + to.start;
+                    loc.end = loc.end - from.start + to.start;
+                    *v = serde_json::to_value(&loc).unwrap();
+                }
+            }
+        }
+        serde_json::Value::Object(o) => o.values_mut().for_each(|v| relocate(v, from, to)),
+        serde_json::Value::Array(a) => a.iter_mut().for_each(|v| relocate(v, from, to)),
+        _ => (),
+    }
+}
+
+fn path(key: &str) -> PathBuf {
+    crate::project::target_dir().join("proofcache").join(key)
+}
+
+// the function after symbolic execution, which rewrites some expressions,
+// moved to where the function is now
+pub fn load(key: &str, at: &ast::Location) -> Option<Entry> {
+    let f = std::fs::File::open(path(key)).ok()?;
+    let entry: Entry = match rmp_serde::from_read(&f) {
+        Ok(entry) => entry,
+        Err(_) => {
+            std::fs::remove_file(path(key)).ok();
+            return None;
+        }
+    };
+    if &entry.local.loc == at {
+        return Some(entry);
+    }
+    let from = entry.local.loc.clone();
+    let mut v = serde_json::to_value(&entry).ok()?;
+    relocate(&mut v, &from, at);
+    serde_json::from_value(v).ok()
+}
+
+pub fn store(key: &str, local: &ast::Local, warnings: Vec<Warning>) {
+    let p = path(key);
+    if let Some(parent) = p.parent() {
+        std::fs::create_dir_all(parent).ok();
+    }
+    let entry = Entry {
+        local: local.clone(),
+        warnings,
+    };
+    match rmp_serde::to_vec(&entry) {
+        Ok(v) => {
+            if let Err(e) = std::fs::write(&p, v) {
+                warn!("cannot write {:?}: {}", p, e);
+            }
+        }
+        Err(e) => warn!("cannot encode {:?}: {}", p, e),
+    }
+}
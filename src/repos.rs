@@ -1,3 +1,4 @@
+use super::builddb;
 use super::project;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -101,6 +102,7 @@ pub fn index(project: &project::Config) -> HashSet<PathBuf> {
         cachefile.write(
             &rmp_serde::to_vec(&index).expect(&format!("cannot encode {:?}", cachepath))[..]
         ).expect(&format!("cannot write {:?}", cachepath));
+        builddb::record(&cachepath, cache_key("zz.toml"));
 
         index
     };
@@ -137,24 +139,12 @@ pub fn index(project: &project::Config) -> HashSet<PathBuf> {
     return searchpaths;
 }
 
-pub fn cache(source_file: &str, cache_file: &Path) -> Option<Index> {
-    let m1 = match std::fs::metadata(&source_file) {
-        Ok(v) => v,
-        Err(_) => return None,
-    };
-    let m1 = m1
-        .modified()
-        .expect(&format!("cannot stat {:?}", source_file));
-
-    let m2 = match std::fs::metadata(&cache_file) {
-        Ok(v) => v,
-        Err(_) => return None,
-    };
-    let m2 = m2
-        .modified()
-        .expect(&format!("cannot stat {:?}", cache_file));
+fn cache_key(source_file: &str) -> String {
+    builddb::Hasher::new().file(Path::new(source_file)).finish()
+}
 
-    if m1 > m2 {
+pub fn cache(source_file: &str, cache_file: &Path) -> Option<Index> {
+    if !builddb::fresh(cache_file, &cache_key(source_file)) {
         return None;
     }
 
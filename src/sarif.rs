@@ -0,0 +1,166 @@
+use super::diagnostics;
+use super::make::Stage;
+use super::parser::{self, JsonError, JsonNote, JsonSpan};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+
+// zz check exits from wherever it fails, so it runs until it exits, with its
+// diagnostics collected instead of printed, and the log is built from them after.
+pub fn check(src: Option<PathBuf>, variant: &str) -> i32 {
+    parser::ERRORS_AS_JSON.store(true, Ordering::SeqCst);
+    let (r, collected) = parser::collecting(|| {
+        super::until_exit(|| super::build(super::BuildSet::Check(src), variant, Stage::test(), false))
+    });
+
+    let root = std::env::current_dir().expect("current dir");
+    let errors: Vec<JsonError> = collected.iter().map(|c| c.to_json()).collect();
+    println!("{}", serde_json::to_string_pretty(&log(&root, &errors)).unwrap());
+
+    match r {
+        Ok(()) => 0,
+        Err(code) => code,
+    }
+}
+
+pub fn log(root: &Path, errors: &[JsonError]) -> Value {
+    let mut rules: BTreeMap<String, Value> = BTreeMap::new();
+    for e in errors {
+        rules.entry(e.code.clone()).or_insert_with(|| rule(&e.code));
+    }
+
+    let index: Vec<&String> = rules.keys().collect();
+    let results: Vec<Value> = errors
+        .iter()
+        .map(|e| result(root, e, index.iter().position(|code| **code == e.code).unwrap()))
+        .collect();
+
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {"driver": {
+                "name": "zz",
+                "version": env!("CARGO_PKG_VERSION"),
+                "informationUri": env!("CARGO_PKG_REPOSITORY"),
+                "rules": rules.values().collect::<Vec<&Value>>(),
+            }},
+            "originalUriBaseIds": {
+                "%SRCROOT%": {"uri": format!("{}/", uri(&root.to_string_lossy()))},
+            },
+            "results": results,
+        }],
+    })
+}
+
+fn rule(code: &str) -> Value {
+    let text = diagnostics::template(code)
+        .map(|template| template.replace("{:?}", "{}").replace("{}", "…"))
+        .unwrap_or_else(|| "unknown diagnostic".to_string());
+    let mut r = json!({
+        "id": code,
+        "shortDescription": {"text": text},
+        "defaultConfiguration": {"level": if code.starts_with('W') { "warning" } else { "error" }},
+    });
+    if let Some(help) = diagnostics::explanation(code) {
+        r["help"] = json!({"text": help, "markdown": help});
+    }
+    r
+}
+
+fn result(root: &Path, e: &JsonError, rule_index: usize) -> Value {
+    let mut r = json!({
+        "ruleId": e.code,
+        "ruleIndex": rule_index,
+        "level": if e.severity == "warning" { "warning" } else { "error" },
+        "message": {"text": e.message},
+    });
+
+    let primary = e.primary.as_ref().and_then(|p| location(root, p));
+    if let Some(primary) = &primary {
+        r["locations"] = json!([primary]);
+    }
+
+    let related: Vec<Value> = e
+        .related
+        .iter()
+        .filter_map(|note| location(root, note))
+        .enumerate()
+        .map(|(i, mut l)| {
+            l["id"] = json!(i);
+            l
+        })
+        .collect();
+    if !related.is_empty() {
+        r["relatedLocations"] = json!(related);
+    }
+
+    // the call into this function, then every branch taken to get to the error.
+    // demonstrate collects branches innermost scope first, source order is what a reader follows
+    let mut branches: Vec<&JsonNote> = e
+        .related
+        .iter()
+        .filter(|note| note.kind == "branch" && note.span.is_some())
+        .collect();
+    branches.sort_by_key(|note| {
+        let span = note.span.as_ref().unwrap();
+        (span.file_name.clone(), span.line_start, span.column_start)
+    });
+    let mut steps: Vec<&JsonNote> = e
+        .related
+        .iter()
+        .filter(|note| note.kind == "callsite" && note.span.is_some())
+        .collect();
+    steps.extend(branches);
+    if steps.is_empty() {
+        return r;
+    }
+    let mut flow: Vec<Value> = steps
+        .into_iter()
+        .filter_map(|note| location(root, note))
+        .map(|l| json!({"location": l}))
+        .collect();
+    if let Some(primary) = primary {
+        flow.push(json!({"location": primary}));
+    }
+    r["codeFlows"] = json!([{"threadFlows": [{"locations": flow}]}]);
+    r
+}
+
+fn location(root: &Path, note: &JsonNote) -> Option<Value> {
+    let span: &JsonSpan = note.span.as_ref()?;
+    let artifact = match Path::new(&span.file_name).strip_prefix(root) {
+        Ok(rel) => json!({"uri": uri(&rel.to_string_lossy()), "uriBaseId": "%SRCROOT%"}),
+        Err(_) => json!({"uri": uri(&span.file_name)}),
+    };
+    Some(json!({
+        "physicalLocation": {
+            "artifactLocation": artifact,
+            "region": {
+                "startLine": span.line_start,
+                "startColumn": span.column_start,
+                "endLine": span.line_end,
+                "endColumn": span.column_end,
+            },
+        },
+        "message": {"text": note.message},
+    }))
+}
+
+// absolute paths become file uris, relative ones only get escaped
+fn uri(path: &str) -> String {
+    if Path::new(path).is_absolute() {
+        if let Ok(v) = url::Url::from_file_path(path) {
+            return v.to_string().trim_end_matches('/').to_string();
+        }
+    }
+    let mut s = String::new();
+    for b in path.bytes() {
+        match b {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => s.push(b as char),
+            _ => s.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    s
+}
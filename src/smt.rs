@@ -6,6 +6,7 @@ use std::collections::HashMap;
 use std::collections::HashSet;
 use std::io::Write;
 use std::sync::atomic::AtomicUsize;
+// milliseconds per query, 0 for none
 pub static TIMEOUT: AtomicUsize = AtomicUsize::new(5000);
 
 pub enum Assertion<T> {
@@ -21,7 +22,7 @@ pub struct Var {
 }
 
 pub struct Solver {
-    solver: RefCell<rsmt2::Solver<Rsmt2Junk>>,
+    solver: RefCell<Backend>,
     vars: RefCell<HashMap<Symbol, Var>>,
     theories: HashMap<Symbol, String>,
     debug_loc: crate::ast::Location,
@@ -32,6 +33,20 @@ pub struct Solver {
     ded_syms: HashMap<Symbol, String>,
 
     assert_counter: usize,
+
+    // yices has no floating point theory, floats are opaque bitvectors there
+    floats: bool,
+    warned_floats: std::cell::Cell<bool>,
+
+    // set while the callback of a query that came back unknown runs, and after solve() gave up
+    undecided: std::cell::Cell<bool>,
+    // what the solver said when it failed on the last query, rather than giving up on it
+    failed: RefCell<Option<String>>,
+    timeout: u64,
+
+    // the portfolio winner that was recorded for this function and now runs alone.
+    // it's forgotten when it can't decide a query, so the next run races again
+    record: Option<std::path::PathBuf>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -39,11 +54,49 @@ pub enum Type {
     Bool,
     Signed(u32),
     Unsigned(u32),
+    // ieee754 with exponent and significand bits
+    Float(u32, u32),
 }
 
 pub struct ModelRef(());
 
 impl Solver {
+    fn sort(&self, t: &Type) -> String {
+        match self.opaque(t.clone()) {
+            Type::Bool => format!("Bool"),
+            Type::Signed(s) | Type::Unsigned(s) => format!("(_ BitVec {})", s),
+            Type::Float(e, s) => format!("(_ FloatingPoint {} {})", e, s),
+        }
+    }
+
+    // without a floating point theory, a float is just its bits
+    fn opaque(&self, t: Type) -> Type {
+        match t {
+            Type::Float(e, s) if !self.floats => Type::Unsigned(e + s),
+            t => t,
+        }
+    }
+
+    fn is_float(&self, sym: &TemporalSymbol) -> bool {
+        if let Type::Float(..) = self.vars.borrow()[&sym.0].typ {
+            if !self.floats && !self.warned_floats.replace(true) {
+                super::lint::emit(
+                    "unproven_float",
+                    "W0101",
+                    &self.debug_loc,
+                    "floating point arithmetic is not proven".to_string(),
+                    &[(
+                        self.debug_loc.clone(),
+                        "the selected solver has no floating point theory. use @solver(\"z3\")",
+                    )],
+                );
+            }
+            true
+        } else {
+            false
+        }
+    }
+
     fn var(&self, sym: &TemporalSymbol) -> String {
         let mut fuckyourust = self.vars.borrow_mut();
         let var = fuckyourust.get_mut(&sym.0).unwrap();
@@ -53,10 +106,7 @@ impl Solver {
                 self.solver.borrow_mut(),
                 "(declare-fun {} () {})\n",
                 name,
-                match var.typ {
-                    Type::Bool => format!("Bool"),
-                    Type::Signed(s) | Type::Unsigned(s) => format!("(_ BitVec {})", s),
-                },
+                self.sort(&var.typ),
             )
             .unwrap();
 
@@ -81,17 +131,31 @@ impl Solver {
                 self.solver.borrow_mut(),
                 "(declare-fun {} () {})\n",
                 name,
-                match var.typ {
-                    Type::Bool => format!("Bool"),
-                    Type::Signed(s) | Type::Unsigned(s) => format!("(_ BitVec {})", s),
-                },
+                self.sort(&var.typ),
             )
             .unwrap();
 
             var.temp.insert(sym.1);
         }
 
-        match (&var.typ, t2) {
+        match (self.opaque(var.typ.clone()), self.opaque(t2)) {
+            (Type::Float(e1, s1), Type::Float(e2, s2)) => {
+                if e1 == e2 && s1 == s2 {
+                    name
+                } else {
+                    format!("((_ to_fp {} {}) RNE {})", e2, s2, name)
+                }
+            }
+            (Type::Float(..), Type::Bool) => format!("(not (fp.isZero {}))", name),
+            (Type::Float(..), Type::Signed(size)) => format!("((_ fp.to_sbv {}) RTZ {})", size, name),
+            (Type::Float(..), Type::Unsigned(size)) => format!("((_ fp.to_ubv {}) RTZ {})", size, name),
+            (Type::Bool, Type::Float(e, s)) => {
+                format!("(ite {} ((_ to_fp {} {}) RNE #x01) (_ +zero {} {}))", name, e, s, e, s)
+            }
+            (Type::Signed(_), Type::Float(e, s)) => format!("((_ to_fp {} {}) RNE {})", e, s, name),
+            (Type::Unsigned(_), Type::Float(e, s)) => {
+                format!("((_ to_fp_unsigned {} {}) RNE {})", e, s, name)
+            }
             (Type::Bool, Type::Bool) => name,
             (Type::Bool, Type::Signed(size)) | (Type::Bool, Type::Unsigned(size)) => {
                 format!("(ite {} (_ bv1 {}) (_ bv0 {}))", name, size, size)
@@ -103,9 +167,9 @@ impl Solver {
             | (Type::Signed(rhs_size), Type::Unsigned(lhs_size))
             | (Type::Unsigned(rhs_size), Type::Unsigned(lhs_size))
             | (Type::Unsigned(rhs_size), Type::Signed(lhs_size)) => {
-                if lhs_size < *rhs_size {
+                if lhs_size < rhs_size {
                     format!("( (_ extract {} {}) {} )", lhs_size - 1, 0, name)
-                } else if lhs_size > *rhs_size {
+                } else if lhs_size > rhs_size {
                     format!("( (_ zero_extend {}) {} )", lhs_size - rhs_size, name)
                 } else {
                     name
@@ -157,40 +221,19 @@ impl Solver {
 
         let mut debug_args = Vec::new();
         for t in args {
-            match t {
-                Type::Bool => {
-                    debug_args.push("Bool".to_string());
-                }
-                Type::Signed(size) | Type::Unsigned(size) => {
-                    debug_args.push(format!("(_ BitVec {})", size));
-                }
-            }
+            debug_args.push(self.sort(&t));
         }
         let debug_args = debug_args.join(" ");
 
-        match t {
-            Type::Signed(size) | Type::Unsigned(size) => {
-                write!(
-                    self.solver.borrow_mut(),
-                    "(declare-fun {} ({}) (_ BitVec {})); theory {}\n",
-                    lname,
-                    debug_args,
-                    size,
-                    name
-                )
-                .unwrap();
-            }
-            Type::Bool => {
-                write!(
-                    self.solver.borrow_mut(),
-                    "(declare-fun {} ({}) Bool); theory {}\n",
-                    lname,
-                    debug_args,
-                    name
-                )
-                .unwrap();
-            }
-        };
+        write!(
+            self.solver.borrow_mut(),
+            "(declare-fun {} ({}) {}); theory {}\n",
+            lname,
+            debug_args,
+            self.sort(&t),
+            name
+        )
+        .unwrap();
         self.theories.insert(sym, lname);
         self.checkpoint();
     }
@@ -332,7 +375,16 @@ impl Solver {
     pub fn literal(&mut self, tmp: Symbol, val: u64, typ: Type) {
         let smt_lhs = self.var(&(tmp, 0));
 
-        match typ {
+        match self.opaque(typ) {
+            Type::Float(e, s) => {
+                self.solver
+                    .borrow_mut()
+                    .assert(&format!(
+                        "(= {} ((_ to_fp_unsigned {} {}) RNE (_ bv{} 64)))\n",
+                        smt_lhs, e, s, val
+                    ))
+                    .unwrap();
+            }
             Type::Unsigned(size) | Type::Signed(size) => {
                 self.solver
                     .borrow_mut()
@@ -353,6 +405,126 @@ impl Solver {
         self.checkpoint();
     }
 
+    // negative integer literals are two's complement, which only matters when they become a float
+    pub fn signed_literal(&mut self, tmp: Symbol, val: i64, typ: Type) {
+        match self.opaque(typ.clone()) {
+            Type::Float(e, s) => {
+                let smt_lhs = self.var(&(tmp, 0));
+                self.solver
+                    .borrow_mut()
+                    .assert(&format!(
+                        "(= {} ((_ to_fp {} {}) RNE (_ bv{} 64)))\n",
+                        smt_lhs, e, s, val as u64
+                    ))
+                    .unwrap();
+                self.checkpoint();
+            }
+            Type::Unsigned(size) | Type::Signed(size) if size < 64 => {
+                self.literal(tmp, val as u64 & ((1u64 << size) - 1), typ)
+            }
+            _ => self.literal(tmp, val as u64, typ),
+        }
+    }
+
+    fn float_const(e: u32, s: u32, val: f64) -> String {
+        // exact bits of the double, rounded into the target precision
+        let bits = val.to_bits();
+        let double = format!(
+            "(fp #b{:01b} #b{:011b} #b{:052b})",
+            bits >> 63,
+            (bits >> 52) & 0x7ff,
+            bits & 0xf_ffff_ffff_ffff
+        );
+        if e == 11 && s == 53 {
+            double
+        } else {
+            format!("((_ to_fp {} {}) RNE {})", e, s, double)
+        }
+    }
+
+    pub fn float_literal(&mut self, tmp: Symbol, val: f64, typ: Type) {
+        let smt_lhs = self.var(&(tmp, 0));
+
+        if let Type::Float(e, s) = self.opaque(typ) {
+            self.solver
+                .borrow_mut()
+                .assert(&format!("(= {} {})\n", smt_lhs, Self::float_const(e, s, val)))
+                .unwrap();
+        }
+        self.checkpoint();
+    }
+
+    // the float must be a number that truncates into the range of the integer type
+    pub fn float_fits(&mut self, tmp: Symbol, from: TemporalSymbol, into: Type) {
+        let smt_tmp = self.var(&(tmp, 0));
+        let smt_from = self.var(&from);
+
+        let is_float = matches!(self.vars.borrow()[&from.0].typ, Type::Float(..));
+        if !is_float {
+            self.solver.borrow_mut().assert(&smt_tmp).unwrap();
+            return;
+        }
+        // every one of these casts is an unproven check, so each is reported
+        if !self.floats {
+            super::lint::emit(
+                "unproven_float",
+                "W0101",
+                &self.debug_loc,
+                "floating point arithmetic is not proven".to_string(),
+                &[(
+                    self.debug_loc.clone(),
+                    "this cast may be out of range for the integer. the selected solver has no floating point theory. use @solver(\"z3\")",
+                )],
+            );
+            self.solver.borrow_mut().assert(&smt_tmp).unwrap();
+            return;
+        }
+        let (e, s) = match self.vars.borrow()[&from.0].typ {
+            Type::Float(e, s) => (e, s),
+            _ => unreachable!(),
+        };
+        let (min, max) = match into {
+            Type::Signed(size) => (
+                format!("(fp.geq {} {})", smt_from, Self::float_const(e, s, -(2f64.powi(size as i32 - 1)))),
+                format!("(fp.lt {} {})", smt_from, Self::float_const(e, s, 2f64.powi(size as i32 - 1))),
+            ),
+            Type::Unsigned(size) => (
+                format!("(fp.gt {} {})", smt_from, Self::float_const(e, s, -1.0)),
+                format!("(fp.lt {} {})", smt_from, Self::float_const(e, s, 2f64.powi(size as i32))),
+            ),
+            _ => ("true".to_string(), "true".to_string()),
+        };
+        self.solver
+            .borrow_mut()
+            .assert(&format!("(= {} (and {} {}))\n", smt_tmp, min, max))
+            .unwrap();
+        self.checkpoint();
+    }
+
+    // fp.isNaN or fp.isInfinite of a float
+    pub fn float_is(&mut self, tmp: Symbol, from: TemporalSymbol, class: &str) {
+        let smt_tmp = self.var(&(tmp, 0));
+        if !self.floats {
+            super::lint::emit(
+                "unproven_float",
+                "W0101",
+                &self.debug_loc,
+                "floating point arithmetic is not proven".to_string(),
+                &[(
+                    self.debug_loc.clone(),
+                    "this is not known. the selected solver has no floating point theory. use @solver(\"z3\")",
+                )],
+            );
+            return;
+        }
+        let smt_from = self.var(&from);
+        self.solver
+            .borrow_mut()
+            .assert(&format!("(= {} ({} {}))\n", smt_tmp, class, smt_from))
+            .unwrap();
+        self.checkpoint();
+    }
+
     pub fn infix_op_will_wrap(
         &self,
         _lhs: TemporalSymbol,
@@ -412,6 +584,31 @@ impl Solver {
         let smt_lhs = self.var(&lhs);
         let smt_rhs = self.var(&rhs);
 
+        if self.is_float(&lhs) {
+            let smt_op = match op {
+                crate::ast::InfixOperator::Equals => format!("(fp.eq {} {})", smt_lhs, smt_rhs),
+                crate::ast::InfixOperator::Nequals => format!("(not (fp.eq {} {}))", smt_lhs, smt_rhs),
+                crate::ast::InfixOperator::Add => format!("(fp.add RNE {} {})", smt_lhs, smt_rhs),
+                crate::ast::InfixOperator::Subtract => format!("(fp.sub RNE {} {})", smt_lhs, smt_rhs),
+                crate::ast::InfixOperator::Multiply => format!("(fp.mul RNE {} {})", smt_lhs, smt_rhs),
+                crate::ast::InfixOperator::Divide => format!("(fp.div RNE {} {})", smt_lhs, smt_rhs),
+                crate::ast::InfixOperator::Moreeq => format!("(fp.geq {} {})", smt_lhs, smt_rhs),
+                crate::ast::InfixOperator::Lesseq => format!("(fp.leq {} {})", smt_lhs, smt_rhs),
+                crate::ast::InfixOperator::Lessthan => format!("(fp.lt {} {})", smt_lhs, smt_rhs),
+                crate::ast::InfixOperator::Morethan => format!("(fp.gt {} {})", smt_lhs, smt_rhs),
+                // not defined on floats in C
+                _ => return,
+            };
+            if self.floats {
+                self.solver
+                    .borrow_mut()
+                    .assert(&format!("(= {} {})", smt_tmp, smt_op))
+                    .unwrap();
+            }
+            self.checkpoint();
+            return;
+        }
+
         match op {
             crate::ast::InfixOperator::Equals => {
                 assert!(t == Type::Bool);
@@ -607,14 +804,27 @@ impl Solver {
         op: crate::ast::PostfixOperator,
         t: Type,
     ) {
-        let size = match t {
+        let smt_to = self.var_as(&to, t.clone());
+        let smt_from = self.var_as(&from, t.clone());
+
+        let size = match self.opaque(t) {
             Type::Signed(v) | Type::Unsigned(v) => v,
+            Type::Float(e, s) => {
+                let one = Self::float_const(e, s, 1.0);
+                let smt_op = match op {
+                    crate::ast::PostfixOperator::Increment => format!("(fp.add RNE {} {})", smt_from, one),
+                    crate::ast::PostfixOperator::Decrement => format!("(fp.sub RNE {} {})", smt_from, one),
+                };
+                self.solver
+                    .borrow_mut()
+                    .assert(&format!("(= {} {} )", smt_to, smt_op))
+                    .unwrap();
+                self.checkpoint();
+                return;
+            }
             Type::Bool => panic!("ICE: postfix_op undefined on bool"),
         };
 
-        let smt_to = self.var_as(&to, t.clone());
-        let smt_from = self.var_as(&from, t.clone());
-
         let smt_op = match op {
             crate::ast::PostfixOperator::Increment => {
                 format!("(bvadd {} (_ bv1 {}))", smt_from, size)
@@ -641,7 +851,20 @@ impl Solver {
         let smt_to = self.var_as(&to, t.clone());
         let smt_from = self.var_as(&from, t.clone());
 
-        match t {
+        match self.opaque(t) {
+            Type::Float(e, s) => {
+                let one = Self::float_const(e, s, 1.0);
+                let smt_op = match op {
+                    crate::ast::PrefixOperator::Increment => format!("(fp.add RNE {} {})", smt_from, one),
+                    crate::ast::PrefixOperator::Decrement => format!("(fp.sub RNE {} {})", smt_from, one),
+                    // not defined on floats in C
+                    _ => return,
+                };
+                self.solver
+                    .borrow_mut()
+                    .assert(&format!("(= {} {} )", smt_to, smt_op))
+                    .unwrap();
+            }
             Type::Signed(size) | Type::Unsigned(size) => {
                 let smt_op = match op {
                     crate::ast::PrefixOperator::Boolnot => format!("(not (= {} ))", smt_from),
@@ -714,6 +937,28 @@ impl Solver {
         //}
     }
 
+    // like attest, but only holds within the current branch
+    pub fn assume(&mut self, lhs: TemporalSymbol, compare_with: bool) -> bool {
+        let mut smt = self.var(&lhs);
+
+        if !compare_with {
+            smt = format!("(not {})", smt);
+        }
+
+        let branch_smt = self.build_branch_bundle();
+        write!(
+            self.solver.borrow_mut(),
+            "(assert (! (=> {} {}) :named A{}))",
+            branch_smt,
+            smt,
+            self.assert_counter,
+        )
+        .unwrap();
+        self.assert_counter += 1;
+
+        self.solve()
+    }
+
     // must call from within assert or value
     pub fn extract(&self, _model: &ModelRef, lhs: TemporalSymbol) -> Option<u64> {
         let smt_lhs = self.var(&lhs);
@@ -770,17 +1015,22 @@ impl Solver {
                 asserts_debug.join(" ")
             ))
             .unwrap();
-        let rs = self.solve();
+        let rs = self.check();
 
         let r = match rs {
-            true => {
+            Some(true) => {
                 write!(self.solver.borrow_mut(), "; sat / failed\n").unwrap();
                 with(false, Some(ModelRef(())))
             }
-            false => {
+            Some(false) => {
                 write!(self.solver.borrow_mut(), "; unsat / pass\n").unwrap();
                 with(true, None)
             }
+            // not proven, but there's no counterexample either. see undecided()
+            None => {
+                write!(self.solver.borrow_mut(), "; unknown / failed\n").unwrap();
+                self.undecided_with(|| with(false, None))
+            }
         };
         self.solver.borrow_mut().pop(1).unwrap();
         r
@@ -793,9 +1043,13 @@ impl Solver {
     {
         let smt_lhs = self.var(&lhs);
 
-        if !self.solve() {
-            warn!("model broke earlier");
-            return with(Assertion::Unsolveable, None);
+        match self.check() {
+            Some(true) => (),
+            Some(false) => {
+                warn!("model broke earlier");
+                return with(Assertion::Unsolveable, None);
+            }
+            None => return self.undecided_with(|| with(Assertion::Unsolveable, None)),
         }
         let value = self
             .solver
@@ -828,8 +1082,8 @@ impl Solver {
             .borrow_mut()
             .assert(&format!("(not (= {} {}))", smt_lhs, value))
             .unwrap();
-        let rr = match self.solve() {
-            false => {
+        let rr = match self.check() {
+            Some(false) => {
                 self.solver.borrow_mut().pop(1).unwrap();
                 self.solver.borrow_mut().push(1).unwrap();
                 if self.solve() {
@@ -838,7 +1092,9 @@ impl Solver {
                     with(Assertion::Constrained(val), None)
                 }
             }
-            true => with(Assertion::Unconstrained(val), Some(ModelRef(()))),
+            Some(true) => with(Assertion::Unconstrained(val), Some(ModelRef(()))),
+            // there may be other values, but no model shows one
+            None => self.undecided_with(|| with(Assertion::Unconstrained(val), None)),
         };
         self.solver.borrow_mut().pop(1).unwrap();
         rr
@@ -851,9 +1107,13 @@ impl Solver {
     {
         let smt_lhs = self.var(&lhs);
 
-        if !self.solve() {
-            warn!("model broke earlier");
-            return with(Assertion::Unsolveable, None);
+        match self.check() {
+            Some(true) => (),
+            Some(false) => {
+                warn!("model broke earlier");
+                return with(Assertion::Unsolveable, None);
+            }
+            None => return self.undecided_with(|| with(Assertion::Unsolveable, None)),
         }
         let value = self
             .solver
@@ -880,8 +1140,8 @@ impl Solver {
             .borrow_mut()
             .assert(&format!("(not (= {} {}))", smt_lhs, value))
             .unwrap();
-        let rr = match self.solve() {
-            false => {
+        let rr = match self.check() {
+            Some(false) => {
                 self.solver.borrow_mut().pop(1).unwrap();
                 self.solver.borrow_mut().push(1).unwrap();
                 if self.solve() {
@@ -890,12 +1150,66 @@ impl Solver {
                     with(Assertion::Constrained(val), None)
                 }
             }
-            true => with(Assertion::Unconstrained(val), Some(ModelRef(()))),
+            Some(true) => with(Assertion::Unconstrained(val), Some(ModelRef(()))),
+            // there may be other values, but no model shows one
+            None => self.undecided_with(|| with(Assertion::Unconstrained(val), None)),
         };
         self.solver.borrow_mut().pop(1).unwrap();
         rr
     }
 
+    // for zz explain. the smt expression of a symbol or a theory applied to symbols
+    pub fn term(&self, sym: &TemporalSymbol) -> String {
+        self.var(sym)
+    }
+
+    pub fn theory_term(&self, theory: Symbol, args: &[TemporalSymbol]) -> String {
+        let args = args.iter().map(|a| self.var(a)).collect::<Vec<String>>();
+        format!("({} {})", self.theories[&theory], args.join(" "))
+    }
+
+    // true if the expression holds on every path reaching the current branch
+    pub fn holds(&self, smt: &str) -> bool {
+        let branch_smt = self.build_branch_bundle();
+        self.solver.borrow_mut().push(1).unwrap();
+        self.solver
+            .borrow_mut()
+            .assert(&format!("(and {} (not {}))", branch_smt, smt))
+            .unwrap();
+        let r = self.check() == Some(false);
+        self.solver.borrow_mut().pop(1).unwrap();
+        r
+    }
+
+    // the narrowest range an integer is proven to be in, and whether it is.
+    // when a search runs out of queries, the range is what was proven so far, which is wider
+    pub fn bounds(&self, smt: &str, t: &Type) -> Option<(i128, i128, bool)> {
+        let (signed, size) = match t {
+            Type::Signed(size) => (true, *size),
+            Type::Unsigned(size) => (false, *size),
+            _ => return None,
+        };
+        if size > 64 {
+            return None;
+        }
+        let (min, max) = if signed {
+            (-(1i128 << (size - 1)), (1i128 << (size - 1)) - 1)
+        } else {
+            (0, (1i128 << size) - 1)
+        };
+        let lit = |v: i128| format!("(_ bv{} {})", v.rem_euclid(1i128 << size), size);
+        let (ge, le) = if signed { ("bvsge", "bvsle") } else { ("bvuge", "bvule") };
+
+        // the lower bound is the last value the integer is proven to be at least
+        let (lower, above) = least(
+            |v| v == max || !self.holds(&format!("({} {} {})", ge, smt, lit(v + 1))),
+            min,
+            max,
+        );
+        let (below, upper) = least(|v| self.holds(&format!("({} {} {})", le, smt, lit(v))), lower, max);
+        Some((lower, upper, lower == above && below == upper))
+    }
+
     pub fn push(&mut self, reason: &str) {
         write!(self.solver.borrow_mut(), ";{}\n", reason).unwrap();
         self.solver.borrow_mut().push(1).unwrap();
@@ -929,6 +1243,7 @@ impl Solver {
     pub fn check_ded(&self, sym: Symbol, here: &crate::ast::Location) {
         if let Some(name) = self.ded_syms.get(&sym) {
             emit_warn(
+                "W0102",
                 format!("ICE: reuse of dead symbol '{}'", name),
                 &[(
                     here.clone(),
@@ -938,16 +1253,59 @@ impl Solver {
         }
     }
 
+    // false when the solver gave up too, but then the error that follows is E0519 and not a conflict
     pub fn solve(&self) -> bool {
-        match self.solver.borrow_mut().check_sat() {
-            Ok(f) => f,
-            Err(e) => {
-                eprintln!("{:?}", e);
-                false
+        let r = self.check();
+        self.undecided.set(r.is_none());
+        r.unwrap_or(false)
+    }
+
+    // None if the solver gave up, usually because of the timeout, or failed.
+    // a solver that doesn't understand the query hasn't proven anything
+    fn check(&self) -> Option<bool> {
+        let r = self.solver.borrow_mut().check_sat();
+        *self.failed.borrow_mut() = match &r {
+            Err(e) if !e.kind().is_unknown() && !e.kind().is_timeout() => {
+                Some(e.to_string().lines().next().unwrap_or("").to_string())
+            }
+            _ => None,
+        };
+        match r {
+            Ok(f) => Some(f),
+            Err(_) => {
+                if let Some(record) = &self.record {
+                    std::fs::remove_file(record).ok();
+                }
+                None
             }
         }
     }
 
+    // the query whose callback is running was neither proven nor disproven
+    pub fn undecided(&self) -> bool {
+        self.undecided.get()
+    }
+
+    fn undecided_with<R, F: FnOnce() -> R>(&self, with: F) -> R {
+        self.undecided.set(true);
+        let r = with();
+        self.undecided.set(false);
+        r
+    }
+
+    // set along with undecided when that's because the solver failed
+    pub fn failure(&self) -> Option<String> {
+        self.failed.borrow().clone()
+    }
+
+    pub fn timeout(&self) -> u64 {
+        self.timeout
+    }
+
+    pub fn last_loc(&self) -> &crate::ast::Location {
+        &self.debug_loc
+    }
+
     #[cfg(debug_assertions)]
     pub fn checkpoint(&self) {
         //if !self.solve() {
@@ -968,49 +1326,72 @@ impl Solver {
         }
     }
 
-    pub fn new(module_name: String, solver: Option<String>) -> Self {
+    /// `fingerprint` identifies the function that is checked, so that the portfolio winner
+    /// recorded for it is only trusted while it doesn't change.
+    pub fn new(module_name: String, kinds: &[&'static SolverKind], timeout: u64, fingerprint: &str) -> Self {
         //Config::set_global_param_value(":model.partial", "true");
         //Config::set_global_param_value(":parallel.enable", "true");
         //config.set_model_generation(true);
 
-        let want_z3 = if let Some(s) = solver.as_ref() { s == "z3"  } else {false};
-
-        let conf = if want_z3 && which::which("z3").is_ok() {
-            rsmt2::SmtConf::default_z3()
-        } else if which::which("yices_smt2_mt").is_ok() {
-            let mut conf = rsmt2::SmtConf::default_yices_2();
-            conf.incremental();
-            conf.cmd("yices_smt2_mt");
-            conf
-        } else if which::which("yices_smt2").is_ok() {
-            let mut conf = rsmt2::SmtConf::default_yices_2();
-            conf.incremental();
-            conf.cmd("yices_smt2");
-            conf
-        } else if which::which("yices-smt2").is_ok() {
-            let mut conf = rsmt2::SmtConf::default_yices_2();
-            conf.incremental();
-            conf.cmd("yices-smt2");
-            conf
-        } else if which::which("z3").is_ok() {
-            rsmt2::SmtConf::default_z3()
-        } else {
-            panic!("z3 or yices-smt2 required in PATH")
-        };
-
         let td = super::project::target_dir().join("ssa");
         std::fs::create_dir_all(&td).unwrap();
         let outfile = format!("{}/{}.smt2", td.to_string_lossy(), module_name);
 
+        let mut kind = kinds[0];
+        let mut recorded = None;
+        if kinds.len() > 1 {
+            // whoever won last time gets to do it alone
+            let record = super::project::target_dir().join("portfolio").join(&module_name);
+            match crate::portfolio::Portfolio::recorded(&record, fingerprint) {
+                Some(winner) if kinds.iter().any(|k| k.name == winner.name) => {
+                    kind = winner;
+                    recorded = Some(record);
+                }
+                _ => {
+                    let floats = kinds.iter().all(|k| k.floats);
+                    let race = crate::portfolio::Portfolio::new(kinds, &outfile, record, fingerprint, timeout);
+                    return Self::with_backend(Backend::Race(race), floats, timeout);
+                }
+            }
+        }
+
+        // z3 linked in doesn't need a process per module
+        #[cfg(feature = "z3")]
+        {
+            if kind.name == "z3" {
+                let mut script = crate::z3::Script::new(timeout);
+                script.path_tee(outfile).unwrap();
+                let mut s = Self::with_backend(Backend::Native(script), true, timeout);
+                s.record = recorded;
+                return s;
+            }
+        }
+
+        let cmd = kind
+            .command()
+            .unwrap_or_else(|| panic!("ICE: solver {} was selected but is not installed", kind.name));
+        let conf = kind.conf(cmd, timeout);
+
         let mut solver = rsmt2::Solver::new(conf, Rsmt2Junk).unwrap();
         solver.path_tee(outfile).unwrap();
 
+        let mut s = Self::with_backend(Backend::Pipe(solver), kind.floats, timeout);
+        s.record = recorded;
+        s
+    }
+
+    fn with_backend(mut solver: Backend, floats: bool, timeout: u64) -> Self {
         //insanly slow and we don't actually use it.
         //write!(solver,"(set-option :produce-unsat-cores true)\n").unwrap();
 
-        write!(solver, "(set-logic QF_UFBV)\n").unwrap();
+        // QF_UFBV has no floating point
+        if floats {
+            write!(solver, "(set-logic ALL)\n").unwrap();
+        } else {
+            write!(solver, "(set-logic QF_UFBV)\n").unwrap();
+        }
         //write!(solver,"(set-option :parallel.enable true)\n").unwrap();
-        //write!(solver,"(set-option :timeout {})\n", TIMEOUT.load(Ordering::Relaxed) as u64).unwrap();
+        // the timeout is a command line option, because only z3 has (set-option :timeout)
 
         Self {
             solver: RefCell::new(solver),
@@ -1021,11 +1402,310 @@ impl Solver {
             symbol_stack: RefCell::new(vec![Vec::new()]),
             ded_syms: HashMap::new(),
             assert_counter: 0,
+            floats,
+            warned_floats: std::cell::Cell::new(false),
+            undecided: std::cell::Cell::new(false),
+            failed: RefCell::new(None),
+            timeout,
+            record: None,
         }
     }
 }
 
-use rsmt2::{parse::ExprParser, parse::ValueParser, SmtRes};
+// smt-lib2 solvers we know how to run.
+// without any configuration, the first one that is installed is used
+pub struct SolverKind {
+    pub name: &'static str,
+    // executables to look for in PATH, in order of preference
+    pub commands: &'static [&'static str],
+    // has the floating point theory
+    pub floats: bool,
+    configure: fn(&str, u64) -> rsmt2::SmtConf,
+}
+
+pub static SOLVERS: &[SolverKind] = &[
+    SolverKind {
+        name: "yices",
+        commands: &["yices_smt2_mt", "yices_smt2", "yices-smt2"],
+        floats: false,
+        configure: |cmd, timeout| {
+            let mut conf = rsmt2::SmtConf::yices_2(cmd);
+            conf.incremental();
+            // yices only does seconds
+            if timeout > 0 {
+                conf.option(format!("--timeout={}", (timeout + 999) / 1000));
+            }
+            conf
+        },
+    },
+    SolverKind {
+        name: "z3",
+        commands: &["z3"],
+        floats: true,
+        configure: |cmd, timeout| {
+            let mut conf = rsmt2::SmtConf::z3(cmd);
+            if timeout > 0 {
+                conf.option(format!("-t:{}", timeout));
+            }
+            conf
+        },
+    },
+    SolverKind {
+        name: "cvc5",
+        commands: &["cvc5"],
+        floats: true,
+        configure: |cmd, timeout| {
+            let mut conf = rsmt2::SmtConf::cvc4(cmd);
+            conf.models();
+            conf.incremental();
+            if timeout > 0 {
+                conf.option(format!("--tlimit-per={}", timeout));
+            }
+            conf
+        },
+    },
+    SolverKind {
+        name: "bitwuzla",
+        commands: &["bitwuzla"],
+        floats: true,
+        configure: |cmd, timeout| {
+            // yices style only because it has no default options. bitwuzla is always incremental
+            let mut conf = rsmt2::SmtConf::yices_2(cmd);
+            conf.option("--lang");
+            conf.option("smt2");
+            conf.option("--produce-models");
+            if timeout > 0 {
+                conf.option(format!("--time-limit-per={}", timeout));
+            }
+            conf
+        },
+    },
+];
+
+static DEFAULT_SOLVER: AtomicUsize = AtomicUsize::new(usize::MAX);
+// bitmask over SOLVERS
+static PORTFOLIO: AtomicUsize = AtomicUsize::new(0);
+
+impl SolverKind {
+    pub fn by_name(name: &str) -> Result<&'static SolverKind, super::Error> {
+        SOLVERS.iter().find(|s| s.name == name).ok_or_else(|| {
+            super::Error::new(
+                "E0804",
+                format!(
+                    "unknown solver \"{}\". expected one of {}",
+                    name,
+                    SOLVERS.iter().map(|s| s.name).collect::<Vec<&str>>().join(", ")
+                ),
+                Vec::new(),
+            )
+        })
+    }
+
+    pub fn conf(&self, cmd: &str, timeout: u64) -> rsmt2::SmtConf {
+        (self.configure)(cmd, timeout)
+    }
+
+    pub fn command(&self) -> Option<&'static str> {
+        self.commands.iter().find(|cmd| which::which(cmd).is_ok()).cloned()
+    }
+
+    pub fn installed(&self) -> bool {
+        #[cfg(feature = "z3")]
+        {
+            if self.name == "z3" {
+                return true;
+            }
+        }
+        self.command().is_some()
+    }
+
+    // the named solver, but only if it can actually be run
+    pub fn require(name: &str) -> Result<&'static SolverKind, super::Error> {
+        let kind = SolverKind::by_name(name)?;
+        if !kind.installed() {
+            return Err(super::Error::new(
+                "E0805",
+                format!(
+                    "solver {} is not installed. none of {} found in PATH",
+                    kind.name,
+                    kind.commands.join(", ")
+                ),
+                Vec::new(),
+            ));
+        }
+        Ok(kind)
+    }
+
+    // zz.toml [smt] solver
+    pub fn set_default(name: &str) -> Result<(), super::Error> {
+        let kind = SolverKind::by_name(name)?;
+        let i = SOLVERS.iter().position(|s| s.name == kind.name).unwrap();
+        DEFAULT_SOLVER.store(i, std::sync::atomic::Ordering::Relaxed);
+        Ok(())
+    }
+
+    // zz.toml [smt] portfolio
+    pub fn set_portfolio(names: &[String]) -> Result<(), super::Error> {
+        let mut mask = 0;
+        for name in names {
+            let kind = SolverKind::by_name(name)?;
+            mask |= 1 << SOLVERS.iter().position(|s| s.name == kind.name).unwrap();
+        }
+        PORTFOLIO.store(mask, std::sync::atomic::Ordering::Relaxed);
+        Ok(())
+    }
+
+    // the solvers a function without @solver is proven with
+    pub fn configured() -> Result<Vec<&'static SolverKind>, super::Error> {
+        let mask = PORTFOLIO.load(std::sync::atomic::Ordering::Relaxed);
+        if mask == 0 {
+            return Ok(vec![SolverKind::default()?]);
+        }
+        let mut kinds = Vec::new();
+        for (i, kind) in SOLVERS.iter().enumerate() {
+            if mask & (1 << i) != 0 {
+                kinds.push(SolverKind::require(kind.name)?);
+            }
+        }
+        Ok(kinds)
+    }
+
+    pub fn default() -> Result<&'static SolverKind, super::Error> {
+        if let Some(kind) = SOLVERS.get(DEFAULT_SOLVER.load(std::sync::atomic::Ordering::Relaxed)) {
+            return SolverKind::require(kind.name);
+        }
+        SOLVERS.iter().find(|s| s.installed()).ok_or_else(|| {
+            super::Error::new(
+                "E0803",
+                format!(
+                    "no smt solver installed. zz needs one of {} in PATH",
+                    SOLVERS.iter().map(|s| s.name).collect::<Vec<&str>>().join(", ")
+                ),
+                Vec::new(),
+            )
+        })
+    }
+}
+
+// the solvers and timeout a module was proven with, and cached for
+pub fn fingerprint() -> String {
+    let solvers = match SolverKind::configured() {
+        Ok(kinds) => kinds.iter().map(|k| k.name).collect::<Vec<&str>>().join("+"),
+        Err(_) => "none".to_string(),
+    };
+    format!("{}-{}", solvers, TIMEOUT.load(std::sync::atomic::Ordering::Relaxed))
+}
+
+use rsmt2::{errors::ErrorKind, parse::ExprParser, parse::ValueParser, SmtRes};
+
+// where the smt script goes. either an external solver process or z3 linked in
+enum Backend {
+    Pipe(rsmt2::Solver<Rsmt2Junk>),
+    Race(crate::portfolio::Portfolio),
+    #[cfg(feature = "z3")]
+    Native(crate::z3::Script),
+}
+
+impl Backend {
+    fn assert(&mut self, expr: &str) -> SmtRes<()> {
+        match self {
+            Backend::Pipe(s) => s.assert(expr),
+            Backend::Race(s) => s.assert(expr),
+            #[cfg(feature = "z3")]
+            Backend::Native(s) => Backend::native(s, &format!("(assert {})", expr)).map(|_| ()),
+        }
+    }
+
+    fn push(&mut self, n: u8) -> SmtRes<()> {
+        match self {
+            Backend::Pipe(s) => s.push(n),
+            Backend::Race(s) => s.push(n),
+            #[cfg(feature = "z3")]
+            Backend::Native(s) => Backend::native(s, &format!("(push {})", n)).map(|_| ()),
+        }
+    }
+
+    fn pop(&mut self, n: u8) -> SmtRes<()> {
+        match self {
+            Backend::Pipe(s) => s.pop(n),
+            Backend::Race(s) => s.pop(n),
+            #[cfg(feature = "z3")]
+            Backend::Native(s) => Backend::native(s, &format!("(pop {})", n)).map(|_| ()),
+        }
+    }
+
+    fn check_sat(&mut self) -> SmtRes<bool> {
+        match self {
+            Backend::Pipe(s) => s.check_sat(),
+            Backend::Race(s) => s.check_sat(),
+            #[cfg(feature = "z3")]
+            Backend::Native(s) => match Backend::native(s, "(check-sat)")?.as_str() {
+                "sat" => Ok(true),
+                "unsat" => Ok(false),
+                "timeout" => Err(ErrorKind::Timeout.into()),
+                _ => Err(ErrorKind::Unknown.into()),
+            },
+        }
+    }
+
+    fn get_values(&mut self, exprs: &[String]) -> SmtRes<Vec<(String, String)>> {
+        match self {
+            Backend::Pipe(s) => s.get_values(exprs),
+            Backend::Race(s) => {
+                let mut r = Vec::new();
+                for expr in exprs {
+                    let out = s.get_value(expr)?;
+                    r.push((expr.clone(), value_reply(expr, &out)?));
+                }
+                Ok(r)
+            }
+            #[cfg(feature = "z3")]
+            Backend::Native(s) => {
+                let mut r = Vec::new();
+                for expr in exprs {
+                    let value = s.value(expr).map_err(ErrorKind::SolverError)?;
+                    r.push((expr.clone(), value));
+                }
+                Ok(r)
+            }
+        }
+    }
+
+    #[cfg(feature = "z3")]
+    fn native(s: &mut crate::z3::Script, cmd: &str) -> SmtRes<String> {
+        s.eval(cmd).map_err(|e| ErrorKind::SolverError(e).into())
+    }
+}
+
+// ((expr value))
+fn value_reply(expr: &str, out: &str) -> SmtRes<String> {
+    let value = out
+        .strip_prefix("((")
+        .and_then(|v| v.strip_prefix(expr))
+        .and_then(|v| v.strip_suffix("))"))
+        .ok_or_else(|| ErrorKind::ParseError(format!("unexpected value"), out.to_string()))?;
+    Ok(value.trim().to_string())
+}
+
+impl Write for Backend {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Backend::Pipe(s) => s.write(buf),
+            Backend::Race(s) => s.write(buf),
+            #[cfg(feature = "z3")]
+            Backend::Native(s) => s.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Backend::Pipe(s) => s.flush(),
+            Backend::Race(s) => s.flush(),
+            #[cfg(feature = "z3")]
+            Backend::Native(s) => s.flush(),
+        }
+    }
+}
 
 #[derive(Clone, Copy)]
 pub struct Rsmt2Junk;
@@ -1039,3 +1719,43 @@ impl<'a> ExprParser<String, (), &'a str> for Rsmt2Junk {
         Ok(input.into())
     }
 }
+
+// a query per step, each up to the solver timeout, so a search has to stop somewhere
+const BOUND_QUERIES: usize = 20;
+
+// narrows down the smallest value in from..=to that passes a test which, once passed, passes for every larger
+// value, and that passes for to. proven bounds are mostly small numbers, so it looks at growing distances
+// from zero before it bisects. the value is somewhere in the returned range, which is a single value unless
+// it ran out of queries
+fn least(mut passes: impl FnMut(i128) -> bool, from: i128, to: i128) -> (i128, i128) {
+    let (mut lo, mut hi) = (from, to);
+    let pivot = 0.max(from).min(to);
+    let mut first = true;
+    let mut down = false;
+    // how far from the pivot the next look is, until the test flips
+    let mut dist = Some(1i128);
+    for _ in 0..BOUND_QUERIES {
+        if lo >= hi {
+            break;
+        }
+        let at = match dist {
+            _ if first => pivot,
+            Some(d) if down && pivot - d > lo => pivot - d,
+            Some(d) if !down && pivot + d < hi => pivot + d,
+            _ => lo + (hi - lo) / 2,
+        };
+        let passed = passes(at);
+        if passed {
+            hi = at;
+        } else {
+            lo = at + 1;
+        }
+        if first {
+            first = false;
+            down = passed;
+        } else if let Some(d) = dist {
+            dist = if passed == down { Some(d * 2 + 1) } else { None };
+        }
+    }
+    (lo, hi)
+}
@@ -1,13 +1,33 @@
 #![allow(unused)]
-use super::parser::{self, emit_debug, emit_warn};
+use super::parser::{self, emit_debug};
 use super::Error;
 use crate::ast;
+use crate::datamodel::DataModel;
 use crate::flatten;
+use crate::lint;
 use crate::expand;
 use crate::name::Name;
+use crate::proofcache;
 use crate::smt::{self, Solver};
 use ast::Tags;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// zz check --emit-counterexamples
+pub static EMIT_COUNTEREXAMPLES: AtomicBool = AtomicBool::new(false);
+
+lazy_static::lazy_static! {
+    // zz explain file:LINE:COL
+    pub static ref EXPLAIN: std::sync::Mutex<Option<ast::Location>> = std::sync::Mutex::new(None);
+}
+
+// what the solver can prove about one name in scope
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct Explained {
+    pub name: String,
+    pub typed: String,
+    pub facts: Vec<String>,
+}
 
 pub type Symbol = usize;
 pub type TemporalSymbol = (Symbol, u64);
@@ -60,6 +80,7 @@ enum Value {
     },
     Unconstrained(String),
     Integer(parser::Integer),
+    Float(f64),
     Macro(Name),
 }
 
@@ -69,6 +90,7 @@ impl std::fmt::Display for Value {
             Value::Void => write!(f, "void"),
             Value::Uninitialized => write!(f, "uninitialized"),
             Value::Integer(s) => write!(f, "integer ({:?})", s),
+            Value::Float(s) => write!(f, "float ({})", s),
             Value::InfixOp { .. } => write!(f, "op"),
             Value::PrefixOp { .. } => write!(f, "op"),
             Value::PostfixOp { .. } => write!(f, "op"),
@@ -109,6 +131,8 @@ struct Scope {
         ast::Location,
         bool, /*only demonstrace if true*/
     )>,
+    // names in the branch conditions that lead into this scope
+    guards: Vec<Name>,
 }
 
 pub struct Symbolic {
@@ -121,14 +145,25 @@ pub struct Symbolic {
     current_function_name: String,
     current_function_ret: Option<Symbol>,
     current_function_model: Vec<ast::Expression>,
+    current_function_args: Vec<(String, TemporalSymbol)>,
     current_call: Vec<ast::Location>,
     in_loop: bool,
     in_model: bool,
     in_callassign_body: bool,
 
+    // invariants and step statements of the loops we're in, checked again on continue
+    loops: Vec<Option<(Vec<ast::Expression>, Vec<Box<ast::Statement>>)>>,
+
 
     macros_available: bool,
     incomplete: bool,
+
+    explain: Option<ast::Location>,
+    explained: Option<Vec<Explained>>,
+
+    // arguments the current function fails with. found by demonstrate,
+    // written out by trace which knows the condition they violate
+    counterexample: std::cell::RefCell<Option<Vec<String>>>,
 }
 
 pub enum ScopeReturn {
@@ -196,9 +231,9 @@ impl Symbolic {
         };
         self.ssa.theory(
             sym,
-            vec![smt::Type::Unsigned(64)],
+            vec![Self::smt_pointer_type()],
             "len",
-            smt::Type::Unsigned(64),
+            smt::Type::Unsigned(DataModel::current().size_width()),
         );
         self.builtin.insert("len".to_string(), sym);
 
@@ -237,7 +272,7 @@ impl Symbolic {
             body: None,
         };
         self.ssa
-            .theory(sym, vec![smt::Type::Unsigned(64)], "safe", smt::Type::Bool);
+            .theory(sym, vec![Self::smt_pointer_type()], "safe", smt::Type::Bool);
         self.builtin.insert("safe".to_string(), sym);
 
         // built in nullterm theory
@@ -276,7 +311,7 @@ impl Symbolic {
         };
         self.ssa.theory(
             sym,
-            vec![smt::Type::Unsigned(64)],
+            vec![Self::smt_pointer_type()],
             "nullterm",
             smt::Type::Bool,
         );
@@ -320,7 +355,7 @@ impl Symbolic {
         };
         self.ssa.theory(
             sym,
-            vec![smt::Type::Unsigned(64)],
+            vec![Self::smt_pointer_type()],
             "symbol",
             smt::Type::Bool,
         );
@@ -406,9 +441,9 @@ impl Symbolic {
         };
         self.ssa.theory(
             sym,
-            vec![smt::Type::Unsigned(64)],
+            vec![Self::smt_pointer_type()],
             "tailof",
-            smt::Type::Unsigned(64),
+            smt::Type::Unsigned(DataModel::current().size_width()),
         );
         self.builtin.insert("tailof".to_string(), sym);
 
@@ -472,6 +507,7 @@ impl Symbolic {
                     let ret = if let Some(ret) = ret {
                         if let ast::Type::Other(_) = ret.typed.t {
                             return Err(self.trace(
+                                "E0514",
                                 format!("theory is unprovable"),
                                 vec![(
                                     ret.typed.loc.clone(),
@@ -483,6 +519,7 @@ impl Symbolic {
                         }
                     } else {
                         return Err(self.trace(
+                            "E0334",
                             format!("theory needs a return value"),
                             vec![(
                                 d.loc.clone(),
@@ -579,7 +616,7 @@ impl Symbolic {
                                     Ok(i)
                                 },
                                 _ => {
-                                    Err(self.trace("array size must be static".to_string(), vec![
+                                    Err(self.trace("E0320", "array size must be static".to_string(), vec![
                                         (expr.loc().clone(), format!("expression cannot be reduced to a constrained value at compile time"))
                                     ]))
                                 }
@@ -769,6 +806,7 @@ impl Symbolic {
                         )?;
                         if !self.ssa.solve() {
                             return Err(self.trace(
+                                "E0512",
                                 format!("function is unprovable"),
                                 vec![(
                                     fun.loc.clone(),
@@ -810,6 +848,7 @@ impl Symbolic {
 
         let mut argsyms = Vec::new();
         let mut prev: Option<Symbol> = None;
+        self.current_function_args.clear();
         for i in 0..args.len() {
             let argname = Name::from(&args[i].name);
             let sym = self.alloc(
@@ -819,6 +858,7 @@ impl Symbolic {
                 args[i].tags.clone(),
             )?;
             argsyms.push(sym);
+            self.current_function_args.push((args[i].name.clone(), (sym, self.memory[sym].temporal)));
             self.memory[sym].value =
                 Value::Unconstrained(format!("passed by value as {}", argname));
 
@@ -845,6 +885,7 @@ impl Symbolic {
 
                 if !self.ssa.attest((sym, self.memory[sym].temporal), true) {
                     return Err(self.trace(
+                        "E0510",
                         format!("callsite assign broke ssa"),
                         vec![(
                             assign.loc().clone(),
@@ -860,6 +901,7 @@ impl Symbolic {
                     Some(v) => v,
                     None => {
                         return Err(self.trace(
+                            "E0607",
                             format!("tail tag without previous arg"),
                             vec![(args[i].loc.clone(), format!("something went wrong here"))],
                         ));
@@ -868,6 +910,7 @@ impl Symbolic {
 
                 if self.memory[sym].typed.t != ast::Type::USize {
                     return Err(self.trace(
+                        "E0907",
                         format!("ICE: tail binding not emitted as usize"),
                         vec![(args[i].loc.clone(), format!("this is a bug"))],
                     ));
@@ -911,6 +954,7 @@ impl Symbolic {
                         Some(ast::Def::Struct { fields, .. }) => {
                             if fields.len() < 1 {
                                 return Err(self.trace(
+                                    "E0608",
                                     format!("tail binding on struct with no members"),
                                     vec![(
                                         self.memory[prev].declared.clone(),
@@ -922,6 +966,7 @@ impl Symbolic {
                         }
                         o => {
                             return Err(self.trace(
+                                "E0606",
                                 format!("tail value on non struct {:?} ({})", o, n.human_name()),
                                 vec![(
                                     self.memory[prev].declared.clone(),
@@ -932,6 +977,7 @@ impl Symbolic {
                     },
                     o => {
                         return Err(self.trace(
+                            "E0605",
                             format!("tail value on non struct {:?}", o),
                             vec![(
                                 self.memory[prev].declared.clone(),
@@ -943,6 +989,7 @@ impl Symbolic {
 
                 if self.memory[prev].typed.ptr.len() != 1 {
                     return Err(self.trace(
+                        "E0609",
                         format!("tail passed as non pointer"),
                         vec![(
                             self.memory[prev].declared.clone(),
@@ -999,6 +1046,7 @@ impl Symbolic {
             let sym = self.execute_expr(callassert)?;
             if self.memory[sym].t != smt::Type::Bool {
                 return Err(self.trace(
+                    "E0301",
                     format!("expected boolean, got {}", self.memory[sym].typed),
                     vec![(
                         callassert.loc().clone(),
@@ -1008,6 +1056,7 @@ impl Symbolic {
             }
             if !self.ssa.attest((sym, self.memory[sym].temporal), true) {
                 return Err(self.trace(
+                    "E0508",
                     format!("callsite assert broke ssa solution"),
                     vec![(
                         callassert.loc().clone(),
@@ -1042,8 +1091,19 @@ impl Symbolic {
         }
 
 
-        self.execute_scope(&mut body.statements)?;
+        let rere = self.execute_scope(&mut body.statements)?;
 
+        // after the last statement
+        if let Some(at) = &self.explain {
+            if at.file == body.end.file && at.start < body.end.end {
+                return Err(self.explain());
+            }
+        }
+
+        // a return as the last statement already checked
+        if let ScopeReturn::NoReturn = rere {
+            self.check_unchecked_errs(&body.end)?;
+        }
         self.in_model = true;
         self.check_function_model(&body.end)?;
         self.in_model = false;
@@ -1073,6 +1133,7 @@ impl Symbolic {
 
             if self.memory[casym].t != smt::Type::Bool {
                 return Err(self.trace(
+                    "E0301",
                     format!("expected boolean, got {}", self.memory[casym].typed),
                     vec![(
                         callsite_effect.loc().clone(),
@@ -1103,7 +1164,7 @@ impl Symbolic {
                         if let Some(model) = &model {
                             estack.extend(self.demonstrate(model, sym, 0));
                         }
-                        Err(self.trace(format!("unproven model"), estack))
+                        Err(self.trace("E0502", format!("unproven model"), estack))
                     }
                     true => Ok(()),
                 })?;
@@ -1115,6 +1176,73 @@ impl Symbolic {
         Ok(())
     }
 
+    // an err::Err local that may hold an error when returning, unless a branch on the way here looked at it.
+    // the error branch of err::check can't prove checked either, so only the guard tells them apart
+    fn check_unchecked_errs(&mut self, end: &ast::Location) -> Result<(), Error> {
+        let guarded: Vec<&Name> = self.stack.iter().flat_map(|s| s.guards.iter()).collect();
+        let mut errs = Vec::new();
+        for scope in &self.stack[1..] {
+            for (name, sym) in &scope.locals {
+                let storage = &self.memory[*sym];
+                let t = match &storage.typed.t {
+                    ast::Type::Other(t) if storage.typed.ptr.is_empty() && t.len() > 2 => t,
+                    _ => continue,
+                };
+                if t.0[t.len() - 2] != "err" || t.0[t.len() - 1] != "Err" || guarded.contains(&name) {
+                    continue;
+                }
+                let mut theory = t.clone();
+                theory.pop();
+                theory.push("checked".to_string());
+                if let Some(ast::Def::Theory { .. }) = self.defs.get(&theory) {
+                    errs.push((name.clone(), theory, storage.declared.clone()));
+                }
+            }
+        }
+
+        for (name, theory, declared) in errs {
+            self.push("unchecked err".into());
+            self.ssa.push("unchecked err");
+            let mut checked = ast::Expression::Call {
+                loc: end.clone(),
+                name: Box::new(ast::Expression::Name(ast::Typed {
+                    t: ast::Type::Other(theory),
+                    loc: end.clone(),
+                    ..Default::default()
+                })),
+                args: vec![Box::new(ast::Expression::Name(ast::Typed {
+                    t: ast::Type::Other(name.clone()),
+                    loc: end.clone(),
+                    ..Default::default()
+                }))],
+                expanded: false,
+                emit: ast::EmitBehaviour::Default,
+            };
+            let in_model = self.in_model;
+            self.in_model = true;
+            let sym = self.execute_expr(&mut checked)?;
+            self.in_model = in_model;
+
+            let ok = self.ssa.assert(vec![(sym, self.memory[sym].temporal)], |a, _| a);
+            self.ssa.pop("end of unchecked err");
+            self.pop();
+
+            if !ok {
+                lint::emit(
+                    "unchecked_err",
+                    "W0107",
+                    end,
+                    format!("err '{}' may be returned without being checked", name),
+                    &[
+                        (end.clone(), "returning here".to_string()),
+                        (declared, format!("'{}' may still hold an error", name)),
+                    ],
+                );
+            }
+        }
+        Ok(())
+    }
+
     fn autocast(
         &mut self,
         expr: &mut ast::Expression,
@@ -1131,6 +1259,7 @@ impl Symbolic {
                              ast::Def::Closure {args: closureargs, nameloc: closurenameloc, .. }) => {
                                 if fnargs.len() != closureargs.len() {
                                     return Err(Error::new(
+                                        "E0316",
                                         "call argument count mismatch".to_string(),
                                         vec![
                                         (expr.loc().clone(), format!(
@@ -1154,6 +1283,7 @@ impl Symbolic {
                                     if fnargs[i].typed.t != closureargs[i].typed.t ||
                                     fnargs[i].typed.ptr.len() != closureargs[i].typed.ptr.len() {
                                         return Err(Error::new(
+                                            "E0305",
                                             "incompatible function pointers".to_string(),
                                             vec![
                                             (expr.loc().clone(),format!(
@@ -1273,6 +1403,7 @@ impl Symbolic {
     ) -> Result<(ast::Typed, Symbol, Symbol), Error> {
         if let Value::Theory { .. } = self.memory[a].value {
             return Err(self.trace(
+                "E0332",
                 format!(
                     "theory '{}' is not a real world object",
                     self.memory[a].name
@@ -1282,6 +1413,7 @@ impl Symbolic {
         }
         if let Value::Theory { .. } = self.memory[b].value {
             return Err(self.trace(
+                "E0332",
                 format!(
                     "theory '{}' is not a real world object",
                     self.memory[b].name
@@ -1334,6 +1466,22 @@ impl Symbolic {
 
         // if one is an unsigned literal, cast it into the other type
         if self.memory[a].typed.t == ast::Type::ULiteral {
+            if let Value::Integer(v) = &self.memory[a].value {
+                if !Self::literal_fits(v, &Self::smt_type(&self.memory[b].typed)) {
+                    return Err(self.trace(
+                        "E0306",
+                        format!("literal does not fit into {}", self.memory[b].typed),
+                        vec![(
+                            here.clone(),
+                            format!(
+                                "{} is too small on a {} target",
+                                self.memory[b].typed,
+                                DataModel::current()
+                            ),
+                        )],
+                    ));
+                }
+            }
             let tmp = self.temporary(
                 format!("implicit coercion of {}", self.memory[a].name),
                 self.memory[b].typed.clone(),
@@ -1342,16 +1490,40 @@ impl Symbolic {
             )?;
 
             self.memory[tmp].value = self.memory[a].value.clone();
-            self.ssa.assign(
-                (tmp, self.memory[tmp].temporal),
-                (a, self.memory[a].temporal),
-                Self::smt_type(&self.memory[tmp].typed),
-            );
+            if let (Value::Float(v), smt::Type::Float(..)) = (&self.memory[a].value, &self.memory[tmp].t) {
+                self.ssa.float_literal(tmp, *v, self.memory[tmp].t.clone());
+            } else if let (Value::Integer(parser::Integer::Signed(v)), smt::Type::Float(..)) =
+                (&self.memory[a].value, &self.memory[tmp].t)
+            {
+                self.ssa.signed_literal(tmp, *v, self.memory[tmp].t.clone());
+            } else {
+                self.ssa.assign(
+                    (tmp, self.memory[tmp].temporal),
+                    (a, self.memory[a].temporal),
+                    Self::smt_type(&self.memory[tmp].typed),
+                );
+            }
 
             return Ok((self.memory[b].typed.clone(), tmp, b));
         }
 
         if self.memory[b].typed.t == ast::Type::ULiteral {
+            if let Value::Integer(v) = &self.memory[b].value {
+                if !Self::literal_fits(v, &Self::smt_type(&self.memory[a].typed)) {
+                    return Err(self.trace(
+                        "E0306",
+                        format!("literal does not fit into {}", self.memory[a].typed),
+                        vec![(
+                            here.clone(),
+                            format!(
+                                "{} is too small on a {} target",
+                                self.memory[a].typed,
+                                DataModel::current()
+                            ),
+                        )],
+                    ));
+                }
+            }
             let tmp = self.temporary(
                 format!("implicit coercion of {}", self.memory[b].name),
                 self.memory[a].typed.clone(),
@@ -1360,11 +1532,19 @@ impl Symbolic {
             )?;
 
             self.memory[tmp].value = self.memory[b].value.clone();
-            self.ssa.assign(
-                (tmp, self.memory[tmp].temporal),
-                (b, self.memory[b].temporal),
-                Self::smt_type(&self.memory[tmp].typed),
-            );
+            if let (Value::Float(v), smt::Type::Float(..)) = (&self.memory[b].value, &self.memory[tmp].t) {
+                self.ssa.float_literal(tmp, *v, self.memory[tmp].t.clone());
+            } else if let (Value::Integer(parser::Integer::Signed(v)), smt::Type::Float(..)) =
+                (&self.memory[b].value, &self.memory[tmp].t)
+            {
+                self.ssa.signed_literal(tmp, *v, self.memory[tmp].t.clone());
+            } else {
+                self.ssa.assign(
+                    (tmp, self.memory[tmp].temporal),
+                    (b, self.memory[b].temporal),
+                    Self::smt_type(&self.memory[tmp].typed),
+                );
+            }
 
             return Ok((self.memory[a].typed.clone(), a, tmp));
         }
@@ -1402,6 +1582,7 @@ impl Symbolic {
         }
 
         return Err(self.trace(
+            "E0304",
             format!(
                 "incompatible types {} and {}",
                 self.memory[a].typed, self.memory[b].typed
@@ -1431,6 +1612,9 @@ impl Symbolic {
 
     fn execute_scope(&mut self, body: &mut Vec<Box<ast::Statement>>) -> Result<ScopeReturn, Error> {
         for i in 0..body.len() {
+            if self.explain_before(&body[i]) {
+                return Err(self.explain());
+            }
             let (body, _rest) = body.split_at_mut(i + 1);
 
             match body[i].as_mut() {
@@ -1443,7 +1627,7 @@ impl Symbolic {
                     assign,
                 } => {
                     let mut typed = typed_o.clone();
-                    if array.is_some() {
+                    if !matches!(array, ast::Array::None) {
                         typed.ptr.push(ast::Pointer {
                             loc: loc.clone(),
                             tags: Tags::new(),
@@ -1453,16 +1637,16 @@ impl Symbolic {
                     let sym =
                         self.alloc(Name::from(name.as_str()), typed, loc.clone(), tags.clone())?;
 
-                    if let Some(array) = array {
+                    if !matches!(array, ast::Array::None) {
                         self.ssa_mark_safe(sym, loc)?;
-                        if let Some(expr) = array {
+                        if let ast::Array::Sized(expr) = array {
                             let asym = self.execute_expr(expr)?;
                             let val = self.ssa.value((asym, self.memory[asym].temporal), |a,_| match a {
                                 smt::Assertion::Constrained(i) => {
                                     Ok(i)
                                 },
                                 _ => {
-                                    Err(self.trace("array size must be static".to_string(), vec![
+                                    Err(self.trace("E0320", "array size must be static".to_string(), vec![
                                         (expr.loc().clone(), format!("expression cannot be reduced to a constrained value at compile time"))
                                     ]))
                                 }
@@ -1490,6 +1674,7 @@ impl Symbolic {
                     if typed_o.t == ast::Type::Elided {
                         if self.memory[sym].typed.t == ast::Type::Elided {
                             return Err(self.trace(
+                                "E0307",
                                 "type cannot be elided".to_string(),
                                 vec![(loc.clone(), format!("unable to find type of this local"))],
                             ));
@@ -1530,13 +1715,18 @@ impl Symbolic {
                     */
 
                     let mut previous_ifs: Vec<(TemporalSymbol, ast::Location)> = Vec::new();
+                    let mut guards = Vec::new();
 
                     for (branch_loc, ref mut branch_expr, branch_body) in branches {
+                        if let Some(branch_expr) = branch_expr {
+                            expr_names(branch_expr, &mut guards);
+                        }
                         let positive_sym = if let Some(branch_expr) = branch_expr {
                             let sym = self.execute_expr(branch_expr)?;
 
                             if self.memory[sym].typed.t != ast::Type::Bool {
                                 return Err(self.trace(
+                                    "E0301",
                                     format!("expected boolean, got {}", self.memory[sym].typed),
                                     vec![(
                                         branch_expr.loc().clone(),
@@ -1549,8 +1739,11 @@ impl Symbolic {
                             self.ssa.bool_value(sym, |a, _model| match a {
                                 smt::Assertion::Constrained(val) => {
                                     if !self.in_loop {
-                                        emit_warn(
-                                            "unnecessary branch condition",
+                                        lint::emit(
+                                            "unnecessary_branch",
+                                            "W0103",
+                                            branch_expr.loc(),
+                                            "unnecessary branch condition".to_string(),
                                             &[(
                                                 branch_expr.loc().clone(),
                                                 format!("expression is always {}", val),
@@ -1572,6 +1765,7 @@ impl Symbolic {
                         };
 
                         self.push("branch".to_string());
+                        self.cur().guards = guards.clone();
                         self.ssa.debug_loc(&branch_loc);
                         self.ssa.branch();
 
@@ -1612,6 +1806,7 @@ impl Symbolic {
                             self.copy(retsym, e, expr.loc())?;
                         }
                     }
+                    self.check_unchecked_errs(loc)?;
                     self.check_function_model(loc)?;
                     // stop. do not execute anything behind return
                     return Ok(ScopeReturn::Return(loc.clone()));
@@ -1713,6 +1908,7 @@ impl Symbolic {
 
                         if newtype.ptr.len() > 0 {
                             return Err(self.trace(
+                                "E0315",
                                 format!("assign arithmetic is not yet implemented"),
                                 vec![(loc.clone(), format!("use a=a+n instead of a+=n"))],
                             ));
@@ -1751,6 +1947,11 @@ impl Symbolic {
                     }
                 }
                 ast::Statement::Continue { loc } => {
+                    if let Some(Some((invariants, step))) = self.loops.last().cloned() {
+                        let mut step = step;
+                        self.execute_scope(&mut step)?;
+                        self.check_loop_invariants(&invariants, loc, "is not maintained when continuing here")?;
+                    }
                     return Ok(ScopeReturn::Return(loc.clone()));
                 }
                 ast::Statement::Break { loc,.. } => {
@@ -1761,6 +1962,56 @@ impl Symbolic {
                     self.execute_scope(&mut block.statements)?;
                     self.pop();
                 }
+                ast::Statement::For { e1, e2, e3, invariants, body, ..} if invariants.len() > 0 => {
+                    self.push("for loop".to_string());
+
+                    let prev_loop = self.in_loop;
+                    self.in_loop = false;
+                    self.execute_scope(e1)?;
+
+                    let loc = match e2 {
+                        Some(expr) => expr.loc().clone(),
+                        None => invariants[0].loc().clone(),
+                    };
+                    self.check_loop_invariants(invariants, &loc, "does not hold when entering the loop")?;
+
+                    // any iteration
+                    self.havoc_loop_assigned(&body.statements, e3, &loc)?;
+                    self.ssa.branch();
+                    self.constrain_loop_invariants(invariants)?;
+                    if let Some(expr) = e2 {
+                        let sym = self.execute_loop_condition(expr)?;
+                        self.ssa.constrain_branch(sym, true);
+                    }
+
+                    self.loops.push(Some((invariants.clone(), e3.clone())));
+                    let rere = self.execute_scope(&mut body.statements)?;
+                    if let ScopeReturn::NoReturn = rere {
+                        self.execute_scope(e3)?;
+                        self.check_loop_invariants(invariants, &body.end, "is not maintained by the loop body")?;
+                    }
+                    self.loops.pop();
+                    self.ssa.unbranch(false);
+
+                    // the last iteration
+                    self.havoc_loop_assigned(&body.statements, e3, &loc)?;
+                    if !has_break(&body.statements) {
+                        self.assume_loop_invariants(invariants)?;
+                        if let Some(expr) = e2 {
+                            let sym = self.execute_loop_condition(expr)?;
+                            if !self.ssa.assume(sym, false) {
+                                return Err(self.trace(
+                                    "E0505",
+                                    format!("loop never terminates"),
+                                    vec![(expr.loc().clone(), format!("condition is always true"))],
+                                ));
+                            }
+                        }
+                    }
+
+                    self.in_loop = prev_loop;
+                    self.pop();
+                }
                 ast::Statement::For { e1, e2, e3, body, ..} => {
                     self.push("for loop".to_string());
                     //self.ssa.push("for loop");
@@ -1776,6 +2027,7 @@ impl Symbolic {
                         let sym = self.execute_expr(expr)?;
                         if self.memory[sym].t != smt::Type::Bool {
                             return Err(self.trace(
+                                "E0301",
                                 format!("expected boolean, got {}", self.memory[sym].typed),
                                 vec![(expr.loc().clone(), format!("must be boolean"))],
                             ));
@@ -1786,6 +2038,7 @@ impl Symbolic {
                             .push((sym.clone(), expr.loc().clone(), false));
                         if !self.ssa.attest(sym, true) {
                             return Err(self.trace(
+                                "E0506",
                                 format!("condition breaks ssa"),
                                 vec![(
                                     expr.loc().clone(),
@@ -1800,18 +2053,62 @@ impl Symbolic {
                     //self.execute_scope(&mut body.statements.clone())?;
                     //self.pop();
 
+                    self.loops.push(None);
                     self.execute_scope(&mut body.statements)?;
+                    self.loops.pop();
                     self.in_loop = prev_loop;
                     //self.ssa.pop("end of for loop");
                     self.pop();
                 }
-                ast::Statement::While { expr, body } => {
+                ast::Statement::While { expr, invariants, body } if invariants.len() > 0 => {
+                    self.push("while loop".to_string());
+
+                    let prev_loop = self.in_loop;
+                    self.in_loop = false;
+
+                    let loc = expr.loc().clone();
+                    self.check_loop_invariants(invariants, &loc, "does not hold when entering the loop")?;
+
+                    // any iteration
+                    self.havoc_loop_assigned(&body.statements, &[], &loc)?;
+                    self.ssa.branch();
+                    self.constrain_loop_invariants(invariants)?;
+                    let sym = self.execute_loop_condition(expr)?;
+                    self.ssa.constrain_branch(sym, true);
+
+                    self.loops.push(Some((invariants.clone(), Vec::new())));
+                    let rere = self.execute_scope(&mut body.statements)?;
+                    if let ScopeReturn::NoReturn = rere {
+                        self.check_loop_invariants(invariants, &body.end, "is not maintained by the loop body")?;
+                    }
+                    self.loops.pop();
+                    self.ssa.unbranch(false);
+
+                    // the last iteration
+                    self.havoc_loop_assigned(&body.statements, &[], &loc)?;
+                    if !has_break(&body.statements) {
+                        self.assume_loop_invariants(invariants)?;
+                        let sym = self.execute_loop_condition(expr)?;
+                        if !self.ssa.assume(sym, false) {
+                            return Err(self.trace(
+                                "E0505",
+                                format!("loop never terminates"),
+                                vec![(loc.clone(), format!("condition is always true"))],
+                            ));
+                        }
+                    }
+
+                    self.in_loop = prev_loop;
+                    self.pop();
+                }
+                ast::Statement::While { expr, body, .. } => {
                     self.push("while loop".to_string());
                     //self.ssa.push("while loop");
 
                     let sym = self.execute_expr(expr)?;
                     if self.memory[sym].t != smt::Type::Bool {
                         return Err(self.trace(
+                            "E0301",
                             format!("expected boolean, got {}", self.memory[sym].typed),
                             vec![(expr.loc().clone(), format!("must be boolean"))],
                         ));
@@ -1823,6 +2120,7 @@ impl Symbolic {
 
                     if !self.ssa.attest(sym, true) {
                         return Err(self.trace(
+                            "E0506",
                             format!("condition breaks ssa"),
                             vec![(
                                 expr.loc().clone(),
@@ -1834,7 +2132,9 @@ impl Symbolic {
                     let prev_loop = self.in_loop;
                     self.in_loop = true;
 
+                    self.loops.push(None);
                     self.execute_scope(&mut body.statements)?;
+                    self.loops.pop();
 
                     self.in_loop = prev_loop;
                     //self.ssa.pop("end of while loop");
@@ -1850,6 +2150,149 @@ impl Symbolic {
         Ok(ScopeReturn::NoReturn)
     }
 
+    fn execute_loop_condition(&mut self, expr: &mut ast::Expression) -> Result<TemporalSymbol, Error> {
+        let sym = self.execute_expr(expr)?;
+        if self.memory[sym].t != smt::Type::Bool {
+            return Err(self.trace(
+                "E0301",
+                format!("expected boolean, got {}", self.memory[sym].typed),
+                vec![(expr.loc().clone(), format!("must be boolean"))],
+            ));
+        }
+        let sym = (sym, self.memory[sym].temporal);
+        self.cur().trace.push((sym.clone(), expr.loc().clone(), false));
+        Ok(sym)
+    }
+
+    fn execute_loop_invariant(&mut self, invariant: &ast::Expression) -> Result<TemporalSymbol, Error> {
+        let mut invariant = invariant.clone();
+        let in_model = self.in_model;
+        self.in_model = true;
+        let sym = self.execute_expr(&mut invariant)?;
+        self.in_model = in_model;
+        if self.memory[sym].t != smt::Type::Bool {
+            return Err(self.trace(
+                "E0301",
+                format!("expected boolean, got {}", self.memory[sym].typed),
+                vec![(invariant.loc().clone(), format!("loop invariant must be boolean"))],
+            ));
+        }
+        Ok((sym, self.memory[sym].temporal))
+    }
+
+    fn check_loop_invariants(
+        &mut self,
+        invariants: &Vec<ast::Expression>,
+        here: &ast::Location,
+        what: &str,
+    ) -> Result<(), Error> {
+        for invariant in invariants {
+            let sym = self.execute_loop_invariant(invariant)?;
+            self.ssa.assert(vec![sym], |a, model| match a {
+                false => {
+                    let mut estack = vec![
+                        (invariant.loc().clone(), format!("loop invariant {}", what)),
+                        (here.clone(), format!("checked here")),
+                    ];
+                    if let Some(model) = &model {
+                        estack.extend(self.demonstrate(model, sym, 0));
+                    }
+                    Err(self.trace("E0503", format!("unproven loop invariant"), estack))
+                }
+                true => Ok(()),
+            })?;
+        }
+        Ok(())
+    }
+
+    fn constrain_loop_invariants(&mut self, invariants: &Vec<ast::Expression>) -> Result<(), Error> {
+        for invariant in invariants {
+            let sym = self.execute_loop_invariant(invariant)?;
+            self.ssa.constrain_branch(sym, true);
+        }
+        Ok(())
+    }
+
+    fn assume_loop_invariants(&mut self, invariants: &Vec<ast::Expression>) -> Result<(), Error> {
+        for invariant in invariants {
+            let sym = self.execute_loop_invariant(invariant)?;
+            if !self.ssa.assume(sym, true) {
+                return Err(self.trace(
+                    "E0504",
+                    format!("loop invariant breaks ssa"),
+                    vec![(
+                        invariant.loc().clone(),
+                        format!("there may be conflicting constraints"),
+                    )],
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // forget everything about locals the loop may modify.
+    // whatever holds for them after n iterations is stated by the invariants
+    fn havoc_loop_assigned(
+        &mut self,
+        body: &[Box<ast::Statement>],
+        step: &[Box<ast::Statement>],
+        loc: &ast::Location,
+    ) -> Result<(), Error> {
+        let mut assigned = LoopAssigned::default();
+        loop_assigned(body, &mut assigned);
+        loop_assigned(step, &mut assigned);
+        assigned.names.sort();
+        assigned.names.dedup();
+
+        // a write through something that isn't a name could have gone anywhere
+        let roots: Vec<(Symbol, bool)> = if assigned.unknown {
+            self.stack.iter().flat_map(|scope| scope.locals.values().map(|sym| (*sym, true))).collect()
+        } else {
+            assigned
+                .names
+                .iter()
+                // declared inside the loop
+                .filter_map(|(name, itself)| Some((self.name(&Name::from(name), loc).ok()?, *itself)))
+                .collect()
+        };
+
+        let mut seen = HashSet::new();
+        for (sym, itself) in roots {
+            self.havoc(sym, itself, loc, &mut seen);
+        }
+        Ok(())
+    }
+
+    // a modified struct or array may have any of its members modified,
+    // and a pointer may have been written through
+    fn havoc(&mut self, sym: Symbol, itself: bool, loc: &ast::Location, seen: &mut HashSet<Symbol>) {
+        let reachable: Vec<Symbol> = match &self.memory[sym].value {
+            Value::Struct { members } => members.values().cloned().collect(),
+            Value::Array { array, .. } => array.values().cloned().collect(),
+            Value::Address(to) => vec![*to],
+            // not data
+            Value::Function { .. } | Value::Theory { .. } | Value::Macro(_) | Value::Void => return,
+            _ => Vec::new(),
+        };
+
+        if itself {
+            if !seen.insert(sym) {
+                return;
+            }
+            self.memory[sym].temporal += 1;
+            let tt = self.memory[sym].temporal;
+            self.memory[sym].assignments.insert(tt, loc.clone());
+            match self.memory[sym].value {
+                Value::Array { .. } | Value::Struct { .. } => (),
+                _ => self.memory[sym].value = Value::Unconstrained("modified in loop".to_string()),
+            }
+        }
+
+        for sym in reachable {
+            self.havoc(sym, true, loc, seen);
+        }
+    }
+
     fn expand_callargs(
         &mut self,
         defined: &Vec<ast::NamedArg>,
@@ -1946,6 +2389,7 @@ impl Symbolic {
                     },
                     o => {
                         return Err(self.trace(
+                            "E0520",
                             format!("invalid callsite_source {}", o),
                             vec![(loc.clone(), format!(""))],
                         ));
@@ -1958,6 +2402,7 @@ impl Symbolic {
                     Some(v) => v,
                     None => {
                         return Err(self.trace(
+                            "E0607",
                             format!("tail tag without previous arg"),
                             vec![(callloc.clone(), format!("something went wrong here"))],
                         ));
@@ -2020,6 +2465,7 @@ impl Symbolic {
                                         continue;
                                     } else {
                                         return Err(self.trace(
+                                            "E0601",
                                             format!("tail size of {} bound to wrong pointer", self.memory[callptr].name),
                                             vec![
                                                 (prev_loc.clone(), format!("void tail requires a pointer of depth 1")),
@@ -2057,6 +2503,7 @@ impl Symbolic {
                                 })
                             } else {
                                 return Err(self.trace(
+                                    "E0602",
                                     format!("tail size of {} bound to wrong value", self.memory[callptr].name),
                                     vec![
                                         (prev_loc.clone(), format!("void tail requires a pointer of depth 1")),
@@ -2084,6 +2531,7 @@ impl Symbolic {
                                 }
                                 ast::Tail::Dynamic(_) => {
                                     return Err(self.trace(
+                                        "E0603",
                                         format!("tail size of {} not bound", self.memory[callptr].name),
                                         vec![
                                         (prev_loc.clone(), format!("tail len required here")),
@@ -2125,6 +2573,7 @@ impl Symbolic {
                             }
                             ast::Tail::Dynamic(_) | ast::Tail::None => {
                                 return Err(self.trace(
+                                        "E0603",
                                         format!("tail size of {} not bound", self.memory[callptr].name),
                                         vec![
                                         (prev_loc.clone(), format!("tail len required here")),
@@ -2230,6 +2679,7 @@ impl Symbolic {
     ) -> Result<Symbol, Error> {
         if self.memory[rhs_sym].typed.t.signed() {
             return Err(self.trace(
+                "E0336",
                 format!("array access with signed index is not well defined"),
                 vec![(
                     rhsloc.clone(),
@@ -2242,6 +2692,7 @@ impl Symbolic {
             && self.memory[rhs_sym].typed.t != ast::Type::ULiteral
         {
             return Err(self.trace(
+                "E0337",
                 format!("array access with something not a usize"),
                 vec![(
                     rhsloc.clone(),
@@ -2269,8 +2720,9 @@ impl Symbolic {
             _ => (),
         }
 
-        if self.memory[lhs_sym].t != smt::Type::Unsigned(64) {
+        if self.memory[lhs_sym].t != Self::smt_pointer_type() {
             return Err(self.trace(
+                "E0406",
                 format!("cannot prove memory access due to unexpected type"),
                 vec![(
                     loc.clone(),
@@ -2334,12 +2786,10 @@ impl Symbolic {
             |a, model| match a {
                 false => {
                     let mut estack = Vec::new();
-                    estack.extend(self.demonstrate(
-                        model.as_ref().unwrap(),
-                        (tmp2, self.memory[tmp2].temporal),
-                        0,
-                    ));
-                    Err(self.trace(format!("possible out of bounds array access"), estack))
+                    if let Some(model) = &model {
+                        estack.extend(self.demonstrate(model, (tmp2, self.memory[tmp2].temporal), 0));
+                    }
+                    Err(self.trace("E0403", format!("possible out of bounds array access"), estack))
                 }
                 true => Ok(()),
             },
@@ -2395,6 +2845,7 @@ impl Symbolic {
                 return Ok(ctx);
             } else {
                 return Err(self.trace(
+                    "E0323",
                     format!("closure does not a have a field named {}", rhs),
                     vec![(loc.clone(), format!("cannot access closure as struct here"))],
                 ));
@@ -2419,6 +2870,7 @@ impl Symbolic {
             None => match &self.memory[lhs_sym].typed.t {
                 ast::Type::Other(t) if t.0[1] == "ext" => {
                     return Err(self.trace(
+                        "E0407",
                         format!(
                             "{} is not safe to access. it is an untracked C type {}",
                             self.memory[lhs_sym].name, self.memory[lhs_sym].typed
@@ -2431,6 +2883,7 @@ impl Symbolic {
                 }
                 _ => {
                     return Err(self.trace(
+                        "E0325",
                         format!(
                             "{} is not accessible as struct. it is typeof({})",
                             self.memory[lhs_sym].name, self.memory[lhs_sym].typed
@@ -2452,6 +2905,7 @@ impl Symbolic {
             Some(f) => f,
             None => {
                 return Err(self.trace(
+                    "E0322",
                     format!(
                         "{} of type {} does not a have a field named {}",
                         self.memory[lhs_sym].name, self.memory[lhs_sym].typed, rhs
@@ -2474,6 +2928,7 @@ impl Symbolic {
             }
             o => {
                 return Err(self.trace(
+                    "E0324",
                     format!(
                         "{} is not accessible as struct. it is {}",
                         self.memory[lhs_sym].name, o
@@ -2495,7 +2950,7 @@ impl Symbolic {
                         Ok(i)
                     },
                     _ => {
-                        Err(self.trace("array size must be static".to_string(), vec![
+                        Err(self.trace("E0320", "array size must be static".to_string(), vec![
                             (expr.loc().clone(), format!("expression cannot be reduced to a constrained value at compile time"))
                         ]))
                     }
@@ -2577,7 +3032,7 @@ impl Symbolic {
         self.ssa.debug_loc(expr.loc());
         match expr {
             ast::Expression::Cpp {loc, ..} => {
-                Err(self.trace("invalid c preprocessor directive in local expression location".to_string(), vec![
+                Err(self.trace("E0709", "invalid c preprocessor directive in local expression location".to_string(), vec![
                     (loc.clone(), format!("expression cannot be reduced because it depends on a c macro"))
                 ]))
             },
@@ -2733,6 +3188,13 @@ impl Symbolic {
                         ..Default::default()
                     };
                     self.literal(loc, Value::Integer(v), t)
+                } else if let Ok(v) = v.parse::<f64>() {
+                    let t = ast::Typed {
+                        t: ast::Type::ULiteral,
+                        loc: loc.clone(),
+                        ..Default::default()
+                    };
+                    self.literal(loc, Value::Float(v), t)
                 } else {
                     let t = ast::Typed {
                         t: ast::Type::ULiteral,
@@ -2751,11 +3213,13 @@ impl Symbolic {
 
                 if !op.takes_boolean() && newtype.t == ast::Type::Bool {
                     return Err(self.trace(
+                        "E0308",
                         format!("invalid types for integer operator"),
                         vec![(loc.clone(), format!("not defined for type {}", newtype))],
                     ));
                 } else if !op.takes_integer() && newtype.t != ast::Type::Bool {
                     return Err(self.trace(
+                        "E0309",
                         format!("invalid types for boolean operator"),
                         vec![(loc.clone(), format!("not defined for type {}", newtype))],
                     ));
@@ -2766,7 +3230,7 @@ impl Symbolic {
                     match format!("{}", o).as_str() {
                         "::ext::<stddef.h>::char" => (),
                         _ => if newtype.ptr.len() == 0 {
-                            return Err(self.trace(format!("unprovable types for expression"), vec![
+                            return Err(self.trace("E0310", format!("unprovable types for expression"), vec![
                                 (loc.clone(), format!("not defined for type {}. consider casting to a builtin type", newtype))
                             ]))
                         }
@@ -2783,7 +3247,7 @@ impl Symbolic {
                 };
 
                 if newtype.t.signed() && *op == crate::ast::InfixOperator::Shiftright {
-                    return Err(self.trace(format!("shift right of signed value is unprovable"), vec![
+                    return Err(self.trace("E0411", format!("shift right of signed value is unprovable"), vec![
                         (loc.clone(), format!("compiler specific behaviour is not allowed because it is not provable"))
                     ]));
                 }
@@ -2850,12 +3314,15 @@ impl Symbolic {
                         |a, model| match a {
                             false => {
                                 let mut estack = Vec::new();
-                                estack.extend(self.demonstrate(
-                                    model.as_ref().unwrap(),
-                                    (len_assert, self.memory[len_assert].temporal),
-                                    0,
-                                ));
+                                if let Some(model) = &model {
+                                    estack.extend(self.demonstrate(
+                                        model,
+                                        (len_assert, self.memory[len_assert].temporal),
+                                        0,
+                                    ));
+                                }
                                 Err(self.trace(
+                                    "E0404",
                                     format!("possible out of bounds pointer arithmetic"),
                                     estack,
                                 ))
@@ -2892,6 +3359,7 @@ impl Symbolic {
                         //ast::InfixOperator::Subtract => ast::InfixOperator::Add,
                         _ => {
                             return Err(self.trace(
+                                "E0405",
                                 format!("unprovable pointer arithmetic"),
                                 vec![(expr.loc().clone(), format!("only + is possible"))],
                             ));
@@ -2949,6 +3417,14 @@ impl Symbolic {
             }
             ast::Expression::Cast { expr, into, loc } => {
                 let rhs = self.execute_expr(expr)?;
+
+                if let (smt::Type::Float(..), smt::Type::Signed(_))
+                    | (smt::Type::Float(..), smt::Type::Unsigned(_)) = (&self.memory[rhs].t, &Self::smt_type(into))
+                {
+                    if into.ptr.len() == 0 {
+                        self.float_fits_into_ssa(rhs, into, loc)?;
+                    }
+                }
                 let tmp = self.temporary(
                     format!("cast of {}", self.memory[rhs].name),
                     into.clone(),
@@ -3041,7 +3517,7 @@ impl Symbolic {
                         // because if we want to prove pointer arithmetic
                         // this value is meaningless
                         self.ssa
-                            .literal(tmp, lhs_sym as u64, smt::Type::Unsigned(64));
+                            .literal(tmp, lhs_sym as u64, Self::smt_pointer_type());
 
                         self.ssa_mark_safe(tmp, loc)?;
                         Ok(tmp)
@@ -3052,6 +3528,7 @@ impl Symbolic {
                         if *op == crate::ast::PrefixOperator::Boolnot {
                             if self.memory[rhs_sym].t != smt::Type::Bool {
                                 return Err(self.trace(
+                                    "E0301",
                                     format!("expected boolean, got {}", self.memory[rhs_sym].typed),
                                     vec![(
                                         expr.loc().clone(),
@@ -3062,8 +3539,9 @@ impl Symbolic {
                         } else if *op == crate::ast::PrefixOperator::Bitnot {
                             if self.memory[rhs_sym].t == smt::Type::Bool {
                                 return Err(self.trace(
+                                    "E0302",
                                     format!(
-                                        "expected integer , got {}",
+                                        "expected integer, got {}",
                                         self.memory[rhs_sym].typed
                                     ),
                                     vec![(
@@ -3099,6 +3577,7 @@ impl Symbolic {
                         let rhs_sym = self.execute_expr(expr)?;
                         if self.memory[rhs_sym].t == smt::Type::Bool {
                             return Err(self.trace(
+                                "E0302",
                                 format!("expected integer, got {}", self.memory[rhs_sym].typed),
                                 vec![(expr.loc().clone(), format!("invalid operand on boolean"))],
                             ));
@@ -3155,6 +3634,7 @@ impl Symbolic {
                                 }
                                 if !found {
                                     return Err(self.trace(
+                                        "E0321",
                                         format!("{} has no member {}", o, name),
                                         vec![(expr.loc().clone(), format!("here"))],
                                     ));
@@ -3167,6 +3647,7 @@ impl Symbolic {
                             return Ok(aptr)
                         } else {
                             return Err(self.trace(
+                                "E0318",
                                 format!("cannot initialize non-struct with a struct literal"), vec![
                                 (loc.clone(), format!("this expression is a struct literal")),
                                 (typed.loc.clone(), format!("but this is not a struct")),
@@ -3181,6 +3662,7 @@ impl Symbolic {
             ast::Expression::ArrayInit { fields, loc } => {
                 if fields.len() < 1 {
                     return Err(self.trace(
+                        "E0319",
                         format!("empty literal array not possible"),
                         vec![(loc.clone(), format!("here"))],
                     ));
@@ -3237,6 +3719,13 @@ impl Symbolic {
 
         match static_name.as_ref().map(|s| s.as_str()) {
             Some("sizeof") => {
+                // primitives have a known size on the target, everything else is deferred to C
+                let mut size = None;
+                if let [arg] = &args[..] {
+                    if let ast::Expression::Name(typed) = arg.as_ref() {
+                        size = Self::primitive_size(typed);
+                    }
+                }
                 for arg in args {
                     self.execute_expr(arg)?;
                 }
@@ -3250,12 +3739,16 @@ impl Symbolic {
                     loc.clone(),
                     Tags::new(),
                 )?;
+                if let Some(size) = size {
+                    self.ssa.literal(tmp, size, self.memory[tmp].t.clone());
+                }
                 self.current_call.pop();
                 return Ok(tmp);
             }
             Some("typeof") => {
                 if args.len() != 1 {
                     return Err(self.trace(
+                        "E0316",
                         "call argument count mismatch".to_string(),
                         vec![(
                             name.loc().clone(),
@@ -3280,6 +3773,7 @@ impl Symbolic {
 
                         *emit = ast::EmitBehaviour::Error {
                             loc: loc.clone(),
+                            code: "E0339".to_string(),
                             message: format!("typeof cannot be used here as it has no actual rvalue",),
                         };
                         self.current_call.pop();
@@ -3318,6 +3812,7 @@ impl Symbolic {
             Some("len") => {
                 if args.len() != 1 {
                     return Err(self.trace(
+                        "E0316",
                         "call argument count mismatch".to_string(),
                         vec![(
                             name.loc().clone(),
@@ -3350,6 +3845,7 @@ impl Symbolic {
                 self.ssa.debug("static_attest");
                 if args.len() != 1 {
                     return Err(self.trace(
+                        "E0316",
                         "call argument count mismatch".to_string(),
                         vec![(
                             name.loc().clone(),
@@ -3365,12 +3861,14 @@ impl Symbolic {
 
                 if self.memory[sym].t != smt::Type::Bool {
                     return Err(self.trace(
+                        "E0301",
                         format!("expected boolean, got {}", self.memory[sym].typed),
                         vec![(args[0].loc().clone(), format!("argument must be boolean"))],
                     ));
                 }
                 if !self.ssa.attest((sym, self.memory[sym].temporal), true) {
                     return Err(self.trace(
+                        "E0512",
                         format!("function is unprovable"),
                         vec![(
                             expr.loc().clone(),
@@ -3397,6 +3895,7 @@ impl Symbolic {
                 self.ssa.debug("static_assert");
                 if args.len() != 1 {
                     return Err(self.trace(
+                        "E0316",
                         "call argument count mismatch".to_string(),
                         vec![(
                             name.loc().clone(),
@@ -3411,6 +3910,7 @@ impl Symbolic {
 
                 if self.memory[sym].t != smt::Type::Bool {
                     return Err(self.trace(
+                        "E0301",
                         format!("expected boolean, got {}", self.memory[sym].typed),
                         vec![(
                             args[0].loc().clone(),
@@ -3425,7 +3925,7 @@ impl Symbolic {
                        if let Some(model) = &model {
                            estack.extend(self.demonstrate(model, (sym, self.memory[sym].temporal), 0));
                        }
-                       Err(self.trace(format!("theory is unproven"), estack))
+                       Err(self.trace("E0513", format!("theory is unproven"), estack))
                    }
                    true => {
                        Ok(())
@@ -3453,6 +3953,7 @@ impl Symbolic {
 
                 if args.len() < 1 {
                     return Err(self.trace(
+                        "E0316",
                         "call argument count mismatch".to_string(),
                         vec![(
                             name.loc().clone(),
@@ -3471,15 +3972,17 @@ impl Symbolic {
                 }
                 let val = self.ssa.value((sym, self.memory[sym].temporal), |a,model|match a{
                    smt::Assertion::Unsolveable => {
-                       Err(self.trace(format!("static is not solveable"), vec![
+                       Err(self.trace("E0515", format!("static is not solveable"), vec![
                            (loc.clone(), format!("there may be conflicting constraints"))
                        ]))
                    }
                    smt::Assertion::Unconstrained(_) => {
                        let mut estack = vec![(loc.clone(),
                        format!("you may need an if condition or callsite_assert to increase confidence"))];
-                       estack.extend(self.demonstrate(model.as_ref().unwrap(), (sym, self.memory[sym].temporal), 0));
-                       Err(self.trace(format!("static is unconstrained"), estack))
+                       if let Some(model) = &model {
+                           estack.extend(self.demonstrate(model, (sym, self.memory[sym].temporal), 0));
+                       }
+                       Err(self.trace("E0516", format!("static is unconstrained"), estack))
                    }
                    smt::Assertion::Constrained(val) => {
                        Ok(val)
@@ -3492,15 +3995,17 @@ impl Symbolic {
                     let sym = self.execute_expr(&mut args[1])?;
                     self.ssa.value((sym, self.memory[sym].temporal), |a,model|match a{
                        smt::Assertion::Unsolveable => {
-                           Err(self.trace(format!("static default value is not solveable"), vec![
+                           Err(self.trace("E0517", format!("static default value is not solveable"), vec![
                                (loc.clone(), format!("there may be conflicting constraints"))
                            ]))
                        }
                        smt::Assertion::Unconstrained(_) => {
                            let mut estack = vec![(loc.clone(),
                            format!("you may need an if condition or callsite_assert to increase confidence"))];
-                           estack.extend(self.demonstrate(model.as_ref().unwrap(), (sym, self.memory[sym].temporal), 0));
-                           Err(self.trace(format!("static default value is unconstrained"), estack))
+                           if let Some(model) = &model {
+                               estack.extend(self.demonstrate(model, (sym, self.memory[sym].temporal), 0));
+                           }
+                           Err(self.trace("E0518", format!("static default value is unconstrained"), estack))
                        }
                        smt::Assertion::Constrained(val) => {
                            Ok(val)
@@ -3526,9 +4031,70 @@ impl Symbolic {
                 self.current_call.pop();
                 return r;
             }
+            Some(class @ "isnan") | Some(class @ "isinf") => {
+                if args.len() != 1 {
+                    return Err(self.trace(
+                        "E0316",
+                        "call argument count mismatch".to_string(),
+                        vec![(
+                            name.loc().clone(),
+                            format!("builtin needs 1 argument, but you passed {}", args.len()),
+                        )],
+                    ));
+                }
+                // a proof about the value, C has its own isnan in math.h
+                *emit = ast::EmitBehaviour::Error {
+                    loc: loc.clone(),
+                    code: "E0522".to_string(),
+                    message: format!("assertion of theory {} outside static()", class),
+                };
+                let sym = self.execute_expr(&mut args[0])?;
+                if self.memory[sym].typed.t == ast::Type::ULiteral {
+                    let v = match self.memory[sym].value {
+                        Value::Float(v) if class == "isnan" => v.is_nan(),
+                        Value::Float(v) => v.is_infinite(),
+                        _ => false,
+                    };
+                    self.current_call.pop();
+                    return self.literal(
+                        loc,
+                        Value::Integer(parser::Integer::Unsigned(v as u64)),
+                        ast::Typed {
+                            t: ast::Type::Bool,
+                            loc: loc.clone(),
+                            ..Default::default()
+                        },
+                    );
+                }
+                if !matches!(self.memory[sym].t, smt::Type::Float(..)) {
+                    return Err(self.trace(
+                        "E0317",
+                        "call argument type mismatch".to_string(),
+                        vec![(
+                            args[0].loc().clone(),
+                            format!("{} needs a float, this is {}", class, self.memory[sym].typed),
+                        )],
+                    ));
+                }
+                let tmp = self.temporary(
+                    format!("{}({})", class, self.memory[sym].name),
+                    ast::Typed {
+                        t: ast::Type::Bool,
+                        loc: loc.clone(),
+                        ..Default::default()
+                    },
+                    loc.clone(),
+                    Tags::new(),
+                )?;
+                let smt_class = if class == "isnan" { "fp.isNaN" } else { "fp.isInfinite" };
+                self.ssa.float_is(tmp, (sym, self.memory[sym].temporal), smt_class);
+                self.current_call.pop();
+                return Ok(tmp);
+            }
             Some("constrained") => {
                 if args.len() != 1 {
                     return Err(self.trace(
+                        "E0316",
                         "call argument count mismatch".to_string(),
                         vec![(
                             name.loc().clone(),
@@ -3584,6 +4150,7 @@ impl Symbolic {
         match &self.memory[name_sym].value {
             Value::Macro(name) => {
                 return Err(self.trace(
+                    "E0705",
                     format!("macro expansion requires @ident syntax"),
                     vec![(loc.clone(), format!("use @macro() instead of macro()"))],
                 ));
@@ -3592,6 +4159,7 @@ impl Symbolic {
                 let mut body = body.clone();
                 *emit = ast::EmitBehaviour::Error {
                     loc: loc.clone(),
+                    code: "E0522".to_string(),
                     message: format!(
                         "assertion of theory {} outside static()",
                         self.memory[name_sym].name
@@ -3605,6 +4173,7 @@ impl Symbolic {
                 }
                 if args.len() != fargs.len() {
                     return Err(self.trace(
+                        "E0316",
                         "call argument count mismatch".to_string(),
                         vec![(
                             name.loc().clone(),
@@ -3643,6 +4212,7 @@ impl Symbolic {
 
                     } else {
                         return Err(self.trace(
+                            "E0335",
                             format!(
                                 "incompatible arguments to theory {}",
                                 self.memory[name_sym].name
@@ -3746,6 +4316,7 @@ impl Symbolic {
 
                 if (args.len() > fargs.len() && !vararg) || args.len() < fargs.len() {
                     return Err(self.trace(
+                        "E0316",
                         "call argument count mismatch".to_string(),
                         vec![(
                             name.loc().clone(),
@@ -3766,7 +4337,7 @@ impl Symbolic {
 
                     if let Some(farg) = fargs.get(i) {
                         if self.memory[s].typed != farg.typed {
-                            return Err(self.trace("call argument type mismatch".to_string(), vec![
+                            return Err(self.trace("E0317", "call argument type mismatch".to_string(), vec![
                                 (arg.loc().clone(), format!("type {} cannot be used as argument of type {}",
                                     &self.memory[name_sym].typed, farg.typed))
                             ]));
@@ -3820,6 +4391,7 @@ impl Symbolic {
                                         estack.extend(self.demonstrate(model, sym, 0));
                                     }
                                     Err(self.trace(
+                                        "E0501",
                                         format!(
                                             "unproven callsite assert for {}",
                                             self.memory[sym.0].name
@@ -3906,6 +4478,7 @@ impl Symbolic {
 
                     if !self.ssa.attest((casym, self.memory[casym].temporal), true) {
                         return Err(self.trace(
+                            "E0509",
                             format!("callsite effect would break SSA"),
                             vec![(
                                 expr.loc().clone(),
@@ -3963,6 +4536,7 @@ impl Symbolic {
             }
             o => {
                 return Err(self.trace(
+                    "E0409",
                     format!("call expression on {} not safe", o),
                     vec![(loc.clone(), format!("call requires a function"))],
                 ));
@@ -4028,12 +4602,10 @@ impl Symbolic {
                     let mut estack =
                         vec![(loc.clone(),
                 format!("you may need an if condition or callsite_assert to prove it is safe"))];
-                    estack.extend(self.demonstrate(
-                        model.as_ref().unwrap(),
-                        (tmp1, self.memory[tmp1].temporal),
-                        0,
-                    ));
-                    Err(self.trace(format!("deref of unsafe pointer"), estack))
+                    if let Some(model) = &model {
+                        estack.extend(self.demonstrate(model, (tmp1, self.memory[tmp1].temporal), 0));
+                    }
+                    Err(self.trace("E0401", format!("deref of unsafe pointer"), estack))
                 }
                 true => Ok(()),
             },
@@ -4082,8 +4654,9 @@ impl Symbolic {
             popped_tags,
         )?;
 
-        if self.memory[lhs_sym].t != smt::Type::Unsigned(64) {
+        if self.memory[lhs_sym].t != Self::smt_pointer_type() {
             return Err(self.trace(
+                "E0406",
                 format!("cannot prove memory access due to unexpected type"),
                 vec![(
                     loc.clone(),
@@ -4107,6 +4680,7 @@ impl Symbolic {
             }
             o => {
                 return Err(self.trace(
+                    "E0402",
                     format!("deref of {} is not possible",  self.memory[lhs_sym].typed),
                     vec![(loc.clone(), format!("this must be a pointer, but {} is a {}", self.memory[lhs_sym].name, o))],
                 ))
@@ -4116,13 +4690,18 @@ impl Symbolic {
         Ok(member)
     }
 
+    fn smt_pointer_type() -> crate::smt::Type {
+        crate::smt::Type::Unsigned(DataModel::current().pointer_width())
+    }
+
     fn smt_type(t: &ast::Typed) -> crate::smt::Type {
         if t.ptr.len() > 0 {
-            return crate::smt::Type::Unsigned(64);
+            return Self::smt_pointer_type();
         }
+        let dm = DataModel::current();
         match t.t {
             ast::Type::Bool => crate::smt::Type::Bool,
-            ast::Type::Other(_) => crate::smt::Type::Unsigned(64),
+            ast::Type::Other(_) => Self::smt_pointer_type(),
             ast::Type::U8 => crate::smt::Type::Unsigned(8),
             ast::Type::U16 => crate::smt::Type::Unsigned(16),
             ast::Type::U32 => crate::smt::Type::Unsigned(32),
@@ -4134,14 +4713,14 @@ impl Symbolic {
             ast::Type::I64 => crate::smt::Type::Signed(64),
             ast::Type::I128 => crate::smt::Type::Signed(128),
 
-            ast::Type::UInt => crate::smt::Type::Unsigned(64),
-            ast::Type::Int => crate::smt::Type::Signed(64),
+            ast::Type::UInt => crate::smt::Type::Unsigned(dm.int_width()),
+            ast::Type::Int => crate::smt::Type::Signed(dm.int_width()),
 
-            ast::Type::USize => crate::smt::Type::Unsigned(64),
-            ast::Type::ISize => crate::smt::Type::Signed(64),
+            ast::Type::USize => crate::smt::Type::Unsigned(dm.size_width()),
+            ast::Type::ISize => crate::smt::Type::Signed(dm.size_width()),
 
-            ast::Type::F64 => crate::smt::Type::Unsigned(64),
-            ast::Type::F32 => crate::smt::Type::Unsigned(64),
+            ast::Type::F64 => crate::smt::Type::Float(11, 53),
+            ast::Type::F32 => crate::smt::Type::Float(8, 24),
 
             // these are actually just pollution in smt. they're casted before use
             ast::Type::New => crate::smt::Type::Unsigned(64),
@@ -4154,6 +4733,100 @@ impl Symbolic {
         }
     }
 
+    // the arguments of the current function in the model, as zz literals.
+    // only works for functions that take integers and bools by value
+    fn counterexample_args(&self, model: &smt::ModelRef) -> Option<Vec<String>> {
+        let mut args = Vec::new();
+        for (name, sym) in &self.current_function_args {
+            let typed = &self.memory[sym.0].typed;
+            let v = match self.ssa.extract(model, *sym) {
+                Some(v) if typed.ptr.is_empty() => v,
+                _ => {
+                    debug!("no counterexample for {}: {} is not a plain value", self.current_function_name, name);
+                    return None;
+                }
+            };
+            let v = match typed.t {
+                ast::Type::Bool => format!("{}", v > 0),
+                ast::Type::U8
+                | ast::Type::U16
+                | ast::Type::U32
+                | ast::Type::U64
+                | ast::Type::UInt
+                | ast::Type::USize => format!("{}", v),
+                ast::Type::Char
+                | ast::Type::I8
+                | ast::Type::I16
+                | ast::Type::I32
+                | ast::Type::I64
+                | ast::Type::Int
+                | ast::Type::ISize => {
+                    let shift = 64 - Symbolic::primitive_size(typed).unwrap() * 8;
+                    format!("{}", ((v << shift) as i64) >> shift)
+                }
+                _ => {
+                    debug!("no counterexample for {}: {} is not an integer", self.current_function_name, name);
+                    return None;
+                }
+            };
+            args.push(v);
+        }
+        Some(args)
+    }
+
+    // write a test that calls the current function with the arguments it fails with to target/cex/.
+    // copied into tests/, it is rejected at the call once the function requires what it needs,
+    // and runs once the function handles these arguments
+    fn emit_counterexample(&self, args: &[String], violated: &str) -> std::io::Result<()> {
+        let fname = Name::from(&self.current_function_name);
+        if fname.len() < 3 {
+            return Ok(());
+        }
+        let module = Name(fname.0[..fname.len() - 1].to_vec());
+        let fnname = fname.0.last().unwrap();
+
+        let dir = crate::project::target_dir().join("cex");
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join(format!("cex_{}.zz", fnname));
+        let src = format!(
+            "// counterexample for {} generated by zz check --emit-counterexamples\n\
+             // these arguments break {}\n\
+             using {};\n\
+             \n\
+             export fn main() -> int {{\n\
+             \x20   {}::{}({});\n\
+             \x20   return 0;\n\
+             }}\n",
+            fname,
+            violated,
+            module.0[1..].join("::"),
+            module.0.last().unwrap(),
+            fnname,
+            args.join(", "),
+        );
+        std::fs::write(&path, src)?;
+        info!("counterexample written to {}, copy it into tests/ to keep it", path.to_string_lossy());
+        Ok(())
+    }
+
+    fn primitive_size(t: &ast::Typed) -> Option<u64> {
+        let dm = DataModel::current();
+        if t.ptr.len() > 0 {
+            return Some(dm.pointer_width() as u64 / 8);
+        }
+        let bits = match t.t {
+            ast::Type::U8 | ast::Type::I8 | ast::Type::Char => 8,
+            ast::Type::U16 | ast::Type::I16 => 16,
+            ast::Type::U32 | ast::Type::I32 | ast::Type::F32 => 32,
+            ast::Type::U64 | ast::Type::I64 | ast::Type::F64 => 64,
+            ast::Type::U128 | ast::Type::I128 => 128,
+            ast::Type::Int | ast::Type::UInt => dm.int_width(),
+            ast::Type::USize | ast::Type::ISize => dm.size_width(),
+            _ => return None,
+        };
+        Some(bits as u64 / 8)
+    }
+
     // new stack variable
     fn alloc(
         &mut self,
@@ -4166,6 +4839,7 @@ impl Symbolic {
 
         if let Some(prev) = self.cur().locals.get(&name).cloned() {
             return Err(self.trace(
+                "E0908",
                 format!(
                     "ICE in {}: redeclation of local name '{}' should have failed in expand",
                     self.current_module_name, name
@@ -4239,13 +4913,14 @@ impl Symbolic {
 
                 if self.memory[casym].t != smt::Type::Bool {
                     return Err(self.trace(
+                        "E0301",
                         format!("expected boolean, got {}", self.memory[casym].typed),
                         vec![(expr.loc().clone(), format!("expression must evaluate to boolean"))],
                     ));
                 }
 
                 if !self.ssa.attest((casym, self.memory[casym].temporal), true) {
-                    return Err(self.trace(format!("attachment leads to conflicting constraints"),
+                    return Err(self.trace("E0511", format!("attachment leads to conflicting constraints"),
                         vec![(
                             expr.loc().clone(),
                             format!("attachment leads to conflicting constraints"),
@@ -4406,6 +5081,7 @@ impl Symbolic {
             ast::Tail::None => return Ok(()),
             ast::Tail::Dynamic(_) => {
                 return Err(self.trace(
+                    "E0604",
                     format!("tail size must be known for stack variables"),
                     vec![(
                         self.memory[sym].typed.loc.clone(),
@@ -4433,6 +5109,7 @@ impl Symbolic {
             Some(v) => v,
             None => {
                 return Err(self.trace(
+                    "E0605",
                     format!("tail value on non struct {}", self.memory[sym].typed),
                     vec![(loc.clone(), format!("cannot emit tail binding to ssa. this might be a bug in zz"))],
                 ));
@@ -4491,8 +5168,8 @@ impl Symbolic {
     fn copy(&mut self, lhs: Symbol, rhs: Symbol, used_here: &ast::Location) -> Result<(), Error> {
         // transfer theories of pointers
         // TODO: nah thats shitty. they should automatically transfer in smt
-        if self.memory[rhs].t == smt::Type::Unsigned(64)
-            && self.memory[lhs].t == smt::Type::Unsigned(64)
+        if self.memory[rhs].t == Self::smt_pointer_type()
+            && self.memory[lhs].t == Self::smt_pointer_type()
         {
             let tmp_safe_transfer = self.temporary(
                 format!(
@@ -4559,18 +5236,20 @@ impl Symbolic {
         match self.memory[rhs].value.clone() {
             Value::Void => {
                 return Err(self.trace(
+                    "E0338",
                     format!("void is not a value: '{}'", self.memory[rhs].name),
                     vec![(used_here.clone(), "used here".to_string())],
                 ));
             }
             Value::Uninitialized => {
                 // FIXME for now this is too noisy.
-                //return Err(self.trace(format!("unsafe read access to uninitialized local '{}'", self.memory[rhs].name), vec![
+                //return Err(self.trace("E0408", format!("unsafe read access to uninitialized local '{}'", self.memory[rhs].name), vec![
                 //    (used_here.clone(), "used here".to_string())
                 //]));
             }
             Value::Theory { .. } => {
                 return Err(self.trace(
+                    "E0333",
                     format!("taking the value of a theory is not a thing"),
                     vec![(used_here.clone(), "used here".to_string())],
                 ));
@@ -4587,6 +5266,7 @@ impl Symbolic {
                             *prev_len = len;
                         } else if len > *prev_len {
                             return Err(self.trace(
+                                "E0314",
                                 format!("assigning arrays of different len"),
                                 vec![(
                                     used_here.clone(),
@@ -4652,6 +5332,7 @@ impl Symbolic {
                 Value::Integer(parser::Integer::Unsigned(f)) if f == 0 => (),
                 _ => {
                     return Err(self.trace(
+                        "E0312",
                         "assignment of incompatible pointer depth".to_string(),
                         vec![(
                             used_here.clone(),
@@ -4667,6 +5348,7 @@ impl Symbolic {
 
         if self.memory[lhs].t != self.memory[rhs].t {
             return Err(self.trace(
+                "E0313",
                 "assignment of incompatible types (2)".into(),
                 vec![(
                     used_here.clone(),
@@ -4707,7 +5389,7 @@ impl Symbolic {
     }
 
     fn ssa_mark_safe(&mut self, sym: Symbol, loc: &ast::Location) -> Result<(), Error> {
-        if self.memory[sym].t != smt::Type::Unsigned(64) {
+        if self.memory[sym].t != Self::smt_pointer_type() {
             panic!("ICE: ssa_mark_safe on non pointer");
         }
         let tmp = self.temporary(
@@ -4731,7 +5413,7 @@ impl Symbolic {
     }
 
     fn ssa_mark_nullterm(&mut self, sym: Symbol, loc: &ast::Location) -> Result<(), Error> {
-        if self.memory[sym].t != smt::Type::Unsigned(64) {
+        if self.memory[sym].t != Self::smt_pointer_type() {
             panic!("ICE: nullterm on non pointer");
         }
         let tmp = self.temporary(
@@ -4754,6 +5436,38 @@ impl Symbolic {
         Ok(())
     }
 
+    // C leaves casting a float to an integer that can't hold it undefined
+    fn float_fits_into_ssa(&mut self, sym: Symbol, into: &ast::Typed, loc: &ast::Location) -> Result<(), Error> {
+        let tmp = self.temporary(
+            format!("{} fits into {}", self.memory[sym].name, into),
+            ast::Typed {
+                t: ast::Type::Bool,
+                loc: loc.clone(),
+                ..Default::default()
+            },
+            loc.clone(),
+            Tags::new(),
+        )?;
+        self.ssa.float_fits(tmp, (sym, self.memory[sym].temporal), Self::smt_type(into));
+
+        self.ssa.assert(
+            vec![(tmp, self.memory[tmp].temporal)],
+            |a, model| match a {
+                false => {
+                    let mut estack = vec![(
+                        loc.clone(),
+                        format!("{} may be nan or out of range for {}", self.memory[sym].name, into),
+                    )];
+                    if let Some(model) = &model {
+                        estack.extend(self.demonstrate(model, (sym, self.memory[sym].temporal), 0));
+                    }
+                    Err(self.trace("E0412", format!("float to integer cast may overflow"), estack))
+                }
+                true => Ok(()),
+            },
+        )
+    }
+
     fn len_into_ssa(&mut self, sym: Symbol, loc: &ast::Location, len: usize) -> Result<(), Error> {
         let tmp = self.temporary(
             format!("len({})", self.memory[sym].name),
@@ -4771,7 +5485,7 @@ impl Symbolic {
             .expect("ICE: len theory not built in");
         self.ssa
             .invocation(*lensym, vec![(sym, self.memory[sym].temporal)], (tmp, 0));
-        self.ssa.literal(tmp, len as u64, smt::Type::Unsigned(64));
+        self.ssa.literal(tmp, len as u64, self.memory[tmp].t.clone());
         Ok(())
     }
 
@@ -4801,6 +5515,7 @@ impl Symbolic {
         }
 
         return Err(self.trace(
+            "E0203",
             format!("undefined symbol '{}'", name),
             vec![(
                 used_here.clone(),
@@ -4809,27 +5524,39 @@ impl Symbolic {
         ));
     }
 
-    fn new(module_name: &Name, solver: Option<String>, macros_available: bool) -> Self {
+    fn new(
+        module_name: &Name,
+        solver: &[&'static smt::SolverKind],
+        timeout: u64,
+        fingerprint: &str,
+        macros_available: bool,
+    ) -> Self {
         Symbolic {
             stack: vec![Scope {
                 name: "global".to_string(),
                 locals: Default::default(),
                 trace: Vec::new(),
+                guards: Vec::new(),
             }],
             memory: Default::default(),
-            ssa: Solver::new(module_name.0.join("_"), solver),
+            ssa: Solver::new(module_name.0.join("_"), solver, timeout, fingerprint),
             builtin: Default::default(),
             defs: HashMap::new(),
             current_module_name: module_name.human_name(),
             current_function_name: String::new(),
             current_function_ret: None,
             current_function_model: Vec::new(),
+            current_function_args: Vec::new(),
             current_call: Vec::new(),
             in_loop: false,
+            loops: Vec::new(),
             in_model: false,
             in_callassign_body: false,
             macros_available,
             incomplete: false,
+            explain: None,
+            explained: None,
+            counterexample: Default::default(),
         }
     }
 
@@ -4839,6 +5566,7 @@ impl Symbolic {
             name,
             locals: HashMap::new(),
             trace: Vec::new(),
+            guards: Vec::new(),
         });
     }
 
@@ -4865,11 +5593,26 @@ impl Symbolic {
                     loc.clone(),
                     Tags::new(),
                 )?;
+                if !Self::literal_fits(&v, &self.memory[sym].t) {
+                    return Err(self.trace(
+                        "E0306",
+                        format!("literal does not fit into {}", t),
+                        vec![(
+                            loc.clone(),
+                            format!("{} is too small on a {} target", t, DataModel::current()),
+                        )],
+                    ));
+                }
                 self.memory[sym].value = value;
-                let v = match v {
+                let mut v = match v {
                     parser::Integer::Signed(v) => v as u64,
                     parser::Integer::Unsigned(v) => v as u64,
                 };
+                if let smt::Type::Signed(w) | smt::Type::Unsigned(w) = self.memory[sym].t {
+                    if w < 64 {
+                        v &= (1u64 << w) - 1;
+                    }
+                }
                 self.ssa.literal(sym, v, self.memory[sym].t.clone());
                 self.ssa_mark_valid(sym, loc)?;
                 Ok(sym)
@@ -4888,6 +5631,17 @@ impl Symbolic {
         }
     }
 
+    // literals must be representable on the target, C would silently truncate them.
+    // a signed type has one bit less for the magnitude
+    fn literal_fits(v: &parser::Integer, t: &smt::Type) -> bool {
+        match (v, t) {
+            (parser::Integer::Unsigned(v), smt::Type::Signed(w)) => *w > 64 || *v < (1u64 << (w - 1)),
+            (parser::Integer::Unsigned(v), smt::Type::Unsigned(w)) => *w >= 64 || *v < (1u64 << w),
+            (parser::Integer::Signed(v), smt::Type::Signed(w)) => *w >= 64 || *v >= -(1i64 << (w - 1)),
+            _ => true,
+        }
+    }
+
     fn demonstrate(
         &self,
         model: &smt::ModelRef,
@@ -4896,6 +5650,10 @@ impl Symbolic {
     ) -> Vec<(ast::Location, String)> {
         let mut estack = Vec::new();
 
+        if depth == 0 && EMIT_COUNTEREXAMPLES.load(Ordering::Relaxed) {
+            *self.counterexample.borrow_mut() = self.counterexample_args(model);
+        }
+
         let valloc = match self.memory[sym.0].assignments.get(&sym.1) {
             Some(loc) => loc.clone(),
             None => self.memory[sym.0].declared.clone(),
@@ -5017,6 +5775,143 @@ impl Symbolic {
         estack
     }
 
+    // zz explain stops before the statement holding the position,
+    // but goes into blocks to get closer
+    fn explain_before(&self, stm: &ast::Statement) -> bool {
+        let at = match &self.explain {
+            Some(v) => v,
+            None => return false,
+        };
+        let (loc, compound) = match stm {
+            ast::Statement::Mark { loc, .. }
+            | ast::Statement::Label { loc, .. }
+            | ast::Statement::Assign { loc, .. }
+            | ast::Statement::Expr { loc, .. }
+            | ast::Statement::Continue { loc, .. }
+            | ast::Statement::Break { loc, .. }
+            | ast::Statement::Return { loc, .. }
+            | ast::Statement::Var { loc, .. }
+            | ast::Statement::CBlock { loc, .. }
+            | ast::Statement::MacroCall { loc, .. } => (loc, false),
+            ast::Statement::Switch { loc, .. } => (loc, true),
+            ast::Statement::If { branches } => (&branches[0].0, true),
+            ast::Statement::While { expr, .. } => (expr.loc(), true),
+            ast::Statement::For { .. } | ast::Statement::Block(_) | ast::Statement::Unsafe(_) => return false,
+        };
+        if loc.file != at.file {
+            return false;
+        }
+        if compound {
+            at.start < loc.start
+        } else {
+            at.start < loc.end
+        }
+    }
+
+    // collects the facts about everything in scope and unwinds with an error
+    // that execute() knows to ignore
+    fn explain(&mut self) -> Error {
+        let mut locals = Vec::new();
+        for scope in &self.stack[1..] {
+            for (name, sym) in &scope.locals {
+                if name.0.len() == 1 && name.0[0] == "return" {
+                    continue;
+                }
+                locals.push((name.to_string(), *sym));
+            }
+        }
+        locals.sort_by_key(|(_, sym)| *sym);
+
+        let mut explained = Vec::new();
+        for (name, sym) in locals {
+            self.explain_symbol(name, sym, &mut explained);
+        }
+        self.explained = Some(explained);
+        Error::new("E0806", "explained".to_string(), Vec::new())
+    }
+
+    fn explain_symbol(&self, name: String, sym: Symbol, into: &mut Vec<Explained>) {
+        let storage = &self.memory[sym];
+        let at = (sym, storage.temporal);
+        let mut facts = Vec::new();
+
+        if let Value::Struct { members } = &storage.value {
+            let mut members = members.iter().collect::<Vec<(&String, &Symbol)>>();
+            members.sort();
+            for (member, msym) in members {
+                self.explain_symbol(format!("{}.{}", name, member), *msym, into);
+            }
+            return;
+        }
+
+        if !storage.typed.ptr.is_empty() {
+            for theory in &["safe", "nullterm"] {
+                let th = self.builtin[*theory];
+                if self.ssa.holds(&self.ssa.theory_term(th, &[at])) {
+                    facts.push(format!("{}({})", theory, name));
+                }
+            }
+            let th = self.builtin["len"];
+            let t = smt::Type::Unsigned(DataModel::current().size_width());
+            if let Some((lo, hi, exact)) = self.ssa.bounds(&self.ssa.theory_term(th, &[at]), &t) {
+                facts.extend(Self::explain_range(&format!("len({})", name), lo, hi, exact, &t));
+            }
+        } else {
+            match (&storage.typed.t, &storage.t) {
+                (ast::Type::Bool, _) => {
+                    let term = self.ssa.term(&at);
+                    if self.ssa.holds(&term) {
+                        facts.push(format!("{}", name));
+                    } else if self.ssa.holds(&format!("(not {})", term)) {
+                        facts.push(format!("!{}", name));
+                    }
+                }
+                (ast::Type::Other(_), _)
+                | (ast::Type::New, _)
+                | (ast::Type::Elided, _)
+                | (ast::Type::Typeid, _)
+                | (ast::Type::Void, _) => (),
+                (_, t) => {
+                    if let Some((lo, hi, exact)) = self.ssa.bounds(&self.ssa.term(&at), t) {
+                        facts.extend(Self::explain_range(&name, lo, hi, exact, t));
+                    }
+                }
+            }
+        }
+
+        into.push(Explained {
+            name,
+            typed: storage.typed.to_string(),
+            facts,
+        });
+    }
+
+    // a range is only a fact if it's narrower than the type. one the solver
+    // could narrow down further if it was asked more often is marked
+    fn explain_range(name: &str, lo: i128, hi: i128, exact: bool, t: &smt::Type) -> Option<String> {
+        let (min, max) = match t {
+            smt::Type::Signed(size) => (-(1i128 << (size - 1)), (1i128 << (size - 1)) - 1),
+            smt::Type::Unsigned(size) => (0, (1i128 << size) - 1),
+            _ => return None,
+        };
+        let fact = if lo == hi {
+            format!("{} == {}", name, lo)
+        } else if lo > min && hi < max {
+            format!("{} <= {} <= {}", lo, name, hi)
+        } else if lo > min {
+            format!("{} >= {}", name, lo)
+        } else if hi < max {
+            format!("{} <= {}", name, hi)
+        } else {
+            return None;
+        };
+        if exact {
+            Some(fact)
+        } else {
+            Some(format!("{} (approximate)", fact))
+        }
+    }
+
     fn stmname(&self, sym: Symbol) -> String {
         let name = self.memory[sym]
             .name
@@ -5026,65 +5921,394 @@ impl Symbolic {
         format!("{}_{}", sym, name)
     }
 
-    pub fn trace(&self, message: String, mut details: Vec<(ast::Location, String)>) -> Error {
+    pub fn trace(&self, code: &'static str, message: String, mut details: Vec<(ast::Location, String)>) -> Error {
+        let counterexample = self.counterexample.borrow_mut().take();
+
+        // there is no counterexample, the solver just gave up or failed
+        if self.ssa.undecided() {
+            let loc = details
+                .first()
+                .map(|(loc, _)| loc.clone())
+                .unwrap_or_else(|| self.ssa.last_loc().clone());
+            let failure = self.ssa.failure();
+            let why = match &failure {
+                Some(_) => format!("{} could not be decided, the solver failed on it", message),
+                None => format!(
+                    "{} could not be decided within {}ms. try @smt_timeout",
+                    message,
+                    self.ssa.timeout()
+                ),
+            };
+            details.insert(0, (loc, why));
+            for loc in self.current_call.iter().rev() {
+                details.push((loc.clone(), "last callsite".to_string()));
+            }
+            if let Some(failure) = failure {
+                return Error::new("E0808", format!("smt solver failed: {}", failure), details);
+            }
+            return Error::new("E0519", format!("proof timed out"), details);
+        }
+
+        if let Some(args) = counterexample {
+            let loc = details
+                .first()
+                .map(|(loc, _)| loc.clone())
+                .unwrap_or_else(|| self.ssa.last_loc().clone());
+            let violated = format!("{} {} at {}", code, message, loc);
+            if let Err(e) = self.emit_counterexample(&args, &violated) {
+                details.push((loc, format!("the counterexample could not be written: {}", e)));
+            }
+        }
+
         for loc in self.current_call.iter().rev() {
             details.push((loc.clone(), "last callsite".to_string()));
         }
 
-        Error::new(message, details)
+        Error::new(code, message, details)
+    }
+}
+
+// what the statements of a loop may modify
+#[derive(Default)]
+struct LoopAssigned {
+    // locals that are assigned, and if they are assigned themselves
+    // or only a member, an element or what they point to
+    names: Vec<(String, bool)>,
+    // something was assigned that isn't reached through a name
+    unknown: bool,
+}
+
+impl LoopAssigned {
+    fn lvalue(&mut self, expr: &ast::Expression) {
+        match (expr, lvalue_root(expr)) {
+            (ast::Expression::Name(_), Some(name)) => self.names.push((name, true)),
+            (_, Some(name)) => self.names.push((name, false)),
+            (_, None) => self.unknown = true,
+        }
+    }
+}
+
+// the local an lvalue like a.b[3]->c or *p is stored in
+fn lvalue_root(expr: &ast::Expression) -> Option<String> {
+    match expr {
+        ast::Expression::Name(name) => Some(name.to_string()),
+        ast::Expression::MemberAccess { lhs, .. } | ast::Expression::ArrayAccess { lhs, .. } => lvalue_root(lhs),
+        ast::Expression::UnaryPre {
+            op: ast::PrefixOperator::Deref,
+            expr,
+            ..
+        }
+        | ast::Expression::Cast { expr, .. }
+        | ast::Expression::Unsafe { expr, .. } => lvalue_root(expr),
+        _ => None,
+    }
+}
+
+// locals that may be modified by these statements
+fn loop_assigned(stms: &[Box<ast::Statement>], names: &mut LoopAssigned) {
+    for stm in stms {
+        match stm.as_ref() {
+            ast::Statement::Assign { lhs, rhs, .. } => {
+                names.lvalue(lhs);
+                expr_assigned(lhs, names);
+                expr_assigned(rhs, names);
+            }
+            ast::Statement::Expr { expr, .. } => expr_assigned(expr, names),
+            ast::Statement::Var { assign: Some(expr), .. } => expr_assigned(expr, names),
+            ast::Statement::Return { expr: Some(expr), .. } => expr_assigned(expr, names),
+            ast::Statement::If { branches } => {
+                for (_, expr, body) in branches {
+                    if let Some(expr) = expr {
+                        expr_assigned(expr, names);
+                    }
+                    loop_assigned(&body.statements, names);
+                }
+            }
+            ast::Statement::Switch { expr, cases, default, .. } => {
+                expr_assigned(expr, names);
+                for (_, body) in cases {
+                    loop_assigned(&body.statements, names);
+                }
+                if let Some(default) = default {
+                    loop_assigned(&default.statements, names);
+                }
+            }
+            ast::Statement::While { expr, body, .. } => {
+                expr_assigned(expr, names);
+                loop_assigned(&body.statements, names);
+            }
+            ast::Statement::For { e1, e2, e3, body, .. } => {
+                loop_assigned(e1, names);
+                if let Some(e2) = e2 {
+                    expr_assigned(e2, names);
+                }
+                loop_assigned(e3, names);
+                loop_assigned(&body.statements, names);
+            }
+            ast::Statement::Block(body) | ast::Statement::Unsafe(body) => {
+                loop_assigned(&body.statements, names);
+            }
+            _ => (),
+        }
+    }
+}
+
+fn expr_assigned(expr: &ast::Expression, names: &mut LoopAssigned) {
+    match expr {
+        ast::Expression::UnaryPost { expr, .. } => {
+            names.lvalue(expr);
+            expr_assigned(expr, names);
+        }
+        ast::Expression::UnaryPre { expr, op, .. } => {
+            match op {
+                ast::PrefixOperator::Increment
+                | ast::PrefixOperator::Decrement
+                | ast::PrefixOperator::AddressOf => names.lvalue(expr),
+                _ => (),
+            }
+            expr_assigned(expr, names);
+        }
+        ast::Expression::MemberAccess { lhs, .. } => expr_assigned(lhs, names),
+        ast::Expression::ArrayAccess { lhs, rhs, .. } | ast::Expression::Infix { lhs, rhs, .. } => {
+            expr_assigned(lhs, names);
+            expr_assigned(rhs, names);
+        }
+        ast::Expression::Call { name, args, .. } => {
+            expr_assigned(name, names);
+            for arg in args {
+                expr_assigned(arg, names);
+            }
+        }
+        ast::Expression::MacroCall { args, .. } | ast::Expression::ArrayInit { fields: args, .. } => {
+            for arg in args {
+                expr_assigned(arg, names);
+            }
+        }
+        ast::Expression::StructInit { fields, .. } => {
+            for (_, field) in fields {
+                expr_assigned(field, names);
+            }
+        }
+        ast::Expression::Cast { expr, .. }
+        | ast::Expression::Unsafe { expr, .. }
+        | ast::Expression::Cpp { expr, .. } => expr_assigned(expr, names),
+        _ => (),
+    }
+}
+
+// every name an expression refers to
+fn expr_names(expr: &ast::Expression, names: &mut Vec<Name>) {
+    match expr {
+        ast::Expression::Name(typed) => {
+            if let ast::Type::Other(name) = &typed.t {
+                names.push(name.clone());
+            }
+        }
+        ast::Expression::UnaryPost { expr, .. }
+        | ast::Expression::UnaryPre { expr, .. }
+        | ast::Expression::Cast { expr, .. }
+        | ast::Expression::Unsafe { expr, .. }
+        | ast::Expression::Cpp { expr, .. } => expr_names(expr, names),
+        ast::Expression::MemberAccess { lhs, .. } => expr_names(lhs, names),
+        ast::Expression::ArrayAccess { lhs, rhs, .. } | ast::Expression::Infix { lhs, rhs, .. } => {
+            expr_names(lhs, names);
+            expr_names(rhs, names);
+        }
+        ast::Expression::Call { name, args, .. } => {
+            expr_names(name, names);
+            for arg in args {
+                expr_names(arg, names);
+            }
+        }
+        ast::Expression::MacroCall { args, .. } | ast::Expression::ArrayInit { fields: args, .. } => {
+            for arg in args {
+                expr_names(arg, names);
+            }
+        }
+        ast::Expression::StructInit { fields, .. } => {
+            for (_, field) in fields {
+                expr_names(field, names);
+            }
+        }
+        _ => (),
     }
 }
 
+fn has_break(stms: &[Box<ast::Statement>]) -> bool {
+    stms.iter().any(|stm| match stm.as_ref() {
+        ast::Statement::Break { .. } => true,
+        ast::Statement::If { branches } => branches.iter().any(|(_, _, body)| has_break(&body.statements)),
+        ast::Statement::Switch { cases, default, .. } => {
+            cases.iter().any(|(_, body)| has_break(&body.statements))
+                || default.as_ref().map(|body| has_break(&body.statements)).unwrap_or(false)
+        }
+        ast::Statement::Block(body) | ast::Statement::Unsafe(body) => has_break(&body.statements),
+        // a labeled break may leave the outer loop too
+        ast::Statement::While { body, .. } | ast::Statement::For { body, .. } => has_break(&body.statements),
+        _ => false,
+    })
+}
+
 pub fn execute(
     module: &mut flatten::Module,
     macros_available: bool,
-) -> (bool /* ok*/, bool /* complete for caching */) {
+) -> (bool /* ok*/, bool /* complete for caching */, Vec<proofcache::Warning> /* to repeat when cached */) {
     use rayon::prelude::*;
 
     let mut incomplete = false;
     let mut defs = Vec::new();
     let mut function_at = Vec::new();
+    let mut cached = Vec::new();
+    let mut warnings = Vec::new();
+
+    let default_solver = smt::SolverKind::configured();
+    let default_timeout = smt::TIMEOUT.load(std::sync::atomic::Ordering::Relaxed) as u64;
+    let no_solver = |e: &Error| {
+        parser::emit_error(e.code, e.message.clone(), &e.details);
+        (false, false, Vec::new())
+    };
+
+    // explaining only runs the function around the position, and always runs it
+    let explain = EXPLAIN.lock().unwrap().clone();
+
     for (i, (d, complete)) in module.d.clone().into_iter().enumerate() {
         if let ast::Def::Function { ref derives, .. } = d.def {
+            if let Some(at) = &explain {
+                // the declaration span runs into the whitespace after it
+                let (source, _) = ast::read_source(d.loc.file.clone());
+                let end = source[..d.loc.end].trim_end().len();
+                if d.loc.file != at.file || at.start < d.loc.start || at.start > end {
+                    continue;
+                }
+            }
             let mut solver = None;
+            let mut timeout = default_timeout;
             for derive in derives {
+                if derive.makro == "smt_timeout" {
+                    match derive.args.first().map(|x| *x.clone()) {
+                        Some(ast::Expression::Literal { v, .. }) if v.parse::<u64>().is_ok() => {
+                            timeout = v.parse().unwrap();
+                        }
+                        _ => {
+                            parser::emit_error(
+                                "E0801",
+                                format!("@smt_timeout macro expects one integer argument in milliseconds"),
+                                &[(derive.loc.clone(), "in this derive")],
+                            );
+                            return (false, false, Vec::new());
+                        }
+                    }
+                }
                 if derive.makro == "solver" {
                     match derive.args.first().map(|x| *x.clone()) {
                         Some(ast::Expression::LiteralString { v, .. }) => {
-                            solver = Some(String::from_utf8_lossy(&v).to_string());
+                            match smt::SolverKind::require(&String::from_utf8_lossy(&v)) {
+                                Ok(kind) => solver = Some(vec![kind]),
+                                Err(e) => {
+                                    parser::emit_error(e.code, e.message, &[(derive.loc.clone(), "in this derive")]);
+                                    return (false, false, Vec::new());
+                                }
+                            }
                         }
                         _ => {
                             parser::emit_error(
+                                "E0802",
                                 format!("@solver macro expects one string literal argument"),
                                 &[(derive.loc.clone(), "in this derive")],
                             );
+                            return (false, false, Vec::new());
                         }
                     }
                 }
             }
             if complete == flatten::TypeComplete::Complete {
-                function_at.push((i, d.name.clone(), module.clone(), solver));
+                let solver = match (solver, &default_solver) {
+                    (Some(kinds), _) => kinds,
+                    (None, Ok(kinds)) => kinds.clone(),
+                    (None, Err(e)) => return no_solver(e),
+                };
+                let key = proofcache::key(module, i, &solver, timeout);
+                let proven = match &explain {
+                    None => proofcache::load(&key, &d.loc),
+                    Some(_) => None,
+                };
+                if let Some(entry) = proven {
+                    debug!("{} proven before", d.name);
+                    for warning in &entry.warnings {
+                        warning.emit();
+                    }
+                    warnings.extend(entry.warnings);
+                    cached.push((i, entry.local));
+                    continue;
+                }
+                function_at.push((i, d.name.clone(), module.clone(), solver, timeout, key));
             }
         }
         defs.push(d.clone());
     }
 
+    for (at, l) in cached {
+        module.d[at].0 = l;
+    }
+
+    if let Some(at) = explain {
+        let (i, name, _, solver, timeout, key) = match function_at.pop() {
+            Some(v) => v,
+            None => {
+                parser::emit_error(
+                    "E0806",
+                    "nothing to explain",
+                    &[(at, "this is not inside a function body")],
+                );
+                super::exit(1);
+            }
+        };
+        let mut sym = Symbolic::new(&Name::from(&name), &solver, timeout, &key, macros_available);
+        sym.explain = Some(at.clone());
+        let r = sym.execute_module(module, Some(i));
+        match (sym.explained, r) {
+            (Some(explained), _) => {
+                print_explained(&at, &name, &explained);
+                super::exit(0);
+            }
+            (None, Err(e)) => parser::emit_error(e.code, e.message.clone(), &e.details),
+            (None, Ok(())) => parser::emit_error(
+                "E0806",
+                "nothing to explain",
+                &[(at, "this is not reachable from the start of the function")],
+            ),
+        }
+        super::exit(1);
+    }
+
     // execute one in serial on the borrowed module to get modifications to globals
-    if let Some((at, name, _, solver)) = function_at.pop() {
-        let mut sym = Symbolic::new(&Name::from(&name), solver, macros_available);
-        if let Err(e) = sym.execute_module(module, Some(at)) {
-            parser::emit_error(e.message.clone(), &e.details);
-            return (false, false);
+    if let Some((at, name, _, solver, timeout, key)) = function_at.pop() {
+        let mut sym = Symbolic::new(&Name::from(&name), &solver, timeout, &key, macros_available);
+        let (r, recorded) = proofcache::recording(|| sym.execute_module(module, Some(at)));
+        if let Err(e) = r {
+            parser::emit_error(e.code, e.message.clone(), &e.details);
+            return (false, false, Vec::new());
         }
         if sym.incomplete {
             incomplete = true;
+        } else if !lint::denied() {
+            // a cached proof would skip the denied lint next time
+            proofcache::store(&key, &module.d[at].0, recorded.clone());
         }
+        warnings.extend(recorded);
     } else {
-        let mut sym = Symbolic::new(&module.name, None, macros_available);
-        if let Err(e) = sym.execute_module(module, None) {
-            parser::emit_error(e.message.clone(), &e.details);
-            return (false, false);
+        let solver = match &default_solver {
+            Ok(kind) => kind,
+            Err(e) => return no_solver(e),
+        };
+        // no function bodies, nothing to record a winner for
+        let mut sym = Symbolic::new(&module.name, solver, default_timeout, "", macros_available);
+        let (r, recorded) = proofcache::recording(|| sym.execute_module(module, None));
+        if let Err(e) = r {
+            parser::emit_error(e.code, e.message.clone(), &e.details);
+            return (false, false, Vec::new());
         }
+        warnings.extend(recorded);
         if sym.incomplete {
             incomplete = true;
         }
@@ -5092,28 +6316,92 @@ pub fn execute(
 
     let repl = function_at
         .into_par_iter()
-        .map(|(at, name, mut module, solver)| {
-            let mut sym = Symbolic::new(&Name::from(&name), solver, macros_available);
-            match sym.execute_module(&mut module, Some(at)) {
+        .map(|(at, name, mut module, solver, timeout, key)| {
+            let mut sym = Symbolic::new(&Name::from(&name), &solver, timeout, &key, macros_available);
+            let (r, recorded) = proofcache::recording(|| sym.execute_module(&mut module, Some(at)));
+            match r {
                 Err(e) => {
-                    parser::emit_error(e.message.clone(), &e.details);
+                    parser::emit_error(e.code, e.message.clone(), &e.details);
                     None
                 }
-                Ok(_) => Some((at, module.d.remove(at).0, sym.incomplete)),
+                Ok(_) => {
+                    let l = module.d.remove(at).0;
+                    if !sym.incomplete && !lint::denied() {
+                        proofcache::store(&key, &l, recorded.clone());
+                    }
+                    Some((at, l, sym.incomplete, recorded))
+                }
             }
         })
-        .collect::<Vec<Option<(usize, ast::Local, bool)>>>();
+        .collect::<Vec<Option<(usize, ast::Local, bool, Vec<proofcache::Warning>)>>>();
 
     for r in repl {
-        if let Some((at, l, incomplete2)) = r {
+        if let Some((at, l, incomplete2, recorded)) = r {
             module.d[at].0 = l;
             if incomplete2 {
                 incomplete = true;
             }
+            warnings.extend(recorded);
         } else {
-            return (false, false);
+            return (false, false, Vec::new());
         }
     }
 
-    (true, !incomplete)
+    (true, !incomplete, warnings)
+}
+
+// zz explain file:LINE:COL, as the byte offset into the file
+pub fn explain_at(position: &str) -> Result<ast::Location, String> {
+    let mut parts = position.rsplitn(3, ':');
+    let col = parts.next().and_then(|v| v.parse::<usize>().ok());
+    let line = parts.next().and_then(|v| v.parse::<usize>().ok());
+    let (file, line, col) = match (parts.next(), line, col) {
+        (Some(file), Some(line), Some(col)) if line > 0 && col > 0 => (file, line, col),
+        _ => return Err(format!("expected file.zz:LINE:COL, got {}", position)),
+    };
+    let file = std::fs::canonicalize(file).map_err(|e| format!("{}: {}", file, e))?;
+    let file = file.to_string_lossy().to_string();
+    let (source, _) = ast::read_source(file.clone());
+
+    let mut start = source.len();
+    if let Some((at, text)) = source.split('\n').scan(0, |at, l| {
+        let r = (*at, l);
+        *at += l.len() + 1;
+        Some(r)
+    }).nth(line - 1) {
+        start = at + text.char_indices().nth(col - 1).map(|(i, _)| i).unwrap_or(text.len());
+    }
+    Ok(ast::Location {
+        file,
+        line,
+        start,
+        end: start,
+    })
+}
+
+fn print_explained(at: &ast::Location, function: &str, explained: &[Explained]) {
+    if parser::ERRORS_AS_JSON.load(Ordering::SeqCst) {
+        println!(
+            "{}",
+            serde_json::json!({
+                "function": Name::from(function).human_name(),
+                "file": at.file,
+                "line": at.line,
+                "names": explained,
+            })
+        );
+        return;
+    }
+
+    println!("in {} at {}", Name::from(function).human_name(), at);
+    let w1 = explained.iter().map(|e| e.name.len()).max().unwrap_or(0);
+    let w2 = explained.iter().map(|e| e.typed.len()).max().unwrap_or(0);
+    for e in explained {
+        let facts = if e.facts.is_empty() {
+            "nothing proven".to_string()
+        } else {
+            e.facts.join(", ")
+        };
+        println!("    {:w1$}  {:w2$}  {}", e.name, e.typed, facts, w1 = w1, w2 = w2);
+    }
 }
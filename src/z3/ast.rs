@@ -593,8 +593,8 @@ impl<'ctx> Int<'ctx> {
     ///
     /// # Examples
     /// ```
-    /// # use z3::{ast, Config, Context, SatResult, Solver};
-    /// # use z3::ast::Ast;
+    /// # use zz::z3::{ast, Config, Context, SatResult, Solver};
+    /// # use zz::z3::ast::Ast;
     /// # let cfg = Config::new();
     /// # let ctx = Context::new(&cfg);
     /// # let solver = Solver::new(&ctx);
@@ -606,7 +606,7 @@ impl<'ctx> Int<'ctx> {
     /// assert_eq!(solver.check(), SatResult::Sat);
     /// let model = solver.get_model();
     ///
-    /// assert_eq!(-3, model.eval(&x).unwrap().as_i64().unwrap());
+    /// assert_eq!(-3, model.eval(&x, true).unwrap().as_i64().unwrap());
     /// ```
     pub fn from_bv(ast: &BV<'ctx>, signed: bool) -> Int<'ctx> {
         Self::new(ast.ctx, unsafe {
@@ -813,8 +813,8 @@ impl<'ctx> BV<'ctx> {
     ///
     /// # Examples
     /// ```
-    /// # use z3::{ast, Config, Context, SatResult, Solver};
-    /// # use z3::ast::Ast;
+    /// # use zz::z3::{ast, Config, Context, SatResult, Solver};
+    /// # use zz::z3::ast::Ast;
     /// # let cfg = Config::new();
     /// # let ctx = Context::new(&cfg);
     /// # let solver = Solver::new(&ctx);
@@ -827,7 +827,7 @@ impl<'ctx> BV<'ctx> {
     /// assert_eq!(solver.check(), SatResult::Sat);
     /// let model = solver.get_model();
     ///
-    /// assert_eq!(-3, model.eval(&x.to_int(true)).unwrap().as_i64().expect("as_i64() shouldn't fail"));
+    /// assert_eq!(-3, model.eval(&x.to_int(true), true).unwrap().as_i64().expect("as_i64() shouldn't fail"));
     /// ```
     pub fn from_int(ast: &Int<'ctx>, sz: u32) -> BV<'ctx> {
         Self::new(ast.ctx, unsafe {
@@ -1245,8 +1245,8 @@ impl<'ctx> Datatype<'ctx> {
 ///
 /// # Examples
 /// ```
-/// # use z3::{ast, Config, Context, FuncDecl, Pattern, SatResult, Solver, Sort, Symbol};
-/// # use z3::ast::Ast;
+/// # use zz::z3::{ast, Config, Context, FuncDecl, Pattern, SatResult, Solver, Sort, Symbol};
+/// # use zz::z3::ast::Ast;
 /// # use std::convert::TryInto;
 /// # let cfg = Config::new();
 /// # let ctx = Context::new(&cfg);
@@ -1268,7 +1268,7 @@ impl<'ctx> Datatype<'ctx> {
 /// let model = solver.get_model();
 ///
 /// let f_f_3: ast::Int = f.apply(&[&f.apply(&[&ast::Int::from_u64(&ctx, 3).into()])]).try_into().unwrap();
-/// assert_eq!(3, model.eval(&f_f_3).unwrap().as_u64().unwrap());
+/// assert_eq!(3, model.eval(&f_f_3, true).unwrap().as_u64().unwrap());
 /// ```
 pub fn forall_const<'ctx>(
     ctx: &'ctx Context,
@@ -1304,8 +1304,8 @@ pub fn forall_const<'ctx>(
 ///
 /// # Examples
 /// ```
-/// # use z3::{ast, Config, Context, FuncDecl, SatResult, Solver, Sort, Symbol, Pattern};
-/// # use z3::ast::Ast;
+/// # use zz::z3::{ast, Config, Context, FuncDecl, SatResult, Solver, Sort, Symbol, Pattern};
+/// # use zz::z3::ast::Ast;
 /// # use std::convert::TryInto;
 /// # let cfg = Config::new();
 /// # let ctx = Context::new(&cfg);
@@ -1327,7 +1327,7 @@ pub fn forall_const<'ctx>(
 /// let model = solver.get_model();
 ///
 /// let f_f_3: ast::Int = f.apply(&[&f.apply(&[&ast::Int::from_u64(&ctx, 3).into()])]).try_into().unwrap();
-/// assert_eq!(3, model.eval(&f_f_3).unwrap().as_u64().unwrap());
+/// assert_eq!(3, model.eval(&f_f_3, true).unwrap().as_u64().unwrap());
 /// ```
 pub fn exists_const<'ctx>(
     ctx: &'ctx Context,
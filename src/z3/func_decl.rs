@@ -45,7 +45,7 @@ impl<'ctx> FuncDecl<'ctx> {
     /// If the function declaration is a constant, then the arity is `0`.
     ///
     /// ```
-    /// # use z3::{Config, Context, FuncDecl, Solver, Sort, Symbol};
+    /// # use zz::z3::{Config, Context, FuncDecl, Solver, Sort, Symbol};
     /// # let cfg = Config::new();
     /// # let ctx = Context::new(&cfg);
     /// let f = FuncDecl::new(
@@ -20,6 +20,7 @@ mod model;
 mod optimize;
 mod params;
 mod pattern;
+mod script;
 mod solver;
 mod sort;
 mod symbol;
@@ -51,7 +52,7 @@ pub struct Config {
 /// Creating a context with the default configuration:
 ///
 /// ```
-/// use z3::{Config, Context};
+/// use zz::z3::{Config, Context};
 /// let cfg = Config::new();
 /// let ctx = Context::new(&cfg);
 /// ```
@@ -122,7 +123,7 @@ pub struct FuncDecl<'ctx> {
 ///
 /// Example:
 /// ```
-/// # use z3::{ast::Int, Config, Context, DatatypeBuilder, SatResult, Solver, Sort, ast::{Ast, Datatype}};
+/// # use zz::z3::{ast::Int, Config, Context, DatatypeBuilder, SatResult, Solver, Sort, ast::{Ast, Datatype}};
 /// # let cfg = Config::new();
 /// # let ctx = Context::new(&cfg);
 /// # let solver = Solver::new(&ctx);
@@ -146,7 +147,7 @@ pub struct FuncDecl<'ctx> {
 ///
 /// // Get the value out of Some(3)
 /// let ast = option_int.variants[1].accessors[0].apply(&[&y.into()]);
-/// assert_eq!(3, model.eval(&ast.as_int().unwrap()).unwrap().as_i64().unwrap());
+/// assert_eq!(3, model.eval(&ast.as_int().unwrap(), true).unwrap().as_i64().unwrap());
 /// ```
 pub struct DatatypeBuilder<'ctx> {
     ctx: &'ctx Context,
@@ -182,6 +183,25 @@ pub enum SatResult {
     Sat,
 }
 
+/// An SMT-LIB2 command stream evaluated in-process, with its own context and solver.
+///
+/// Text written to it is buffered and only handed to Z3 when a command
+/// that needs a response is issued. Every script can be used from its own
+/// thread, solving does not lock anything shared.
+pub struct Script {
+    ctx: Context,
+    z3_slv: Z3_solver,
+    // in milliseconds, for the solver set-logic makes
+    timeout: u64,
+    // declared functions, and how many there were when each scope was pushed
+    decls: Vec<(Z3_symbol, Z3_func_decl)>,
+    scopes: Vec<usize>,
+    // from the last satisfiable check
+    z3_mdl: Option<Z3_model>,
+    pending: String,
+    tee: Option<std::fs::File>,
+}
+
 /// A pattern for quantifier instantiation, used to guide quantifier instantiation.
 pub struct Pattern<'ctx> {
     ctx: &'ctx Context,
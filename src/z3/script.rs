@@ -0,0 +1,428 @@
+use std::ffi::{CStr, CString};
+use std::io::Write;
+use z3_sys::*;
+use crate::z3::Config;
+use crate::z3::Context;
+use crate::z3::Script;
+
+// contexts are independent of each other, so nothing in here takes Z3_MUTEX.
+// only creating the context does, in Context::new
+impl Script {
+    /// Create a new script with its own context and solver.
+    /// `timeout` is in milliseconds and applies to every `(check-sat)`.
+    pub fn new(timeout: u64) -> Script {
+        let mut cfg = Config::new();
+        cfg.set_model_generation(true);
+        cfg.set_timeout_msec(timeout);
+        let ctx = Context::new(&cfg);
+        unsafe {
+            // errors are reported in the output of eval instead
+            Z3_set_error_handler(ctx.z3_ctx, None);
+        }
+        let z3_slv = solver(&ctx, None, timeout);
+        Script {
+            ctx,
+            z3_slv,
+            timeout,
+            decls: Vec::new(),
+            scopes: Vec::new(),
+            z3_mdl: None,
+            pending: String::new(),
+            tee: None,
+        }
+    }
+
+    /// Also write everything that is evaluated into a file.
+    pub fn path_tee<P: AsRef<std::path::Path>>(&mut self, path: P) -> std::io::Result<()> {
+        self.tee = Some(std::fs::File::create(path)?);
+        Ok(())
+    }
+
+    /// Evaluate a command, together with everything written so far.
+    /// Returns `sat`, `unsat`, `timeout` or `unknown` for a `(check-sat)` and nothing
+    /// for the other commands, or the error Z3 reported.
+    pub fn eval(&mut self, cmd: &str) -> Result<String, String> {
+        let mut script = std::mem::take(&mut self.pending);
+        script.push_str(cmd);
+        script.push('\n');
+
+        if let Some(tee) = &mut self.tee {
+            tee.write_all(script.as_bytes()).map_err(|e| format!("{}", e))?;
+        }
+
+        // consecutive assertions are parsed at once, because every parse is given all declarations
+        let mut out = String::new();
+        let mut asserts = String::new();
+        for command in commands(&script) {
+            if head(command) == "assert" {
+                asserts.push_str(command);
+                asserts.push('\n');
+                continue;
+            }
+            if !asserts.is_empty() {
+                self.assert(&std::mem::take(&mut asserts))?;
+            }
+            out = self.command(command)?;
+        }
+        if !asserts.is_empty() {
+            self.assert(&asserts)?;
+        }
+        Ok(out)
+    }
+
+    /// The value of an expression in the model of the last satisfiable `(check-sat)`,
+    /// printed the same way `(get-value)` prints it.
+    pub fn value(&mut self, expr: &str) -> Result<String, String> {
+        self.eval("")?;
+        let z3_mdl = self.z3_mdl.ok_or_else(|| "(error \"no model available\")".to_string())?;
+
+        // the parser only returns assertions
+        let v = self.parse(&format!("(assert (= {} {}))", expr, expr))?;
+        let ctx = self.ctx.z3_ctx;
+        unsafe {
+            let r = if Z3_ast_vector_size(ctx, v) != 1 {
+                Err(format!("(error \"not an expression: {}\")", expr))
+            } else {
+                let eq = Z3_to_app(ctx, Z3_ast_vector_get(ctx, v, 0));
+                let ast = Z3_get_app_arg(ctx, eq, 0);
+                let mut value: Z3_ast = ast;
+                if Z3_model_eval(ctx, z3_mdl, ast, true, &mut value) {
+                    Z3_inc_ref(ctx, value);
+                    let s = CStr::from_ptr(Z3_ast_to_string(ctx, value)).to_string_lossy().into_owned();
+                    Z3_dec_ref(ctx, value);
+                    Ok(s)
+                } else {
+                    Err(format!("(error \"cannot evaluate {}\")", expr))
+                }
+            };
+            Z3_ast_vector_dec_ref(ctx, v);
+            r
+        }
+    }
+
+    fn command(&mut self, command: &str) -> Result<String, String> {
+        let ctx = self.ctx.z3_ctx;
+        match head(command) {
+            "declare-fun" => {
+                let decl = self.declare(command)?;
+                self.decls.push(decl);
+            }
+            "push" => {
+                for _ in 0..scopes(command)? {
+                    unsafe { Z3_solver_push(ctx, self.z3_slv) };
+                    self.scopes.push(self.decls.len());
+                }
+            }
+            "pop" => {
+                let n = scopes(command)?;
+                if n as usize > self.scopes.len() {
+                    return Err(format!("(error \"pop {} with only {} scopes\")", n, self.scopes.len()));
+                }
+                unsafe { Z3_solver_pop(ctx, self.z3_slv, n) };
+                for _ in 0..n {
+                    let len = self.scopes.pop().unwrap();
+                    for (_, decl) in self.decls.drain(len..) {
+                        unsafe { Z3_dec_ref(ctx, Z3_func_decl_to_ast(ctx, decl)) };
+                    }
+                }
+            }
+            "check-sat" => return Ok(self.check_sat()),
+            // like the smtlib2 frontend, a logic gets its own solver. for QF_UFBV that
+            // bit-blasts, which is a lot faster than the general solver
+            "set-logic" => {
+                if !self.scopes.is_empty() {
+                    return Err("(error \"set-logic after push\")".to_string());
+                }
+                let logic = match Sexp::parse(command) {
+                    Some(Sexp::List(parts)) => match parts.get(1) {
+                        Some(Sexp::Atom(logic)) => logic.to_string(),
+                        _ => return Err(format!("(error \"invalid {}\")", command)),
+                    },
+                    _ => return Err(format!("(error \"invalid {}\")", command)),
+                };
+                let z3_slv = solver(&self.ctx, Some(&logic), self.timeout);
+                unsafe { Z3_solver_dec_ref(ctx, self.z3_slv) };
+                self.z3_slv = z3_slv;
+            }
+            "set-option" | "set-info" => (),
+            other => return Err(format!("(error \"unsupported command {}\")", other)),
+        }
+        Ok(String::new())
+    }
+
+    fn check_sat(&mut self) -> String {
+        let ctx = self.ctx.z3_ctx;
+        if let Some(z3_mdl) = self.z3_mdl.take() {
+            unsafe { Z3_model_dec_ref(ctx, z3_mdl) };
+        }
+        unsafe {
+            match Z3_solver_check(ctx, self.z3_slv) {
+                Z3_L_TRUE => {
+                    let z3_mdl = Z3_solver_get_model(ctx, self.z3_slv);
+                    Z3_model_inc_ref(ctx, z3_mdl);
+                    self.z3_mdl = Some(z3_mdl);
+                    "sat".to_string()
+                }
+                Z3_L_FALSE => "unsat".to_string(),
+                _ => {
+                    let reason = CStr::from_ptr(Z3_solver_get_reason_unknown(ctx, self.z3_slv)).to_string_lossy();
+                    if reason.contains("timeout") || reason.contains("canceled") {
+                        "timeout".to_string()
+                    } else {
+                        "unknown".to_string()
+                    }
+                }
+            }
+        }
+    }
+
+    fn assert(&mut self, asserts: &str) -> Result<(), String> {
+        let v = self.parse(asserts)?;
+        let ctx = self.ctx.z3_ctx;
+        unsafe {
+            for i in 0..Z3_ast_vector_size(ctx, v) {
+                Z3_solver_assert(ctx, self.z3_slv, Z3_ast_vector_get(ctx, v, i));
+            }
+            Z3_ast_vector_dec_ref(ctx, v);
+        }
+        Ok(())
+    }
+
+    // the assertions in some smtlib, which can use everything declared so far
+    fn parse(&self, text: &str) -> Result<Z3_ast_vector, String> {
+        let ctx = self.ctx.z3_ctx;
+        let names: Vec<Z3_symbol> = self.decls.iter().map(|(name, _)| *name).collect();
+        let decls: Vec<Z3_func_decl> = self.decls.iter().map(|(_, decl)| *decl).collect();
+        let text = CString::new(text).map_err(|e| format!("{}", e))?;
+        unsafe {
+            // z3 4.8 returns a vector of the assertions, z3-sys still has the older signature
+            let v = Z3_parse_smtlib2_string(
+                ctx,
+                text.as_ptr(),
+                0,
+                std::ptr::null(),
+                std::ptr::null(),
+                decls.len() as u32,
+                names.as_ptr(),
+                decls.as_ptr(),
+            ) as Z3_ast_vector;
+            self.error()?;
+            Z3_ast_vector_inc_ref(ctx, v);
+            Ok(v)
+        }
+    }
+
+    // (declare-fun name (sort*) sort)
+    fn declare(&self, command: &str) -> Result<(Z3_symbol, Z3_func_decl), String> {
+        let invalid = || format!("(error \"invalid declaration {}\")", command);
+        let parts = match Sexp::parse(command).ok_or_else(invalid)? {
+            Sexp::List(parts) if parts.len() == 4 => parts,
+            _ => return Err(invalid()),
+        };
+        let name = match &parts[1] {
+            Sexp::Atom(name) => name.trim_matches('|'),
+            _ => return Err(invalid()),
+        };
+        let domain = match &parts[2] {
+            Sexp::List(args) => args.iter().map(|arg| self.sort(arg)).collect::<Result<Vec<_>, _>>()?,
+            _ => return Err(invalid()),
+        };
+        let range = self.sort(&parts[3])?;
+
+        let ctx = self.ctx.z3_ctx;
+        let name = CString::new(name).map_err(|e| format!("{}", e))?;
+        unsafe {
+            let name = Z3_mk_string_symbol(ctx, name.as_ptr());
+            let decl = Z3_mk_func_decl(ctx, name, domain.len() as u32, domain.as_ptr(), range);
+            self.error()?;
+            Z3_inc_ref(ctx, Z3_func_decl_to_ast(ctx, decl));
+            Ok((name, decl))
+        }
+    }
+
+    // the sorts zz uses
+    fn sort(&self, sexp: &Sexp) -> Result<Z3_sort, String> {
+        let ctx = self.ctx.z3_ctx;
+        let int = |v: &Sexp| match v {
+            Sexp::Atom(v) => v.parse::<u32>().ok(),
+            _ => None,
+        };
+        let sort = match sexp {
+            Sexp::Atom("Bool") => Some(unsafe { Z3_mk_bool_sort(ctx) }),
+            Sexp::List(parts) => match parts.as_slice() {
+                [Sexp::Atom("_"), Sexp::Atom("BitVec"), size] => {
+                    int(size).map(|size| unsafe { Z3_mk_bv_sort(ctx, size) })
+                }
+                [Sexp::Atom("_"), Sexp::Atom("FloatingPoint"), e, s] => match (int(e), int(s)) {
+                    (Some(e), Some(s)) => Some(unsafe { Z3_mk_fpa_sort(ctx, e, s) }),
+                    _ => None,
+                },
+                _ => None,
+            },
+            _ => None,
+        };
+        sort.ok_or_else(|| format!("(error \"unsupported sort {:?}\")", sexp))
+    }
+
+    fn error(&self) -> Result<(), String> {
+        let ctx = self.ctx.z3_ctx;
+        unsafe {
+            let code = Z3_get_error_code(ctx);
+            if code == ErrorCode::OK {
+                return Ok(());
+            }
+            let msg = CStr::from_ptr(Z3_get_error_msg(ctx, code)).to_string_lossy();
+            Err(format!("(error {:?})", msg))
+        }
+    }
+}
+
+impl Drop for Script {
+    fn drop(&mut self) {
+        let ctx = self.ctx.z3_ctx;
+        unsafe {
+            if let Some(z3_mdl) = self.z3_mdl.take() {
+                Z3_model_dec_ref(ctx, z3_mdl);
+            }
+            for (_, decl) in self.decls.drain(..) {
+                Z3_dec_ref(ctx, Z3_func_decl_to_ast(ctx, decl));
+            }
+            Z3_solver_dec_ref(ctx, self.z3_slv);
+        }
+    }
+}
+
+impl Write for Script {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.pending.push_str(&String::from_utf8_lossy(buf));
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+// the top level commands in a script, without comments
+fn commands(script: &str) -> Vec<&str> {
+    let mut r = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    let mut chars = script.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            ';' => {
+                for (_, c) in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '|' | '"' => {
+                for (_, c2) in chars.by_ref() {
+                    if c2 == c {
+                        break;
+                    }
+                }
+            }
+            '(' => {
+                if depth == 0 {
+                    start = i;
+                }
+                depth += 1;
+            }
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    r.push(&script[start..=i]);
+                }
+            }
+            _ => (),
+        }
+    }
+    r
+}
+
+// a solver for the logic, or one that picks the logic itself
+fn solver(ctx: &Context, logic: Option<&str>, timeout: u64) -> Z3_solver {
+    unsafe {
+        let slv = match logic {
+            Some(logic) => {
+                let logic = CString::new(logic).unwrap();
+                Z3_mk_solver_for_logic(ctx.z3_ctx, Z3_mk_string_symbol(ctx.z3_ctx, logic.as_ptr()))
+            }
+            None => Z3_mk_solver(ctx.z3_ctx),
+        };
+        Z3_solver_inc_ref(ctx.z3_ctx, slv);
+
+        let params = Z3_mk_params(ctx.z3_ctx);
+        Z3_params_inc_ref(ctx.z3_ctx, params);
+        let key = CString::new("timeout").unwrap();
+        let key = Z3_mk_string_symbol(ctx.z3_ctx, key.as_ptr());
+        Z3_params_set_uint(ctx.z3_ctx, params, key, timeout.min(u32::MAX as u64) as u32);
+        Z3_solver_set_params(ctx.z3_ctx, slv, params);
+        Z3_params_dec_ref(ctx.z3_ctx, params);
+        slv
+    }
+}
+
+fn head(command: &str) -> &str {
+    command
+        .trim_start_matches('(')
+        .trim_start()
+        .split(|c: char| c.is_whitespace() || c == ')' || c == '(')
+        .next()
+        .unwrap_or("")
+}
+
+// (push n) or (pop n), where n defaults to 1
+fn scopes(command: &str) -> Result<u32, String> {
+    match Sexp::parse(command) {
+        Some(Sexp::List(parts)) => match parts.get(1) {
+            None => Ok(1),
+            Some(Sexp::Atom(n)) => n.parse().map_err(|_| format!("(error \"invalid {}\")", command)),
+            Some(_) => Err(format!("(error \"invalid {}\")", command)),
+        },
+        _ => Err(format!("(error \"invalid {}\")", command)),
+    }
+}
+
+#[derive(Debug)]
+enum Sexp<'a> {
+    Atom(&'a str),
+    List(Vec<Sexp<'a>>),
+}
+
+impl<'a> Sexp<'a> {
+    fn parse(text: &'a str) -> Option<Sexp<'a>> {
+        let (sexp, rest) = Sexp::next(text)?;
+        if rest.trim().is_empty() {
+            Some(sexp)
+        } else {
+            None
+        }
+    }
+
+    fn next(text: &'a str) -> Option<(Sexp<'a>, &'a str)> {
+        let text = text.trim_start();
+        if let Some(mut rest) = text.strip_prefix('(') {
+            let mut parts = Vec::new();
+            loop {
+                rest = rest.trim_start();
+                if let Some(rest) = rest.strip_prefix(')') {
+                    return Some((Sexp::List(parts), rest));
+                }
+                let (part, r) = Sexp::next(rest)?;
+                parts.push(part);
+                rest = r;
+            }
+        }
+        let end = text
+            .find(|c: char| c.is_whitespace() || c == '(' || c == ')')
+            .unwrap_or(text.len());
+        if end == 0 {
+            return None;
+        }
+        Some((Sexp::Atom(&text[..end]), &text[end..]))
+    }
+}
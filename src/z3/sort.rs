@@ -60,7 +60,7 @@ impl<'ctx> Sort<'ctx> {
     ///
     /// # Examples
     /// ```
-    /// # use z3::{Config, Context, SatResult, Solver, Sort, Symbol};
+    /// # use zz::z3::{Config, Context, SatResult, Solver, Sort, Symbol};
     /// # let cfg = Config::new();
     /// # let ctx = Context::new(&cfg);
     /// # let solver = Solver::new(&ctx);
@@ -81,7 +81,7 @@ impl<'ctx> Sort<'ctx> {
     /// assert_eq!(solver.check(), SatResult::Sat);
     /// let model = solver.get_model();
     ///
-    /// assert!(model.eval(&eq).unwrap().as_bool().unwrap().as_bool().unwrap());
+    /// assert!(model.eval(&eq, true).unwrap().as_bool().unwrap().as_bool().unwrap());
     /// ```
     pub fn enumeration(
         ctx: &'ctx Context,
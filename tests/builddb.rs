@@ -0,0 +1,60 @@
+// outputs are rebuilt when what they were built from changes, not when their mtime does
+
+use std::path::PathBuf;
+use zz::builddb;
+
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("zz-builddb-{}", std::process::id())).join(name);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn fresh_after_record() {
+    let dir = scratch("db");
+    std::env::set_var("ZZ_TARGET_DIR", &dir);
+    let output = dir.join("a.o");
+    let source = dir.join("a.c");
+    std::fs::write(&source, "int a;").unwrap();
+    let key = builddb::Hasher::new().file(&source).str("-O2").finish();
+
+    // nothing recorded, and then nothing to be fresh
+    assert!(!builddb::fresh(&output, &key));
+    builddb::record(&output, key.clone());
+    assert!(!builddb::fresh(&output, &key));
+
+    std::fs::write(&output, "").unwrap();
+    assert!(builddb::fresh(&output, &key));
+
+    // a touch without a change is still fresh, a change isn't
+    std::fs::write(&source, "int a;").unwrap();
+    assert!(builddb::fresh(&output, &builddb::Hasher::new().file(&source).str("-O2").finish()));
+    std::fs::write(&source, "int b;").unwrap();
+    assert!(!builddb::fresh(&output, &builddb::Hasher::new().file(&source).str("-O2").finish()));
+    assert!(!builddb::fresh(&output, &builddb::Hasher::new().file(&source).str("-O0").finish()));
+
+    // recorded in memory until the build is done
+    let db = dir.join("target").join("builddb");
+    assert!(!db.exists());
+    builddb::flush();
+    assert!(db.exists());
+
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn write_if_changed() {
+    let path = scratch("write").join("a.h");
+
+    assert!(builddb::write_if_changed(&path, b"int a;"));
+    let written = std::fs::metadata(&path).unwrap().modified().unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(20));
+
+    assert!(!builddb::write_if_changed(&path, b"int a;"));
+    assert_eq!(std::fs::metadata(&path).unwrap().modified().unwrap(), written);
+
+    assert!(builddb::write_if_changed(&path, b"int b;"));
+    assert_eq!(std::fs::read(&path).unwrap(), b"int b;");
+
+    std::fs::remove_dir_all(path.parent().unwrap()).ok();
+}
@@ -6,11 +6,13 @@ cargo build --release
 
 
 
+# a test can put extra arguments like --variant into an args file
 for i in $THIS/mustpass/*
 do
     cd $i
+    args=$(cat args 2>/dev/null || true)
     ../../../target/release/zz clean;
-    if ../../../target/release/zz run;  then
+    if ../../../target/release/zz run $args;  then
         echo "$i" passed
     else
         echo "$i" failed
@@ -22,12 +24,19 @@ done
 for i in $THIS/mustfail/*
 do
     cd $i
+    args=$(cat args 2>/dev/null || true)
     ../../../target/release/zz clean;
-    if ../../../target/release/zz check;  then
+    if out=$(../../../target/release/zz check $args);  then
         echo "$i" passed, but it should not
         exit 1
+    fi
+    # zz check prints one json line per diagnostic, the expected code must be one of them
+    code=$(cat code)
+    if echo "$out" | grep -q "{\"code\":\"$code\""; then
+        echo "$i" "failed to build with $code, as it's supposed to"
     else
-        echo "$i" "failed to build, as it's supposed to"
+        echo "$i" "failed to build, but not with $code"
+        exit 1
     fi
 done
 
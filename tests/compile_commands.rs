@@ -0,0 +1,64 @@
+// every build lists how each c file is compiled, and forgets files that were deleted.
+// a cobject is compiled from its copy in target/c, which stays behind
+
+use serde_json::Value;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::Command;
+
+// proves every assert and finds every sanity check satisfiable
+const FAKE: &str = "#!/bin/sh\nneg=0\nwhile read -r line; do\n  case \"$line\" in\n    *\"(or (not\"*) neg=1;;\n  esac\n  case \"$line\" in\n    *check-sat*) if [ $neg = 1 ]; then echo unsat; else echo sat; fi; neg=0;;\n  esac\ndone\n";
+
+fn build(dir: &Path) -> Vec<Value> {
+    let out = Command::new(env!("CARGO_BIN_EXE_zz"))
+        .arg("build")
+        .current_dir(dir)
+        .env("PATH", format!("{}:/usr/bin:/bin", dir.join("bin").to_string_lossy()))
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stdout));
+    let p = dir.join("target").join("test").join("compile_commands.json");
+    match serde_json::from_slice(&std::fs::read(p).unwrap()).unwrap() {
+        Value::Array(entries) => entries,
+        _ => panic!("compile_commands.json is not a list"),
+    }
+}
+
+fn compiles(entries: &[Value], file: &str) -> bool {
+    entries.iter().any(|e| e["file"].as_str().unwrap().contains(file))
+}
+
+#[test]
+fn deleted_files_dropped() {
+    let dir = std::env::temp_dir().join(format!("zz-compile-commands-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::create_dir_all(dir.join("bin")).unwrap();
+    for solver in &["yices-smt2", "z3"] {
+        let path = dir.join("bin").join(solver);
+        std::fs::write(&path, FAKE).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    std::fs::write(
+        dir.join("zz.toml"),
+        "[project]\nversion = \"0.1.0\"\nname = \"commands\"\n\n[dependencies]\n\n[variants]\ndefault = []\n",
+    )
+    .unwrap();
+    std::fs::write(dir.join("src").join("main.zz"), "export fn main() -> int {\n    return 0;\n}\n").unwrap();
+    std::fs::write(dir.join("src").join("extra.c"), "int extra() { return 1; }\n").unwrap();
+
+    let entries = build(&dir);
+    assert!(compiles(&entries, "main"));
+    assert!(compiles(&entries, "extra"));
+    for entry in &entries {
+        let command = entry["command"].as_str().unwrap();
+        assert!(command.contains(" -c "), "{}", command);
+        assert!(command.ends_with(entry["output"].as_str().unwrap()), "{}", command);
+    }
+
+    std::fs::remove_file(dir.join("src").join("extra.c")).unwrap();
+    let entries = build(&dir);
+    assert!(compiles(&entries, "main"));
+    assert!(!compiles(&entries, "extra"));
+
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,76 @@
+// a failing proof leaves a test behind that calls the function with the arguments it fails with
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+fn zz(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_zz"))
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap()
+}
+
+fn codes(out: &Output) -> String {
+    String::from_utf8_lossy(&out.stdout).to_string()
+}
+
+#[test]
+fn counterexample_reproduces() {
+    if zz::smt::SolverKind::default().is_err() {
+        eprintln!("skipped, no solver installed");
+        return;
+    }
+    let dir = std::env::temp_dir().join(format!("zz-cex-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::write(
+        dir.join("zz.toml"),
+        "[project]\nversion = \"0.1.0\"\nname = \"cex\"\n\n[dependencies]\n\n[variants]\ndefault = []\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("src").join("main.zz"),
+        "using table;\n\nexport fn main() -> int {\n    return (int)table::at(2);\n}\n",
+    )
+    .unwrap();
+    let table = dir.join("src").join("table.zz");
+    std::fs::write(
+        &table,
+        "export fn at(usize i) -> u8\n{\n    u8 a[4] = {1, 2, 3, 4};\n    return a[i];\n}\n",
+    )
+    .unwrap();
+
+    let out = zz(&dir, &["check", "--emit-counterexamples"]);
+    assert!(!out.status.success());
+    assert!(codes(&out).contains("{\"code\":\"E0403\""));
+
+    let cex = std::fs::read_to_string(dir.join("target").join("cex").join("cex_at.zz")).unwrap();
+    assert!(cex.contains("using cex::table;"));
+    let call = cex.lines().find(|l| l.contains("table::at(")).unwrap();
+    let i: u64 = call.trim().trim_start_matches("table::at(").trim_end_matches(");").parse().unwrap();
+    assert!(i >= 4);
+
+    // at() now requires what it needs, and the test is the call that breaks it
+    std::fs::create_dir_all(dir.join("tests")).unwrap();
+    std::fs::write(dir.join("tests").join("cex_at.zz"), &cex).unwrap();
+    std::fs::write(
+        &table,
+        "export fn at(usize i) -> u8\n    where i < 4\n{\n    u8 a[4] = {1, 2, 3, 4};\n    return a[i];\n}\n",
+    )
+    .unwrap();
+    let out = zz(&dir, &["check"]);
+    assert!(!out.status.success());
+    let out = codes(&out);
+    let rejected = out.lines().find(|l| l.contains("{\"code\":\"E0501\"")).unwrap();
+    assert!(rejected.contains("cex_at.zz"));
+
+    // at() now handles it, and the test runs
+    std::fs::write(
+        &table,
+        "export fn at(usize i) -> u8\n{\n    u8 a[4] = {1, 2, 3, 4};\n    if i >= 4 {\n        return 0;\n    }\n    return a[i];\n}\n",
+    )
+    .unwrap();
+    assert!(zz(&dir, &["test"]).status.success());
+
+    std::fs::remove_dir_all(&dir).ok();
+}
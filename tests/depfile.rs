@@ -0,0 +1,57 @@
+// the headers the c compiler lists in its depfile decide when an object is built again
+
+use std::path::PathBuf;
+
+fn read(name: &str, content: &str) -> Vec<PathBuf> {
+    let dir = std::env::temp_dir().join(format!("zz-depfile-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    std::fs::write(&path, content).unwrap();
+    let deps = zz::make::read_depfile(&path);
+    std::fs::remove_file(&path).ok();
+    deps
+}
+
+fn paths(v: &[&str]) -> Vec<PathBuf> {
+    v.iter().map(PathBuf::from).collect()
+}
+
+#[test]
+fn escaped_spaces() {
+    assert_eq!(
+        read("spaces.d", "out.o: /src/my\\ project/a.c /src/b.h\n"),
+        paths(&["/src/my project/a.c", "/src/b.h"])
+    );
+}
+
+#[test]
+fn escaped_dollars() {
+    assert_eq!(read("dollars.d", "out.o: /src/$$HOME/a.c\n"), paths(&["/src/$HOME/a.c"]));
+}
+
+#[test]
+fn continuations() {
+    assert_eq!(
+        read("lf.d", "out.o: /src/a.c \\\n /src/b.h \\\n /src/c.h\n"),
+        paths(&["/src/a.c", "/src/b.h", "/src/c.h"])
+    );
+    assert_eq!(
+        read("crlf.d", "out.o: /src/a.c \\\r\n /src/b.h \\\r\n /src/c.h\r\n"),
+        paths(&["/src/a.c", "/src/b.h", "/src/c.h"])
+    );
+}
+
+#[test]
+fn drive_letters() {
+    assert_eq!(
+        read("drive.d", "C:\\build\\out.o: C:\\src\\a.c \\\r\n C:\\src\\my\\ b.h\r\n"),
+        paths(&["C:\\src\\a.c", "C:\\src\\my b.h"])
+    );
+}
+
+#[test]
+fn missing() {
+    assert!(read("empty.d", "").is_empty());
+    let dir = std::env::temp_dir().join(format!("zz-depfile-{}", std::process::id()));
+    assert!(zz::make::read_depfile(&dir.join("missing.d")).is_empty());
+}
@@ -0,0 +1,49 @@
+// every code the compiler emits is in the table, and none of them is retired
+
+use std::path::Path;
+
+#[test]
+fn emitted_codes_are_known() {
+    let src = Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+    let mut checked = 0;
+    for entry in std::fs::read_dir(&src).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().map(|v| v != "rs").unwrap_or(true) || path.ends_with("diagnostics.rs") {
+            continue;
+        }
+        let content = std::fs::read_to_string(&path).unwrap();
+        for (at, _) in content.match_indices('"') {
+            let code = match content.get(at + 1..at + 7) {
+                Some(v) => v,
+                None => continue,
+            };
+            let looks_like_code = code.ends_with('"')
+                && (code.starts_with('E') || code.starts_with('W'))
+                && code[1..5].chars().all(|c| c.is_ascii_digit());
+            if !looks_like_code {
+                continue;
+            }
+            let code = &code[..5];
+            assert!(
+                zz::diagnostics::template(code).is_some(),
+                "{:?} emits {}, which is not in diagnostics::CODES",
+                path,
+                code
+            );
+            assert!(zz::diagnostics::retired(code).is_none(), "{:?} emits retired code {}", path, code);
+            checked += 1;
+        }
+    }
+    assert!(checked > 100);
+}
+
+// zz explain-error shows a broken and a fixed example for every code it explains
+#[test]
+fn explanations_have_examples() {
+    for (code, text) in zz::diagnostics::EXPLANATIONS {
+        let fixed = text.find("\nFixed").unwrap_or_else(|| panic!("{} has no fixed example", code));
+        assert!(text[..fixed].contains("```"), "{} has no broken example", code);
+        assert!(text[fixed..].contains("```"), "{} has no fixed example", code);
+        assert!(zz::diagnostics::retired(code).is_none(), "{} is retired but explained", code);
+    }
+}
@@ -0,0 +1,71 @@
+// zz explain shows what is proven at a position, and marks a range it couldn't narrow down
+
+use serde_json::Value;
+use std::path::Path;
+use std::process::Command;
+
+const MAIN: &str = "export fn first(u8 n, u64 m) -> u8
+    where n >= 1 && n <= 10
+    where m >= 3 && m <= 1000000
+{
+    if n < 5 {
+        return n;
+    }
+    return n;
+}
+
+export fn main() -> int {
+    first(2, 4);
+    return 0;
+}
+";
+
+fn explain(dir: &Path, position: &str) -> Value {
+    let out = Command::new(env!("CARGO_BIN_EXE_zz"))
+        .args(&["explain", "--json", position])
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&out.stdout).to_string();
+    assert!(out.status.success(), "{}", stdout);
+    // after the progress bar
+    let at = stdout.find("{\"file\"").unwrap_or_else(|| panic!("nothing explained: {}", stdout));
+    serde_json::from_str(stdout[at..].lines().next().unwrap()).unwrap()
+}
+
+fn facts<'a>(explained: &'a Value, name: &str) -> Vec<&'a str> {
+    let names = explained["names"].as_array().unwrap();
+    let found = names.iter().find(|v| v["name"] == name).unwrap_or_else(|| panic!("{} not in scope", name));
+    found["facts"].as_array().unwrap().iter().map(|v| v.as_str().unwrap()).collect()
+}
+
+#[test]
+fn explained() {
+    if zz::smt::SolverKind::default().is_err() {
+        eprintln!("skipped, no solver installed");
+        return;
+    }
+    let dir = std::env::temp_dir().join(format!("zz-explain-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::write(
+        dir.join("zz.toml"),
+        "[project]\nversion = \"0.1.0\"\nname = \"explain\"\n\n[dependencies]\n\n[variants]\ndefault = []\n",
+    )
+    .unwrap();
+    std::fs::write(dir.join("src").join("main.zz"), MAIN).unwrap();
+
+    // what the branch proved, and what the other one did
+    let inside = explain(&dir, "src/main.zz:6:9");
+    assert_eq!(inside["function"], "explain::main::first");
+    assert_eq!(inside["line"], 6);
+    assert_eq!(facts(&inside, "n"), vec!["1 <= n <= 4"]);
+    assert_eq!(facts(&explain(&dir, "src/main.zz:8:5"), "n"), vec!["5 <= n <= 10"]);
+
+    // 20 steps don't find the upper end of a u64
+    let m = facts(&inside, "m");
+    assert_eq!(m.len(), 1);
+    assert!(m[0].starts_with("m >= 3"));
+    assert!(m[0].ends_with(" (approximate)"));
+
+    std::fs::remove_dir_all(&dir).ok();
+}
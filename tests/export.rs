@@ -0,0 +1,141 @@
+// the exported build files compile with the flags and include paths zz would have used, and link the same
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::Once;
+
+// proves every assert and finds every sanity check satisfiable
+const FAKE: &str = "#!/bin/sh\nneg=0\nwhile read -r line; do\n  case \"$line\" in\n    *\"(or (not\"*) neg=1;;\n  esac\n  case \"$line\" in\n    *check-sat*) if [ $neg = 1 ]; then echo unsat; else echo sat; fi; neg=0;;\n  esac\ndone\n";
+
+const TOML: &str = "[project]
+version = \"0.1.0\"
+name = \"exported\"
+cincludes = [\"inc\"]
+cflags = [\"-DEXPORTED=1\"]
+
+[dependencies]
+
+[variants.default]
+lflags = [\"-lm\"]
+
+[[artifacts]]
+name = \"exported-ninja\"
+main = \"exported::main\"
+type = \"ninja\"
+
+[[artifacts]]
+name = \"exported-cmake\"
+main = \"exported::main\"
+type = \"cmake\"
+
+[[artifacts]]
+name = \"exported-meson\"
+main = \"exported::main\"
+type = \"meson\"
+";
+
+static EXPORT: Once = Once::new();
+
+// one project exported once for all of them
+fn exported() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("zz-export-{}", std::process::id()));
+    EXPORT.call_once(|| {
+        std::fs::create_dir_all(dir.join("bin")).unwrap();
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::create_dir_all(dir.join("inc")).unwrap();
+        for solver in &["yices-smt2", "z3"] {
+            let path = dir.join("bin").join(solver);
+            std::fs::write(&path, FAKE).unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        std::fs::write(dir.join("zz.toml"), TOML).unwrap();
+        std::fs::write(dir.join("src").join("main.zz"), "export fn main() -> int {\n    return 0;\n}\n").unwrap();
+        std::fs::write(dir.join("inc").join("extra.h"), "int extra();\n").unwrap();
+        std::fs::write(dir.join("src").join("extra.c"), "#include \"extra.h\"\nint extra() { return EXPORTED; }\n").unwrap();
+        std::fs::write(dir.join("src").join("more.cpp"), "extern \"C\" int more() { return EXPORTED; }\n").unwrap();
+
+        let out = Command::new(env!("CARGO_BIN_EXE_zz"))
+            .args(&["build", "--export"])
+            .current_dir(&dir)
+            .env("PATH", format!("{}:/usr/bin:/bin", dir.join("bin").to_string_lossy()))
+            .output()
+            .unwrap();
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stdout));
+    });
+    dir
+}
+
+fn read(path: PathBuf) -> String {
+    std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{:?}: {}", path, e))
+}
+
+// from the line that starts with this up to the closing paren
+fn block<'a>(f: &'a str, start: &str) -> &'a str {
+    let b = &f[f.find(start).unwrap_or_else(|| panic!("no {:?} in {}", start, f))..];
+    &b[..b.find("\n)").unwrap()]
+}
+
+#[test]
+fn ninja() {
+    let f = read(exported().join("target").join("ninja").join("exported_ninja").join("build.ninja"));
+
+    // the c++ file is compiled by the c++ compiler, everything with the project's flags and include paths
+    let builds: Vec<&str> = f.lines().filter(|l| l.starts_with("build obj/")).collect();
+    assert_eq!(builds.len(), 4, "{}", f);
+    assert!(builds.iter().any(|l| l.contains(": cxx ") && l.ends_with(".cpp")), "{}", f);
+    assert!(builds.iter().any(|l| l.contains(": cc ") && l.contains("extra")), "{}", f);
+    for line in f.lines().filter(|l| l.starts_with("  cflags = ")) {
+        assert!(line.contains(" -DEXPORTED=1 "), "{}", line);
+        assert!(line.contains("-I ../../../inc "), "{}", line);
+    }
+
+    // and then linked by it too
+    let cxx = f.lines().find(|l| l.starts_with("cxx = ")).unwrap();
+    assert!(f.contains(&format!("\nld = {}\n", &cxx["cxx = ".len()..])), "{}", f);
+    assert!(f.contains("\nlibs = -lm\n"), "{}", f);
+    assert!(f.lines().any(|l| l.starts_with("build exported_ninja: link obj/")), "{}", f);
+    assert!(f.contains("\ndefault libexported_ninja.a\n"), "{}", f);
+}
+
+#[test]
+fn cmake() {
+    let f = read(exported().join("target").join("cmake").join("exported_cmake").join("CMakeLists.txt"));
+
+    let sources = block(&f, "add_executable(\"exported_cmake\"\n");
+    assert_eq!(sources.lines().count(), 5, "{}", sources);
+    assert!(sources.lines().any(|l| l.ends_with(".cpp\"")), "{}", sources);
+    assert!(block(&f, "target_compile_options(\"exported_cmake\" PRIVATE\n").contains("\n    \"-DEXPORTED=1\""), "{}", f);
+
+    let includes = block(&f, "target_include_directories(\"exported_cmake\" PUBLIC\n");
+    assert!(includes.contains("\n    \"${CMAKE_CURRENT_LIST_DIR}/../../include\""), "{}", includes);
+    assert!(includes.contains("\n    \"${CMAKE_CURRENT_LIST_DIR}/../../../inc\""), "{}", includes);
+    assert!(block(&f, "target_link_libraries(\"exported_cmake\" PUBLIC\n").contains("\n    \"-lm\""), "{}", f);
+}
+
+#[test]
+fn meson() {
+    let sdir = exported().join("target").join("meson");
+    let pdir = sdir.join("exported_meson");
+    let f = read(pdir.join("meson.build"));
+
+    // sources compile with the options zz would have used, the c++ one with the c++ compiler
+    assert!(f.contains("project('exported_meson', ['c', 'cpp'], "), "{}", f);
+    let libraries: Vec<&str> = f.split("\n\n").filter(|b| b.contains(" = static_library(")).collect();
+    assert_eq!(libraries.len(), 3, "{}", f);
+    for library in &libraries {
+        assert!(library.contains("\n    '-DEXPORTED=1',\n"), "{}", library);
+        assert!(library.contains("\n  include_directories: exported_meson_includes,\n"), "{}", library);
+        assert_eq!(library.contains(".cpp',\n"), library.contains("\n  cpp_args: [\n"), "{}", library);
+    }
+
+    // the project's include path is copied in with its headers
+    assert!(block(&f, "exported_meson_includes = include_directories(\n").contains("\n  'project/inc',"), "{}", f);
+    assert_eq!(read(pdir.join("project").join("inc").join("extra.h")), "int extra();\n");
+
+    let dep = block(&f, "exported_meson_dep = declare_dependency(\n");
+    assert!(dep.contains("\n  link_with: [exported_meson_lib, exported_meson_1_lib, exported_meson_2_lib],\n"), "{}", dep);
+    assert!(dep.contains("\n    '-lm',\n"), "{}", dep);
+    assert!(f.contains("\nmeson.override_dependency('exported_meson', exported_meson_dep)\n"), "{}", f);
+    assert!(read(sdir.join("exported_meson.wrap")).contains("[provide]\nexported_meson = exported_meson_dep\n"));
+}
@@ -0,0 +1,65 @@
+// zz fmt keeps comments, formats its own output the same way again, and --check only reports
+
+use std::path::Path;
+use std::process::Command;
+
+const MESSY: &str = "// leading comment\nusing <stdio.h>::{printf};\n\n/// the answer\nexport fn   answer( int a )->int {\n    /* block\n       comment */\n  int   b = a+1; // trailing comment\n    return b;\n}\n";
+
+#[test]
+fn idempotent() {
+    let mustpass = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("mustpass");
+    let mut checked = 0;
+    for project in std::fs::read_dir(&mustpass).unwrap() {
+        let src = project.unwrap().path().join("src");
+        for entry in std::fs::read_dir(&src).into_iter().flatten() {
+            let path = entry.unwrap().path();
+            if path.extension().map(|e| e != "zz").unwrap_or(true) {
+                continue;
+            }
+            let (_, once) = zz::fmt::format_file(&path).unwrap_or_else(|e| panic!("{}", e));
+            let twice = zz::fmt::format(&once).ok().unwrap();
+            assert_eq!(once, twice, "formatting {:?} again changed it", path);
+            checked += 1;
+        }
+    }
+    assert!(checked > 50);
+
+    let once = zz::fmt::format(MESSY).ok().unwrap();
+    assert_ne!(once, MESSY);
+    assert_eq!(zz::fmt::format(&once).ok().unwrap(), once);
+}
+
+#[test]
+fn comments_kept() {
+    let formatted = zz::fmt::format(MESSY).ok().unwrap();
+    for comment in &["// leading comment", "/// the answer", "/* block\n       comment */", "// trailing comment"] {
+        assert!(formatted.contains(comment), "{:?} lost {:?}", formatted, comment);
+    }
+    // still on the line it trailed
+    assert!(formatted.lines().any(|l| l.contains("b = a + 1;") && l.ends_with("// trailing comment")));
+}
+
+#[test]
+fn check_only_reports() {
+    let dir = std::env::temp_dir().join(format!("zz-fmt-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("main.zz");
+    let check = || {
+        Command::new(env!("CARGO_BIN_EXE_zz"))
+            .args(&["fmt", "--check"])
+            .arg(&path)
+            .output()
+            .unwrap()
+    };
+
+    std::fs::write(&path, MESSY).unwrap();
+    let out = check();
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stdout).contains("main.zz"));
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), MESSY);
+
+    std::fs::write(&path, zz::fmt::format(MESSY).ok().unwrap()).unwrap();
+    assert!(check().status.success());
+
+    std::fs::remove_dir_all(&dir).ok();
+}
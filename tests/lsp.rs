@@ -0,0 +1,120 @@
+// the language server publishes what is wrong with an open document, and finds where names are defined
+
+use serde_json::{json, Value};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+// the server changes into the project it analyses
+static SERIAL: Mutex<()> = Mutex::new(());
+
+#[derive(Clone, Default)]
+struct Output(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn project(name: &str, main: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("zz-lsp-{}", std::process::id())).join(name);
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::write(
+        dir.join("zz.toml"),
+        "[project]\nversion = \"0.1.0\"\nname = \"lsp\"\n\n[dependencies]\n\n[variants]\ndefault = []\n",
+    )
+    .unwrap();
+    std::fs::write(dir.join("src").join("main.zz"), main).unwrap();
+    std::fs::canonicalize(dir).unwrap()
+}
+
+// runs the server over these messages and returns what it sent back
+fn serve(messages: &[Value]) -> Vec<Value> {
+    let mut input = Vec::new();
+    for msg in messages {
+        let body = msg.to_string();
+        write!(input, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+    }
+    let output = Output::default();
+    let code = zz::lsp::serve(
+        std::io::Cursor::new(input),
+        output.clone(),
+        PathBuf::from(env!("CARGO_BIN_EXE_zz")),
+    );
+    assert_eq!(code, 0);
+
+    let output = output.0.lock().unwrap().clone();
+    let mut output = &output[..];
+    let mut sent = Vec::new();
+    while let Some(at) = output.windows(4).position(|w| w == b"\r\n\r\n") {
+        let header = String::from_utf8_lossy(&output[..at]).to_string();
+        let len: usize = header.trim_start_matches("Content-Length:").trim().parse().unwrap();
+        let body = &output[at + 4..at + 4 + len];
+        sent.push(serde_json::from_slice(body).unwrap());
+        output = &output[at + 4 + len..];
+    }
+    sent
+}
+
+fn session(root: &PathBuf, file: &PathBuf, text: &str, requests: &[Value]) -> Vec<Value> {
+    let uri = format!("file://{}", file.to_string_lossy());
+    let mut messages = vec![
+        json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {"rootUri": format!("file://{}", root.to_string_lossy())}}),
+        json!({"jsonrpc": "2.0", "method": "textDocument/didOpen", "params": {"textDocument": {"uri": uri, "languageId": "zz", "version": 1, "text": text}}}),
+    ];
+    messages.extend(requests.iter().cloned());
+    messages.push(json!({"jsonrpc": "2.0", "id": 99, "method": "shutdown"}));
+    messages.push(json!({"jsonrpc": "2.0", "method": "exit"}));
+    serve(&messages)
+}
+
+#[test]
+fn diagnostics_published() {
+    let _serial = SERIAL.lock().unwrap();
+    let main = "export fn main() -> int {\n    return 0;\n}\n";
+    let root = project("diagnostics", main);
+    let file = root.join("src").join("main.zz");
+
+    // as the editor has it, not as it is saved
+    let sent = session(&root, &file, "export fn main() -> int {\n    return nosuch::answer();\n}\n", &[]);
+    let published = sent
+        .iter()
+        .find(|v| v["method"] == "textDocument/publishDiagnostics")
+        .expect("no diagnostics published");
+    assert_eq!(published["params"]["uri"], format!("file://{}", file.to_string_lossy()));
+    let diagnostic = &published["params"]["diagnostics"][0];
+    assert_eq!(diagnostic["code"], "E0202");
+    assert_eq!(diagnostic["severity"], 1);
+    assert_eq!(diagnostic["range"]["start"], json!({"line": 1, "character": 11}));
+
+    std::fs::remove_dir_all(&root).ok();
+}
+
+#[test]
+fn definition_found() {
+    let _serial = SERIAL.lock().unwrap();
+    let main = "fn answer() -> int {\n    return 42;\n}\n\nexport fn main() -> int {\n    return answer();\n}\n";
+    let root = project("definition", main);
+    let file = root.join("src").join("main.zz");
+    let uri = format!("file://{}", file.to_string_lossy());
+
+    let sent = session(
+        &root,
+        &file,
+        main,
+        &[json!({"jsonrpc": "2.0", "id": 2, "method": "textDocument/definition", "params": {
+            "textDocument": {"uri": uri},
+            "position": {"line": 5, "character": 12},
+        }})],
+    );
+    let found = sent.iter().find(|v| v["id"] == 2).expect("no response to the definition request");
+    assert_eq!(found["result"]["uri"], uri);
+    assert_eq!(found["result"]["range"]["start"]["line"], 0);
+    assert_eq!(found["result"]["range"]["start"]["character"], 3);
+
+    std::fs::remove_dir_all(&root).ok();
+}
@@ -0,0 +1,49 @@
+// every module zz ships and tests with must come back out of the parse cache unchanged
+
+use std::path::{Path, PathBuf};
+
+fn zz_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            // build output, not sources
+            if path.file_name().unwrap() == "target" {
+                continue;
+            }
+            // mustfail projects expecting an E01 code are not supposed to parse
+            if let Ok(code) = std::fs::read_to_string(path.join("code")) {
+                if code.trim().starts_with("E01") {
+                    continue;
+                }
+            }
+            zz_files(&path, files);
+        } else if path.extension().map(|v| v == "zz").unwrap_or(false) {
+            files.push(path);
+        }
+    }
+}
+
+#[test]
+fn parsecache_roundtrip() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut files = Vec::new();
+    zz_files(&root.join("modules"), &mut files);
+    zz_files(&root.join("tests"), &mut files);
+    assert!(!files.is_empty());
+
+    let stage = zz::make::Stage::test();
+    for path in files {
+        let module = zz::parser::parse(&path, &stage);
+        let encoded = rmp_serde::to_vec(&module).expect(&format!("cannot encode {:?}", path));
+        let decoded: zz::ast::Module =
+            rmp_serde::from_read_ref(&encoded).expect(&format!("cannot decode {:?}", path));
+
+        // json compares maps by key rather than by hash order
+        assert_eq!(
+            serde_json::to_value(&module).unwrap(),
+            serde_json::to_value(&decoded).unwrap(),
+            "{:?} changed in the parse cache",
+            path
+        );
+    }
+}
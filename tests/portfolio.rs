@@ -0,0 +1,86 @@
+// several solvers race on the same queries, and the winner is remembered for the next build
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use zz::portfolio::Portfolio;
+use zz::smt::{Solver, SolverKind, Type};
+
+// yices answers right away, cvc5 and bitwuzla never do
+fn solvers() -> PathBuf {
+    static SETUP: std::sync::Once = std::sync::Once::new();
+    let dir = std::env::temp_dir().join(format!("zz-portfolio-{}", std::process::id()));
+    SETUP.call_once(|| {
+        std::fs::create_dir_all(&dir).unwrap();
+        let fast = "#!/bin/sh\nwhile read line; do\n  case \"$line\" in\n    *check-sat*) echo unsat ;;\n  esac\ndone\n";
+        let hung = "#!/bin/sh\nwhile read line; do\n  case \"$line\" in\n    *check-sat*) exec /bin/sleep 60 ;;\n  esac\ndone\n";
+        for (name, script) in &[("yices-smt2", fast), ("cvc5", hung), ("bitwuzla", hung)] {
+            let path = dir.join(name);
+            std::fs::write(&path, script).unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        std::env::set_var("PATH", &dir);
+        std::env::set_var("ZZ_TARGET_DIR", &dir);
+    });
+    dir
+}
+
+fn kind(name: &str) -> &'static SolverKind {
+    SolverKind::require(name).ok().expect("the fake solvers are in PATH")
+}
+
+#[test]
+fn fastest_wins() {
+    let dir = solvers();
+    let record = dir.join("race").join("winner");
+    let mut race = Portfolio::new(
+        &[kind("cvc5"), kind("yices")],
+        &dir.join("race.smt2").to_string_lossy(),
+        record.clone(),
+        "fn a",
+        10000,
+    );
+    let started = Instant::now();
+    assert_eq!(race.check_sat().ok(), Some(false));
+    assert!(started.elapsed() < Duration::from_secs(5));
+
+    assert_eq!(std::fs::read_to_string(&record).unwrap(), "yices\nfn a\n");
+    assert_eq!(Portfolio::recorded(&record, "fn a").map(|k| k.name), Some("yices"));
+    // the function changed since
+    assert!(Portfolio::recorded(&record, "fn b").is_none());
+}
+
+#[test]
+fn hung_solvers_time_out() {
+    let dir = solvers();
+    let record = dir.join("hung").join("winner");
+    let mut race = Portfolio::new(
+        &[kind("cvc5"), kind("bitwuzla")],
+        &dir.join("hung.smt2").to_string_lossy(),
+        record.clone(),
+        "fn a",
+        100,
+    );
+    // the timeout plus the grace
+    let started = Instant::now();
+    assert!(race.check_sat().err().unwrap().kind().is_timeout());
+    assert!(started.elapsed() >= Duration::from_secs(2));
+    assert!(started.elapsed() < Duration::from_secs(20));
+    assert!(!record.exists());
+}
+
+#[test]
+fn recorded_winner_runs_alone() {
+    let dir = solvers();
+    let record = dir.join("target").join("portfolio").join("alone");
+    std::fs::create_dir_all(record.parent().unwrap()).unwrap();
+    std::fs::write(&record, "yices\nfn a\n").unwrap();
+
+    let mut solver = Solver::new("alone".to_string(), &[kind("cvc5"), kind("yices")], 10000, "fn a");
+    solver.declare(0, "a", Type::Bool);
+    assert!(solver.assert(vec![(0, 0)], |proven, _| proven));
+
+    // no race in the script, only yices was asked
+    let script = std::fs::read_to_string(dir.join("target").join("ssa").join("alone.smt2")).unwrap();
+    assert!(!script.contains("; portfolio of"));
+}
@@ -0,0 +1,153 @@
+// a proof is only taken from the cache if it was done for the same function, callees, solver and data model
+
+use std::collections::BTreeSet;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// proves every assert and finds every sanity check satisfiable
+const FAKE: &str = "#!/bin/sh\nneg=0\nwhile read -r line; do\n  case \"$line\" in\n    *\"(or (not\"*) neg=1;;\n  esac\n  case \"$line\" in\n    *check-sat*) if [ $neg = 1 ]; then echo unsat; else echo sat; fi; neg=0;;\n  esac\ndone\n";
+
+const HALF: &str = "fn half(u32 a) -> u32\n    where a > 1\n{\n    return a / 2;\n}\n";
+const SUM: &str = "fn sum(f32 a, f32 b) -> f32\n{\n    return a + b;\n}\n";
+const MAIN: &str = "export fn main() -> int {\n    half(4);\n    sum(1.0, 2.0);\n    return 0;\n}\n";
+
+struct Project {
+    dir: PathBuf,
+    path: String,
+}
+
+impl Project {
+    fn new(name: &str) -> Project {
+        let dir = std::env::temp_dir().join(format!("zz-proofcache-{}", std::process::id())).join(name);
+        let bin = dir.join("bin");
+        std::fs::create_dir_all(&bin).unwrap();
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        for solver in &["yices-smt2", "z3"] {
+            let path = bin.join(solver);
+            std::fs::write(&path, FAKE).unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        let project = Project {
+            path: format!("{}:/usr/bin:/bin", bin.to_string_lossy()),
+            dir,
+        };
+        project.toml("");
+        project.main(HALF, MAIN);
+        project
+    }
+
+    fn toml(&self, extra: &str) {
+        std::fs::write(
+            self.dir.join("zz.toml"),
+            format!(
+                "[project]\nversion = \"0.1.0\"\nname = \"proofcache\"\n\n[dependencies]\n\n[variants]\ndefault = []\n{}",
+                extra
+            ),
+        )
+        .unwrap();
+    }
+
+    fn main(&self, half: &str, main: &str) {
+        std::fs::write(self.dir.join("src").join("main.zz"), format!("{}\n{}\n{}", half, SUM, main)).unwrap();
+    }
+
+    // the warnings of a successful check
+    fn check(&self, args: &[&str]) -> String {
+        let out = Command::new(env!("CARGO_BIN_EXE_zz"))
+            .args(args)
+            .arg("check")
+            .current_dir(&self.dir)
+            .env("PATH", &self.path)
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8_lossy(&out.stdout).to_string();
+        assert!(out.status.success(), "{}", stdout);
+        stdout
+    }
+
+    fn proofs(&self) -> BTreeSet<String> {
+        proofs(&self.dir.join("target").join("proofcache"))
+    }
+
+    // how many proofs checking it again didn't find in the cache
+    fn misses(&self, args: &[&str]) -> usize {
+        let before = self.proofs();
+        self.check(args);
+        self.proofs().difference(&before).count()
+    }
+}
+
+impl Drop for Project {
+    fn drop(&mut self) {
+        std::fs::remove_dir_all(&self.dir).ok();
+    }
+}
+
+fn proofs(dir: &Path) -> BTreeSet<String> {
+    match std::fs::read_dir(dir) {
+        Ok(entries) => entries.map(|e| e.unwrap().file_name().to_string_lossy().to_string()).collect(),
+        Err(_) => BTreeSet::new(),
+    }
+}
+
+#[test]
+fn body_changed() {
+    let p = Project::new("body");
+    assert_eq!(p.misses(&[]), 3);
+    assert_eq!(p.misses(&[]), 0);
+
+    // only half itself, main only sees its contract
+    p.main(&HALF.replace("a / 2", "a / 4"), MAIN);
+    assert_eq!(p.misses(&[]), 1);
+}
+
+#[test]
+fn callee_contract_changed() {
+    let p = Project::new("contract");
+    assert_eq!(p.misses(&[]), 3);
+
+    // half and its caller main, but not sum
+    p.main(&HALF.replace("a > 1", "a > 2"), MAIN);
+    assert_eq!(p.misses(&[]), 2);
+}
+
+#[test]
+fn solver_changed() {
+    let p = Project::new("solver");
+    p.toml("\n[smt]\nsolver = \"yices\"\n");
+    assert_eq!(p.misses(&[]), 3);
+
+    p.toml("\n[smt]\nsolver = \"z3\"\n");
+    assert_eq!(p.misses(&[]), 3);
+
+    p.toml("\n[smt]\nsolver = \"yices\"\n");
+    assert_eq!(p.misses(&[]), 0);
+
+    // every function is proven with a timeout
+    assert_eq!(p.misses(&["--smt-timeout", "1000"]), 3);
+}
+
+#[test]
+fn data_model_changed() {
+    let p = Project::new("datamodel");
+    assert_eq!(p.misses(&[]), 3);
+    assert_eq!(p.misses(&["--target", "ilp32"]), 3);
+    assert_eq!(p.misses(&["--target", "lp64"]), 0);
+}
+
+#[test]
+fn warnings_replayed() {
+    let p = Project::new("warnings");
+    p.toml("\n[smt]\nsolver = \"yices\"\n");
+    assert!(p.check(&[]).contains("{\"code\":\"W0101\""));
+
+    // sum is cached, the module isn't
+    p.main(HALF, &MAIN.replace("half(4)", "half(8)"));
+    let before = p.proofs();
+    assert!(p.check(&[]).contains("{\"code\":\"W0101\""));
+    assert_eq!(p.proofs().difference(&before).count(), 1);
+
+    // and now the module is too
+    assert!(p.check(&[]).contains("{\"code\":\"W0101\""));
+}
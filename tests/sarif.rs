@@ -0,0 +1,76 @@
+// zz check --format sarif prints one sarif 2.1.0 log with a rule for every diagnostic code
+
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::process::{Command, Output};
+
+fn check(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_zz"))
+        .arg("check")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn sarif_log() {
+    let dir = std::env::temp_dir().join(format!("zz-sarif-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::write(
+        dir.join("zz.toml"),
+        "[project]\nversion = \"0.1.0\"\nname = \"sarif\"\n\n[dependencies]\n\n[variants]\ndefault = []\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("src").join("main.zz"),
+        "export fn main() -> int {\n    return nosuch::answer();\n}\n",
+    )
+    .unwrap();
+
+    // what zz check reports as json lines
+    let plain = check(&dir, &[]);
+    let codes: BTreeSet<String> = String::from_utf8_lossy(&plain.stdout)
+        .lines()
+        .filter_map(|l| l.find("{\"code\"").map(|at| l[at..].to_string()))
+        .map(|l| serde_json::from_str::<Value>(&l).unwrap()["code"].as_str().unwrap().to_string())
+        .collect();
+    assert!(codes.contains("E0202"));
+
+    // nothing but the log on stdout
+    let out = check(&dir, &["--format", "sarif"]);
+    assert_eq!(out.status.code(), plain.status.code());
+    let log: Value = serde_json::from_slice(&out.stdout).expect("stdout is not one json document");
+
+    assert_eq!(log["version"], "2.1.0");
+    assert!(log["$schema"].as_str().unwrap().ends_with("sarif-2.1.0.json"));
+    let run = &log["runs"][0];
+    assert_eq!(run["tool"]["driver"]["name"], "zz");
+
+    let rules: Vec<&str> = run["tool"]["driver"]["rules"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(rules.iter().map(|r| r.to_string()).collect::<BTreeSet<String>>(), codes);
+
+    let results = run["results"].as_array().unwrap();
+    assert!(!results.is_empty());
+    for result in results {
+        let id = result["ruleId"].as_str().unwrap();
+        assert_eq!(rules[result["ruleIndex"].as_u64().unwrap() as usize], id);
+        assert!(["error", "warning", "note", "none"].contains(&result["level"].as_str().unwrap()));
+        assert!(result["message"]["text"].is_string());
+    }
+
+    let unresolved = results.iter().find(|r| r["ruleId"] == "E0202").unwrap();
+    let location = &unresolved["locations"][0]["physicalLocation"];
+    assert_eq!(location["artifactLocation"]["uri"], "src/main.zz");
+    assert_eq!(location["artifactLocation"]["uriBaseId"], "%SRCROOT%");
+    assert_eq!(location["region"]["startLine"], 2);
+    assert_eq!(location["region"]["startColumn"], 12);
+
+    std::fs::remove_dir_all(&dir).ok();
+}
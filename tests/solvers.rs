@@ -0,0 +1,55 @@
+// solvers are picked by name, and one that fails on a query hasn't proven it
+
+use std::os::unix::fs::PermissionsExt;
+use zz::smt::{Solver, SolverKind, Type};
+
+#[test]
+fn unknown_solvers() {
+    assert_eq!(SolverKind::by_name("nosuch").err().unwrap().code(), "E0804");
+    assert_eq!(SolverKind::require("nosuch").err().unwrap().code(), "E0804");
+    assert_eq!(SolverKind::set_default("nosuch").err().unwrap().code(), "E0804");
+    assert_eq!(
+        SolverKind::set_portfolio(&["yices".to_string(), "nosuch".to_string()]).err().unwrap().code(),
+        "E0804"
+    );
+    assert!(SolverKind::set_portfolio(&["yices".to_string(), "cvc5".to_string()]).is_ok());
+    assert!(SolverKind::set_portfolio(&[]).is_ok());
+
+    for kind in zz::smt::SOLVERS {
+        assert_eq!(SolverKind::by_name(kind.name).ok().unwrap().name, kind.name);
+    }
+}
+
+#[test]
+fn failing_solver() {
+    let dir = std::env::temp_dir().join(format!("zz-solvers-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::env::set_var("ZZ_TARGET_DIR", &dir);
+
+    // a yices that doesn't understand anything it's asked to check
+    let yices = dir.join("yices-smt2");
+    std::fs::write(
+        &yices,
+        "#!/bin/sh\nwhile read line; do\n  case \"$line\" in\n    *check-sat*) echo '(error \"unsupported\")' ;;\n  esac\ndone\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&yices, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    std::env::set_var("PATH", &dir);
+
+    // nothing else is installed now
+    assert_eq!(SolverKind::require("bitwuzla").err().unwrap().code(), "E0805");
+
+    let kind = SolverKind::require("yices").ok().expect("the fake yices is in PATH");
+    let mut solver = Solver::new("failing".to_string(), &[kind], 0, "");
+    solver.declare(0, "a", Type::Bool);
+    let (proven, undecided, failure) =
+        solver.assert(vec![(0, 0)], |proven, _| (proven, solver.undecided(), solver.failure()));
+
+    std::env::set_var("PATH", path);
+    std::fs::remove_dir_all(&dir).ok();
+
+    assert!(!proven);
+    assert!(undecided);
+    assert!(failure.unwrap().contains("unsupported"));
+}